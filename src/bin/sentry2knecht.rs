@@ -1,13 +1,12 @@
 use clap::Parser;
 use knecht::{
-    add_task_with_fs, append_pain_entry_with_fs, FileSystem, PainEntry, PainSourceType,
-    RealFileSystem,
+    add_task_with_fs, append_pain_entry_with_fs, append_sentry_mapping_with_fs,
+    find_repo_root_with_fs, read_sentry_mappings_with_fs, FileSystem, PainEntry, PainSourceType,
+    RealFileSystem, SentryMapping,
 };
 use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::io::{BufRead, Write};
-use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Parser)]
@@ -79,14 +78,6 @@ struct SentryEntry {
     data: Value,
 }
 
-#[derive(Debug, Clone)]
-struct SentryMapping {
-    sentry_issue_id: String,
-    knecht_task_id: String,
-    last_sync_timestamp: u64,
-    last_event_count: u64,
-}
-
 #[derive(Debug)]
 enum SyncResult {
     Created { task_id: String, pain_count: u64 },
@@ -97,14 +88,23 @@ enum SyncResult {
 fn main() {
     let cli = Cli::parse();
 
-    // Verify .knecht directory exists
-    if !Path::new(".knecht").exists() {
-        eprintln!("Error: .knecht directory not found. Run 'knecht init' first.");
-        std::process::exit(1);
-    }
-
     let fs = RealFileSystem;
 
+    // Find the .knecht directory in this or a parent directory
+    let cwd = std::env::current_dir().unwrap_or_default();
+    match find_repo_root_with_fs(&cwd, &fs) {
+        Some(root) => {
+            if let Err(e) = std::env::set_current_dir(&root) {
+                eprintln!("Error: cannot change to '{}': {}", root.display(), e);
+                std::process::exit(1);
+            }
+        }
+        None => {
+            eprintln!("Error: .knecht directory not found. Run 'knecht init' first.");
+            std::process::exit(1);
+        }
+    }
+
     // Fetch issues from Sentry
     eprintln!("Fetching issues from Sentry...");
     let issues = match fetch_sentry_issues(&cli) {
@@ -274,41 +274,41 @@ fn format_description(issue: &SentryIssue, event: Option<&SentryEvent>) -> Strin
 
         // Exception/stacktrace from entries
         for entry in &event.entries {
-            if entry.entry_type == "exception" {
-                if let Some(values) = entry.data.get("values").and_then(|v| v.as_array()) {
-                    desc.push_str("\n## Exception\n\n");
-                    for exc in values {
-                        if let Some(exc_type) = exc.get("type").and_then(|v| v.as_str()) {
-                            desc.push_str(&format!("**Type:** {}\n", exc_type));
-                        }
-                        if let Some(exc_value) = exc.get("value").and_then(|v| v.as_str()) {
-                            desc.push_str(&format!("**Value:** {}\n", exc_value));
-                        }
+            if entry.entry_type == "exception"
+                && let Some(values) = entry.data.get("values").and_then(|v| v.as_array())
+            {
+                desc.push_str("\n## Exception\n\n");
+                for exc in values {
+                    if let Some(exc_type) = exc.get("type").and_then(|v| v.as_str()) {
+                        desc.push_str(&format!("**Type:** {}\n", exc_type));
+                    }
+                    if let Some(exc_value) = exc.get("value").and_then(|v| v.as_str()) {
+                        desc.push_str(&format!("**Value:** {}\n", exc_value));
+                    }
 
-                        // Stacktrace
-                        if let Some(stacktrace) = exc.get("stacktrace") {
-                            if let Some(frames) = stacktrace.get("frames").and_then(|v| v.as_array()) {
-                                desc.push_str("\n### Stacktrace\n\n```\n");
-                                // Show frames in reverse order (most recent first)
-                                for frame in frames.iter().rev().take(10) {
-                                    let filename = frame.get("filename")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or("?");
-                                    let function = frame.get("function")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or("?");
-                                    let lineno = frame.get("lineNo")
-                                        .and_then(|v| v.as_u64())
-                                        .map(|n| n.to_string())
-                                        .unwrap_or_else(|| "?".to_string());
-                                    desc.push_str(&format!(
-                                        "  {} in {} [Line {}]\n",
-                                        function, filename, lineno
-                                    ));
-                                }
-                                desc.push_str("```\n");
-                            }
+                    // Stacktrace
+                    if let Some(stacktrace) = exc.get("stacktrace")
+                        && let Some(frames) = stacktrace.get("frames").and_then(|v| v.as_array())
+                    {
+                        desc.push_str("\n### Stacktrace\n\n```\n");
+                        // Show frames in reverse order (most recent first)
+                        for frame in frames.iter().rev().take(10) {
+                            let filename = frame.get("filename")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("?");
+                            let function = frame.get("function")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("?");
+                            let lineno = frame.get("lineNo")
+                                .and_then(|v| v.as_u64())
+                                .map(|n| n.to_string())
+                                .unwrap_or_else(|| "?".to_string());
+                            desc.push_str(&format!(
+                                "  {} in {} [Line {}]\n",
+                                function, filename, lineno
+                            ));
                         }
+                        desc.push_str("```\n");
                     }
                 }
             }
@@ -319,54 +319,17 @@ fn format_description(issue: &SentryIssue, event: Option<&SentryEvent>) -> Strin
 }
 
 fn read_sentry_mappings(fs: &dyn FileSystem) -> Result<HashMap<String, SentryMapping>, String> {
-    let path = Path::new(".knecht/sentry-mapping");
-
-    if !fs.exists(path) {
-        return Ok(HashMap::new());
-    }
-
-    let reader = fs.open(path).map_err(|e| format!("Failed to open mapping file: {}", e))?;
-    let mut mappings = HashMap::new();
-
-    for line in reader.lines() {
-        let line = line.map_err(|e| format!("Failed to read line: {}", e))?;
-        if line.is_empty() || line.starts_with('#') {
-            continue;
-        }
-
-        let parts: Vec<&str> = line.split('|').collect();
-        if parts.len() >= 4 {
-            let mapping = SentryMapping {
-                sentry_issue_id: parts[0].to_string(),
-                knecht_task_id: parts[1].to_string(),
-                last_sync_timestamp: parts[2].parse().unwrap_or(0),
-                last_event_count: parts[3].parse().unwrap_or(0),
-            };
-            mappings.insert(mapping.sentry_issue_id.clone(), mapping);
-        }
-    }
-
-    Ok(mappings)
+    // Later lines supersede earlier ones for the same issue
+    let mappings = read_sentry_mappings_with_fs(fs)
+        .map_err(|e| format!("Failed to read mapping file: {}", e))?;
+    Ok(mappings.into_iter()
+        .map(|mapping| (mapping.sentry_issue_id.clone(), mapping))
+        .collect())
 }
 
 fn append_sentry_mapping(mapping: &SentryMapping, fs: &dyn FileSystem) -> Result<(), String> {
-    let path = Path::new(".knecht/sentry-mapping");
-
-    let mut writer = fs
-        .append(path)
-        .map_err(|e| format!("Failed to open mapping file: {}", e))?;
-
-    writeln!(
-        writer,
-        "{}|{}|{}|{}",
-        mapping.sentry_issue_id,
-        mapping.knecht_task_id,
-        mapping.last_sync_timestamp,
-        mapping.last_event_count
-    )
-    .map_err(|e| format!("Failed to write mapping: {}", e))?;
-
-    Ok(())
+    append_sentry_mapping_with_fs(mapping, fs)
+        .map_err(|e| format!("Failed to write mapping: {}", e))
 }
 
 fn sync_single_issue(
@@ -448,17 +411,19 @@ fn add_sentry_pain_entries(
         .unwrap()
         .as_secs();
 
-    for _ in 0..count {
-        let entry = PainEntry {
-            task_id: task_id.to_string(),
-            timestamp: now,
-            source_type: PainSourceType::Sentry,
-            source_id: Some(issue.short_id.clone()),
-            description: format!("Sentry event: {}", issue.title),
-        };
-        append_pain_entry_with_fs(&entry, fs)
-            .map_err(|e| format!("Failed to add pain entry: {}", e))?;
+    if count == 0 {
+        return Ok(());
     }
 
-    Ok(())
+    let entry = PainEntry {
+        task_id: task_id.to_string(),
+        timestamp: now,
+        source_type: PainSourceType::Sentry,
+        source_id: Some(issue.short_id.clone()),
+        description: format!("Sentry event: {}", issue.title),
+        during_task: None,
+        count: u32::try_from(count).unwrap_or(u32::MAX),
+    };
+    append_pain_entry_with_fs(&entry, fs)
+        .map_err(|e| format!("Failed to add pain entry: {}", e))
 }
@@ -0,0 +1,98 @@
+use serde::Serialize;
+
+use crate::{find_task_by_id_with_fs, write_task_with_fs, FileSystem, KnechtError, Task};
+
+/// One acceptance criterion: a non-empty line of `acceptance_criteria`, with any
+/// bullet (`-`, `*`, `+`, `1.`) and checkbox (`[ ]`, `[x]`) stripped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CriteriaItem {
+    pub text: String,
+    pub checked: bool,
+}
+
+/// A criteria line split into its indentation, bullet, checkbox state and text
+struct ItemLine<'a> {
+    indent: &'a str,
+    bullet: Option<&'a str>,
+    checked: bool,
+    text: &'a str,
+}
+
+fn split_item_line(line: &str) -> ItemLine<'_> {
+    let rest = line.trim_start();
+    let indent = &line[..line.len() - rest.len()];
+
+    let numbered = rest.find(['.', ')'])
+        .filter(|&pos| pos > 0 && rest[..pos].chars().all(|c| c.is_ascii_digit()) && rest[pos + 1..].starts_with(' '));
+    let (bullet, rest) = if rest.starts_with("- ") || rest.starts_with("* ") || rest.starts_with("+ ") {
+        (Some(&rest[..1]), rest[2..].trim_start())
+    } else if let Some(pos) = numbered {
+        (Some(&rest[..=pos]), rest[pos + 2..].trim_start())
+    } else {
+        (None, rest)
+    };
+
+    let (checked, text) = if let Some(text) = rest.strip_prefix("[ ]") {
+        (false, text.trim_start())
+    } else if let Some(text) = rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]")) {
+        (true, text.trim_start())
+    } else {
+        (false, rest)
+    };
+
+    ItemLine { indent, bullet, checked, text: text.trim_end() }
+}
+
+/// Split acceptance criteria into checklist items, one per non-empty line
+pub fn parse_criteria_items(criteria: &str) -> Vec<CriteriaItem> {
+    criteria.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(split_item_line)
+        .map(|item| CriteriaItem { text: item.text.to_string(), checked: item.checked })
+        .collect()
+}
+
+/// The task's criteria items, empty when it has no acceptance criteria
+pub fn criteria_items(task: &Task) -> Vec<CriteriaItem> {
+    task.acceptance_criteria.as_deref().map(parse_criteria_items).unwrap_or_default()
+}
+
+/// Set the checked state of items (1-based) by rewriting their lines as `- [x] text`.
+/// Bullets and numbering already present are kept.
+fn set_items_checked(criteria: &str, items: &[usize], checked: bool) -> String {
+    let mut number = 0;
+    let lines: Vec<String> = criteria.lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                return line.to_string();
+            }
+            number += 1;
+            if !items.contains(&number) {
+                return line.to_string();
+            }
+            let item = split_item_line(line);
+            format!("{}{} [{}] {}", item.indent, item.bullet.unwrap_or("-"), if checked { "x" } else { " " }, item.text)
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Check (or uncheck) acceptance criteria items of a task. `items` are 1-based; an empty
+/// slice means every item.
+pub fn check_criteria_items_with_fs(task_id: &str, items: &[usize], checked: bool, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
+    let mut task = find_task_by_id_with_fs(task_id, fs)?;
+    let count = criteria_items(&task).len();
+
+    if let Some(&item) = items.iter().find(|&&item| item == 0 || item > count) {
+        return Err(KnechtError::CriteriaItemNotFound { task_id: task_id.to_string(), item, count });
+    }
+
+    let all: Vec<usize> = (1..=count).collect();
+    let items = if items.is_empty() { &all[..] } else { items };
+    if let Some(criteria) = &task.acceptance_criteria {
+        task.acceptance_criteria = Some(set_items_checked(criteria, items, checked));
+    }
+
+    write_task_with_fs(&task, fs)?;
+    Ok(task)
+}
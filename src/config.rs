@@ -0,0 +1,361 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::priority::{parse_weight, strategy_for_name, unknown_strategy};
+use crate::serializer::{serializer_for_format, CsvSerializer, TaskSerializer};
+use crate::{FileSystem, KnechtError};
+
+/// Printed after `knecht done` unless `done_banner` is set or a post-done hook is installed
+pub const DEFAULT_DONE_BANNER: &str = "
+================================================================================
+REFLECTION REQUIRED
+================================================================================
+
+Run: /reflect
+
+This loads the reflection skill which will guide you through required questions
+about this work session. You MUST complete reflection before continuing.
+
+================================================================================
+";
+
+/// Settings `knecht config` knows about, with their defaults (empty means unset)
+pub const CONFIG_SETTINGS: &[(&str, &str)] = &[
+    ("format", "csv"),
+    ("required_fields", "acceptance_criteria"),
+    ("id_length", "6"),
+    ("done_banner", DEFAULT_DONE_BANNER),
+    ("list_statuses", ""),
+    ("list_footer", "true"),
+    ("claim_lease", ""),
+    ("require_independent_verifier", "false"),
+    ("wip_limit_per_agent", ""),
+    ("wip_limit", ""),
+    ("pain_half_life", ""),
+    ("pain_half_life.manual", ""),
+    ("pain_half_life.skip", ""),
+    ("pain_half_life.sentry", ""),
+    ("next_strategy", "pain"),
+    ("blend.pain", "1"),
+    ("blend.age", "1"),
+    ("blend.pain-per-estimate", "1"),
+    ("statuses", "open, claimed, delivered, done"),
+    ("active_statuses", "open"),
+    ("resolved_statuses", "done"),
+];
+
+/// Settings a user may override in their own config file. Everything else is repository
+/// policy or storage layout, which every clone must agree on.
+pub const USER_CONFIG_KEYS: &[&str] = &["done_banner", "list_statuses", "list_footer", "next_strategy"];
+
+/// Per-status settings, `status.<name>.glyph` and `transitions.<name>`, which are unset by default
+fn is_status_setting(key: &str) -> bool {
+    key.strip_prefix("status.").and_then(|rest| rest.strip_suffix(".glyph")).is_some_and(|status| !status.is_empty())
+        || key.strip_prefix("transitions.").is_some_and(|status| !status.is_empty())
+}
+
+/// Fields `required_fields` may name; the title is always required
+const OPTIONAL_FIELDS: &[&str] = &["description", "acceptance_criteria"];
+
+/// Where an effective setting comes from, for `knecht config get`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    Default,
+    Repo,
+    User,
+}
+
+/// The per-user config file: `$XDG_CONFIG_HOME/knecht/config`, else `~/.config/knecht/config`
+pub fn user_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").filter(|dir| !dir.is_empty()).map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("knecht").join("config"))
+}
+
+/// Read `.knecht/config` with the user's overrides of `USER_CONFIG_KEYS` applied on top; other
+/// keys in the user file are ignored
+pub fn read_config_with_fs(fs: &dyn FileSystem) -> Result<BTreeMap<String, String>, KnechtError> {
+    let mut values = read_repo_config_with_fs(fs)?;
+    values.extend(read_user_config_with_fs(fs)?);
+    Ok(values)
+}
+
+/// The user's overrides of `USER_CONFIG_KEYS`
+fn read_user_config_with_fs(fs: &dyn FileSystem) -> Result<BTreeMap<String, String>, KnechtError> {
+    let Some(path) = user_config_path() else {
+        return Ok(BTreeMap::new());
+    };
+    let mut values = read_config_file_with_fs(&path, fs)?;
+    values.retain(|key, _| USER_CONFIG_KEYS.contains(&key.as_str()));
+    Ok(values)
+}
+
+/// Read `.knecht/config` alone, without user overrides
+pub fn read_repo_config_with_fs(fs: &dyn FileSystem) -> Result<BTreeMap<String, String>, KnechtError> {
+    read_config_file_with_fs(Path::new(".knecht/config"), fs)
+}
+
+/// Parse a config file: one `key = value` per line, `#` starts a comment
+fn read_config_file_with_fs(path: &Path, fs: &dyn FileSystem) -> Result<BTreeMap<String, String>, KnechtError> {
+    let mut values = BTreeMap::new();
+
+    if !fs.exists(path) {
+        return Ok(values);
+    }
+
+    let reader = fs.open(path)?;
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(KnechtError::InvalidConfig {
+                key: line.to_string(),
+                message: format!("line {} of {} is not of the form 'key = value'", index + 1, path.display()),
+            });
+        };
+        values.insert(key.trim().to_string(), value.trim().to_string());
+    }
+
+    Ok(values)
+}
+
+/// The effective value of a setting and where it comes from: the user file, the repository
+/// file, or the built-in default
+pub fn config_value_with_fs(key: &str, fs: &dyn FileSystem) -> Result<(String, ConfigSource), KnechtError> {
+    if let Some(value) = read_user_config_with_fs(fs)?.remove(key) {
+        return Ok((value, ConfigSource::User));
+    }
+    if let Some(value) = read_repo_config_with_fs(fs)?.remove(key) {
+        return Ok((value, ConfigSource::Repo));
+    }
+    match CONFIG_SETTINGS.iter().find(|(name, _)| *name == key) {
+        Some((_, default)) => Ok((default.to_string(), ConfigSource::Default)),
+        None if is_status_setting(key) => Ok((String::new(), ConfigSource::Default)),
+        None => Err(unknown_setting(key)),
+    }
+}
+
+fn unknown_setting(key: &str) -> KnechtError {
+    let known: Vec<&str> = CONFIG_SETTINGS.iter().map(|(name, _)| *name).collect();
+    KnechtError::InvalidConfig { key: key.to_string(), message: format!("unknown setting (known: {})", known.join(", ")) }
+}
+
+pub(crate) fn invalid(key: &str, message: String) -> KnechtError {
+    KnechtError::InvalidConfig { key: key.to_string(), message }
+}
+
+fn parse_flag(key: &str, value: &str) -> Result<bool, KnechtError> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(invalid(key, format!("expected true or false, got '{}'", value))),
+    }
+}
+
+/// Comma-separated values, ignoring blanks
+pub(crate) fn parse_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect()
+}
+
+fn parse_required_fields(value: &str) -> Result<Vec<String>, KnechtError> {
+    let fields = parse_list(value);
+    match fields.iter().find(|field| !OPTIONAL_FIELDS.contains(&field.as_str()) && *field != "none") {
+        Some(field) => Err(invalid("required_fields", format!("unknown field '{}' (expected {})", field, OPTIONAL_FIELDS.join(", ")))),
+        None => Ok(fields.into_iter().filter(|field| field != "none").collect()),
+    }
+}
+
+fn parse_id_length(value: &str) -> Result<usize, KnechtError> {
+    match value.parse::<usize>() {
+        Ok(length) if (4..=32).contains(&length) => Ok(length),
+        _ => Err(invalid("id_length", format!("expected a number from 4 to 32, got '{}'", value))),
+    }
+}
+
+fn parse_limit(key: &str, value: &str) -> Result<usize, KnechtError> {
+    match value.parse::<usize>() {
+        Ok(limit) if limit > 0 => Ok(limit),
+        _ => Err(invalid(key, format!("expected a positive number, got '{}'", value))),
+    }
+}
+
+/// A duration such as `90` (seconds), `30m`, `2h` or `1d`
+fn parse_duration_secs(key: &str, value: &str) -> Result<u64, KnechtError> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => value.split_at(pos),
+        None => (value, "s"),
+    };
+    let multiplier = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => 0,
+    };
+    match number.parse::<u64>() {
+        Ok(n) if n > 0 && multiplier > 0 => Ok(n * multiplier),
+        _ => Err(invalid(key, format!("expected a duration like 90, 30m, 2h or 1d, got '{}'", value))),
+    }
+}
+
+/// Check that `value` is acceptable for a known setting, before `knecht config set` writes it
+pub fn validate_config_value(key: &str, value: &str) -> Result<(), KnechtError> {
+    match key {
+        "format" => serializer_for_format(value).map(|_| ()).ok_or_else(|| invalid(key, format!("unknown task format '{}'", value))),
+        "required_fields" => parse_required_fields(value).map(|_| ()),
+        "id_length" => parse_id_length(value).map(|_| ()),
+        "list_footer" | "require_independent_verifier" => parse_flag(key, value).map(|_| ()),
+        "claim_lease" if !value.is_empty() => parse_duration_secs(key, value).map(|_| ()),
+        _ if key.starts_with("pain_half_life") && !value.is_empty() && CONFIG_SETTINGS.iter().any(|(name, _)| *name == key) => parse_duration_secs(key, value).map(|_| ()),
+        "wip_limit_per_agent" | "wip_limit" if !value.is_empty() => parse_limit(key, value).map(|_| ()),
+        "next_strategy" => strategy_for_name(value).map(|_| ()).ok_or_else(|| unknown_strategy(value)),
+        "blend.pain" | "blend.age" | "blend.pain-per-estimate" => parse_weight(key, value).map(|_| ()),
+        "statuses" | "active_statuses" | "resolved_statuses" if parse_list(value).is_empty() => Err(invalid(key, "expected a comma-separated list of statuses".to_string())),
+        _ if key.starts_with("status.") && value.trim().is_empty() => Err(invalid(key, "a glyph cannot be empty".to_string())),
+        "done_banner" | "list_statuses" | "claim_lease" | "wip_limit_per_agent" | "wip_limit" | "statuses" | "active_statuses" | "resolved_statuses" => Ok(()),
+        "pain_half_life" | "pain_half_life.manual" | "pain_half_life.skip" | "pain_half_life.sentry" => Ok(()),
+        _ if is_status_setting(key) => Ok(()),
+        _ => Err(unknown_setting(key)),
+    }
+}
+
+/// A boolean setting (`true`/`false`, `yes`/`no`, `1`/`0`), `default` when unset
+pub fn config_flag_with_fs(key: &str, default: bool, fs: &dyn FileSystem) -> Result<bool, KnechtError> {
+    match read_config_with_fs(fs)?.get(key) {
+        None => Ok(default),
+        Some(value) => parse_flag(key, value),
+    }
+}
+
+/// Optional task fields `knecht add` insists on (`required_fields`, default `acceptance_criteria`;
+/// `none` requires nothing beyond the title)
+pub fn required_fields_with_fs(fs: &dyn FileSystem) -> Result<Vec<String>, KnechtError> {
+    match read_config_with_fs(fs)?.get("required_fields") {
+        None => Ok(vec!["acceptance_criteria".to_string()]),
+        Some(value) => parse_required_fields(value),
+    }
+}
+
+/// Length of generated task IDs (`id_length`, default 6)
+pub fn id_length_with_fs(fs: &dyn FileSystem) -> Result<usize, KnechtError> {
+    match read_config_with_fs(fs)?.get("id_length") {
+        None => Ok(6),
+        Some(value) => parse_id_length(value),
+    }
+}
+
+/// Text printed after `knecht done` (`done_banner`, with `\n` for line breaks); None when set empty
+pub fn done_banner_with_fs(fs: &dyn FileSystem) -> Result<Option<String>, KnechtError> {
+    match read_config_with_fs(fs)?.get("done_banner") {
+        None => Ok(Some(DEFAULT_DONE_BANNER.to_string())),
+        Some(value) if value.is_empty() => Ok(None),
+        Some(value) => Ok(Some(value.replace("\\n", "\n"))),
+    }
+}
+
+/// Statuses `knecht list` shows without `--all` (`list_statuses`); None means every status
+/// except done and delivered
+pub fn list_statuses_with_fs(fs: &dyn FileSystem) -> Result<Option<Vec<String>>, KnechtError> {
+    Ok(read_config_with_fs(fs)?.get("list_statuses").map(|value| parse_list(value)).filter(|statuses| !statuses.is_empty()))
+}
+
+/// How long a claim holds, in seconds (`claim_lease`); None when claims never expire
+pub fn claim_lease_secs_with_fs(fs: &dyn FileSystem) -> Result<Option<u64>, KnechtError> {
+    match read_config_with_fs(fs)?.get("claim_lease") {
+        None => Ok(None),
+        Some(value) if value.is_empty() => Ok(None),
+        Some(value) => parse_duration_secs("claim_lease", value).map(Some),
+    }
+}
+
+/// Half-life of pain entries from `source` (`manual`, `skip` or `sentry`), in seconds:
+/// `pain_half_life.<source>`, else `pain_half_life`. None when pain never decays.
+pub fn pain_half_life_secs_with_fs(source: &str, fs: &dyn FileSystem) -> Result<Option<u64>, KnechtError> {
+    let config = read_config_with_fs(fs)?;
+    let key = format!("pain_half_life.{}", source);
+    let (key, value) = match config.get(&key) {
+        Some(value) => (key.as_str(), value),
+        None => match config.get("pain_half_life") {
+            Some(value) => ("pain_half_life", value),
+            None => return Ok(None),
+        },
+    };
+    match value.as_str() {
+        "" => Ok(None),
+        value => parse_duration_secs(key, value).map(Some),
+    }
+}
+
+/// A positive limit such as `wip_limit`; None when unset or empty
+pub fn config_limit_with_fs(key: &str, fs: &dyn FileSystem) -> Result<Option<usize>, KnechtError> {
+    match read_config_with_fs(fs)?.get(key) {
+        None => Ok(None),
+        Some(value) if value.is_empty() => Ok(None),
+        Some(value) => parse_limit(key, value).map(Some),
+    }
+}
+
+/// Set `key` in `.knecht/config`, replacing its line in place or appending it.
+/// Comments and other keys are kept as they are.
+pub fn set_config_value_with_fs(key: &str, value: &str, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    set_config_file_value_with_fs(Path::new(".knecht/config"), key, value, fs)
+}
+
+/// Set `key` in the per-user config file, which overrides `.knecht/config`
+pub fn set_user_config_value_with_fs(key: &str, value: &str, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    if !USER_CONFIG_KEYS.contains(&key) {
+        return Err(invalid(key, format!("is a repository setting; only {} can be set per user", USER_CONFIG_KEYS.join(", "))));
+    }
+    let path = user_config_path().ok_or_else(|| invalid(key, "cannot locate the user config directory (set HOME or XDG_CONFIG_HOME)".to_string()))?;
+    if let Some(dir) = path.parent() {
+        fs.create_dir_all(dir)?;
+    }
+    set_config_file_value_with_fs(&path, key, value, fs)
+}
+
+fn set_config_file_value_with_fs(path: &Path, key: &str, value: &str, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    let mut lines = Vec::new();
+
+    if fs.exists(path) {
+        for line in fs.open(path)?.lines() {
+            lines.push(line?);
+        }
+    }
+
+    let entry = format!("{} = {}", key, value);
+    let existing = lines.iter().position(|line| {
+        line.split_once('=').is_some_and(|(k, _)| k.trim() == key) && !line.trim_start().starts_with('#')
+    });
+    match existing {
+        Some(index) => lines[index] = entry,
+        None => lines.push(entry),
+    }
+
+    let mut writer = fs.create(path)?;
+    for line in &lines {
+        writeln!(writer, "{}", line)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The serializer selected by the `format` key, CSV when unset
+pub fn task_serializer_with_fs(fs: &dyn FileSystem) -> Result<Box<dyn TaskSerializer>, KnechtError> {
+    match read_repo_config_with_fs(fs)?.get("format") {
+        None => Ok(Box::new(CsvSerializer)),
+        Some(name) => serializer_for_format(name).ok_or_else(|| KnechtError::InvalidConfig {
+            key: "format".to_string(),
+            message: format!("unknown task format '{}'", name),
+        }),
+    }
+}
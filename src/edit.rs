@@ -0,0 +1,94 @@
+use crate::KnechtError;
+
+const TITLE_MARKER: &str = "--- title ---";
+const DESCRIPTION_MARKER: &str = "--- description ---";
+const CRITERIA_MARKER: &str = "--- acceptance criteria ---";
+
+/// The user-editable fields of a task, as read back from an editor buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditedTask {
+    pub title: String,
+    pub description: Option<String>,
+    pub acceptance_criteria: Option<String>,
+}
+
+/// Lay out task fields for editing: one section per field, each introduced by a marker line.
+/// `#` lines before the first marker are instructions and are ignored when parsing.
+pub fn format_task_for_editing(title: &str, description: Option<&str>, acceptance_criteria: Option<&str>) -> String {
+    format!(
+        "# Edit the sections below. Keep the marker lines; empty sections are cleared.\n\
+         {}\n{}\n\n{}\n{}\n\n{}\n{}\n",
+        TITLE_MARKER,
+        title,
+        DESCRIPTION_MARKER,
+        description.unwrap_or(""),
+        CRITERIA_MARKER,
+        acceptance_criteria.unwrap_or(""),
+    )
+}
+
+/// Parse an editor buffer produced by `format_task_for_editing`
+pub fn parse_edited_task(content: &str) -> Result<EditedTask, KnechtError> {
+    // Each section: the line number of its marker and the lines that follow it
+    let mut sections: [Option<(usize, Vec<&str>)>; 3] = [None, None, None];
+    let mut current: Option<usize> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let marker = match line.trim_end() {
+            TITLE_MARKER => Some(0),
+            DESCRIPTION_MARKER => Some(1),
+            CRITERIA_MARKER => Some(2),
+            _ => None,
+        };
+
+        if let Some(section) = marker {
+            if sections[section].is_some() {
+                return Err(KnechtError::InvalidRecord {
+                    line: index + 1,
+                    message: format!("duplicate '{}' marker", line.trim_end()),
+                });
+            }
+            sections[section] = Some((index + 1, Vec::new()));
+            current = Some(section);
+            continue;
+        }
+
+        match current {
+            Some(section) => sections[section].as_mut().unwrap().1.push(line),
+            None if line.starts_with('#') || line.trim().is_empty() => {}
+            None => {
+                return Err(KnechtError::InvalidRecord {
+                    line: index + 1,
+                    message: format!("text outside a section; expected '{}' first", TITLE_MARKER),
+                });
+            }
+        }
+    }
+
+    let end = content.lines().count() + 1;
+    let text = |section: usize, marker: &str| -> Result<Option<String>, KnechtError> {
+        match &sections[section] {
+            None => Err(KnechtError::InvalidRecord { line: end, message: format!("missing '{}' marker", marker) }),
+            Some((_, lines)) => {
+                let joined = lines.join("\n");
+                let trimmed = joined.trim_matches('\n').trim_end();
+                Ok((!trimmed.is_empty()).then(|| trimmed.to_string()))
+            }
+        }
+    };
+
+    let title = text(0, TITLE_MARKER)?.map(|t| t.trim().to_string()).unwrap_or_default();
+    let title_line = sections[0].as_ref().map_or(end, |(line, _)| line + 1);
+    if title.is_empty() {
+        return Err(KnechtError::InvalidRecord { line: title_line, message: "title cannot be empty".to_string() });
+    }
+    if title.contains('\n') {
+        return Err(KnechtError::InvalidRecord { line: title_line, message: "title must be a single line".to_string() });
+    }
+
+    Ok(EditedTask {
+        title,
+        description: text(1, DESCRIPTION_MARKER)?,
+        acceptance_criteria: text(2, CRITERIA_MARKER)?,
+    })
+}
@@ -0,0 +1,355 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    append_history_entry_with_fs, append_pain_entry_with_fs, append_session_with_fs, read_sessions_with_fs, generate_random_id_with_length, id_length_with_fs, is_valid_task_id, read_history_with_fs, HistoryEntry, migrate_to_directory_format, read_blockers_with_fs,
+    read_pain_entries_with_fs, read_tasks_with_fs, write_task_with_fs, FileSystem, KnechtError,
+    PainEntry, PainSourceType, Task, WorkSession,
+};
+
+/// Version written in the header record of an export
+pub const EXPORT_FORMAT_VERSION: u32 = 2;
+
+/// One line of a JSON Lines export, discriminated by its `type` field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportRecord {
+    Header { version: u32 },
+    Task(Task),
+    Blocker { task_id: String, blocker_id: String },
+    Pain(PainEntry),
+    SentryMapping(SentryMapping),
+    History(HistoryEntry),
+    /// Since version 2
+    Session(WorkSession),
+}
+
+/// A line of `.knecht/sentry-mapping`, linking a Sentry issue to the task created for it.
+/// The file is append-only: the last line for an issue is the current state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SentryMapping {
+    pub sentry_issue_id: String,
+    pub knecht_task_id: String,
+    pub last_sync_timestamp: u64,
+    pub last_event_count: u64,
+}
+
+/// How `import_records_with_fs` treats data already in the repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Keep existing data; imported tasks overwrite tasks with the same ID
+    Merge,
+    /// Discard all existing tasks, blockers, pain, Sentry mappings, history and sessions first
+    Replace,
+}
+
+/// What an import wrote, including the old → new IDs when remapping
+#[derive(Debug, Default, Serialize)]
+pub struct ImportSummary {
+    pub tasks: usize,
+    pub blockers: usize,
+    pub pain_entries: usize,
+    pub sentry_mappings: usize,
+    pub history_entries: usize,
+    pub sessions: usize,
+    pub remapped_ids: BTreeMap<String, String>,
+}
+
+/// Read every line of the Sentry mapping log, oldest first
+pub fn read_sentry_mappings_with_fs(fs: &dyn FileSystem) -> Result<Vec<SentryMapping>, KnechtError> {
+    let path = Path::new(".knecht/sentry-mapping");
+
+    if !fs.exists(path) {
+        return Ok(Vec::new());
+    }
+
+    let reader = fs.open(path)?;
+    let mut mappings = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let parts: Vec<&str> = line.split('|').collect();
+        if parts.len() >= 4 {
+            mappings.push(SentryMapping {
+                sentry_issue_id: parts[0].to_string(),
+                knecht_task_id: parts[1].to_string(),
+                last_sync_timestamp: parts[2].parse().unwrap_or(0),
+                last_event_count: parts[3].parse().unwrap_or(0),
+            });
+        }
+    }
+
+    Ok(mappings)
+}
+
+/// Append a line to the Sentry mapping log
+pub fn append_sentry_mapping_with_fs(mapping: &SentryMapping, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    let mut writer = fs.append(Path::new(".knecht/sentry-mapping"))?;
+    writeln!(writer, "{}|{}|{}|{}",
+        mapping.sentry_issue_id, mapping.knecht_task_id, mapping.last_sync_timestamp, mapping.last_event_count)?;
+    Ok(())
+}
+
+/// Collect the whole tracker into export records: a header, then tasks (sorted by ID),
+/// blockers, pain entries, Sentry mappings, history and work sessions in file order. Blockers,
+/// pain, history and sessions of deleted tasks are left out, so every export can be imported.
+pub fn export_records_with_fs(fs: &dyn FileSystem) -> Result<Vec<ExportRecord>, KnechtError> {
+    let mut tasks = read_tasks_with_fs(fs)?;
+    tasks.sort_by(|a, b| a.id.cmp(&b.id));
+    let ids: HashSet<String> = tasks.iter().map(|t| t.id.clone()).collect();
+
+    let mut records = vec![ExportRecord::Header { version: EXPORT_FORMAT_VERSION }];
+    records.extend(tasks.into_iter().map(ExportRecord::Task));
+    records.extend(read_blockers_with_fs(fs)?.into_iter()
+        .filter(|(task_id, blocker_id)| ids.contains(task_id) && ids.contains(blocker_id))
+        .map(|(task_id, blocker_id)| ExportRecord::Blocker { task_id, blocker_id }));
+    records.extend(read_pain_entries_with_fs(fs)?.into_iter()
+        .filter(|entry| ids.contains(&entry.task_id))
+        .map(ExportRecord::Pain));
+    records.extend(read_sentry_mappings_with_fs(fs)?.into_iter().map(ExportRecord::SentryMapping));
+    records.extend(read_history_with_fs(fs)?.into_iter()
+        .filter(|entry| ids.contains(&entry.task_id))
+        .map(ExportRecord::History));
+    records.extend(read_sessions_with_fs(fs)?.into_iter()
+        .filter(|session| ids.contains(&session.task_id))
+        .map(ExportRecord::Session));
+    Ok(records)
+}
+
+/// Write records as JSON Lines, one record per line
+pub fn write_jsonl(records: &[ExportRecord], mut writer: impl Write) -> Result<(), KnechtError> {
+    for record in records {
+        let line = serde_json::to_string(record)
+            .map_err(|e| KnechtError::InvalidRecord { line: 0, message: e.to_string() })?;
+        writeln!(writer, "{}", line)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Parse JSON Lines records, skipping blank lines
+pub fn read_jsonl(reader: impl BufRead) -> Result<Vec<ExportRecord>, KnechtError> {
+    let mut records = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: ExportRecord = serde_json::from_str(&line)
+            .map_err(|e| KnechtError::InvalidRecord { line: index + 1, message: e.to_string() })?;
+        if let ExportRecord::Header { version } = record
+            && version > EXPORT_FORMAT_VERSION
+        {
+            return Err(KnechtError::InvalidRecord {
+                line: index + 1,
+                message: format!("unsupported export version {}", version),
+            });
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Load export records into the repository.
+///
+/// With `remap_ids` every imported task gets a fresh ID and all references to it
+/// (blockers, pain entries, skip sources, Sentry mappings, history, sessions) are rewritten, so tasks can be
+/// copied between repositories without colliding. Records are checked by `validate_records_with_fs`
+/// before anything is written.
+pub fn import_records_with_fs(records: &[ExportRecord], mode: ImportMode, remap_ids: bool, fs: &dyn FileSystem) -> Result<ImportSummary, KnechtError> {
+    validate_records_with_fs(records, mode, fs)?;
+
+    migrate_to_directory_format(fs)?;
+    let tasks_dir = Path::new(".knecht/tasks");
+    fs.create_dir_all(tasks_dir)?;
+
+    if mode == ImportMode::Replace {
+        for entry in fs.read_dir(tasks_dir)? {
+            fs.remove_file(&entry)?;
+        }
+        for log in [".knecht/blockers", ".knecht/pain", ".knecht/sentry-mapping", ".knecht/history", ".knecht/sessions"] {
+            let path = Path::new(log);
+            if fs.exists(path) {
+                fs.remove_file(path)?;
+            }
+        }
+    }
+
+    let mut summary = ImportSummary::default();
+
+    if remap_ids {
+        let id_length = id_length_with_fs(fs)?;
+        let mut taken: HashSet<String> = read_tasks_with_fs(fs)?.into_iter().map(|t| t.id).collect();
+        for record in records {
+            if let ExportRecord::Task(task) = record {
+                let mut new_id = generate_random_id_with_length(id_length);
+                while taken.contains(&new_id) {
+                    new_id = generate_random_id_with_length(id_length);
+                }
+                taken.insert(new_id.clone());
+                summary.remapped_ids.insert(task.id.clone(), new_id);
+            }
+        }
+    }
+    let remap = |id: &str| summary.remapped_ids.get(id).cloned().unwrap_or_else(|| id.to_string());
+
+    let mut blockers = read_blockers_with_fs(fs)?;
+    let existing_pain = read_pain_entries_with_fs(fs)?;
+    let existing_mappings = read_sentry_mappings_with_fs(fs)?;
+    let existing_history = read_history_with_fs(fs)?;
+    let existing_sessions = read_sessions_with_fs(fs)?;
+    let (mut tasks, mut new_blockers, mut pain, mut mappings, mut history, mut sessions) = (0, 0, 0, 0, 0, 0);
+
+    for record in records {
+        match record {
+            ExportRecord::Header { .. } => {}
+            ExportRecord::Task(task) => {
+                let task = Task { id: remap(&task.id), ..task.clone() };
+                write_task_with_fs(&task, fs)?;
+                tasks += 1;
+            }
+            ExportRecord::Blocker { task_id, blocker_id } => {
+                let blocker = (remap(task_id), remap(blocker_id));
+                if !blockers.contains(&blocker) {
+                    blockers.push(blocker);
+                    new_blockers += 1;
+                }
+            }
+            ExportRecord::Pain(entry) => {
+                let mut entry = entry.clone();
+                entry.task_id = remap(&entry.task_id);
+                if matches!(entry.source_type, PainSourceType::Skip) {
+                    entry.source_id = entry.source_id.as_deref().map(remap);
+                }
+                entry.during_task = entry.during_task.as_deref().map(remap);
+                if !existing_pain.contains(&entry) {
+                    append_pain_entry_with_fs(&entry, fs)?;
+                    pain += 1;
+                }
+            }
+            ExportRecord::SentryMapping(mapping) => {
+                let mapping = SentryMapping { knecht_task_id: remap(&mapping.knecht_task_id), ..mapping.clone() };
+                if !existing_mappings.contains(&mapping) {
+                    append_sentry_mapping_with_fs(&mapping, fs)?;
+                    mappings += 1;
+                }
+            }
+            ExportRecord::History(entry) => {
+                let entry = HistoryEntry { task_id: remap(&entry.task_id), ..entry.clone() };
+                if !existing_history.contains(&entry) {
+                    append_history_entry_with_fs(&entry, fs)?;
+                    history += 1;
+                }
+            }
+            ExportRecord::Session(session) => {
+                let session = WorkSession { task_id: remap(&session.task_id), ..session.clone() };
+                if !existing_sessions.contains(&session) {
+                    append_session_with_fs(&session, fs)?;
+                    sessions += 1;
+                }
+            }
+        }
+    }
+
+    if new_blockers > 0 {
+        write_blockers_with_fs(&blockers, fs)?;
+    }
+
+    summary.tasks = tasks;
+    summary.blockers = new_blockers;
+    summary.pain_entries = pain;
+    summary.sentry_mappings = mappings;
+    summary.history_entries = history;
+    summary.sessions = sessions;
+    Ok(summary)
+}
+
+/// Reject records that would corrupt the repository: task IDs that are not a single plain file
+/// name, blockers, pain, history and sessions of a task that is neither imported nor (when
+/// merging) already there, and values with line breaks, or with `|` outside a log's last
+/// column, that would split a line of the line-based logs
+fn validate_records_with_fs(records: &[ExportRecord], mode: ImportMode, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    let mut known: HashSet<String> = match mode {
+        ImportMode::Merge => read_tasks_with_fs(fs)?.into_iter().map(|t| t.id).collect(),
+        ImportMode::Replace => HashSet::new(),
+    };
+    known.extend(records.iter().filter_map(|record| match record {
+        ExportRecord::Task(task) => Some(task.id.clone()),
+        _ => None,
+    }));
+
+    for (index, record) in records.iter().enumerate() {
+        let line = index + 1;
+        let task_id = |id: &str| check_task_id(line, id);
+        let task_ref = |id: &str| {
+            check_task_id(line, id)?;
+            match known.contains(id) {
+                true => Ok(()),
+                false => Err(KnechtError::InvalidRecord { line, message: format!("unknown task '{}'", id) }),
+            }
+        };
+        let field = |name: &str, value: &str, last_column: bool| check_log_field(line, name, value, last_column);
+
+        match record {
+            ExportRecord::Header { .. } => {}
+            ExportRecord::Task(task) => task_id(&task.id)?,
+            ExportRecord::Blocker { task_id, blocker_id } => {
+                task_ref(task_id)?;
+                task_ref(blocker_id)?;
+            }
+            ExportRecord::Pain(entry) => {
+                task_ref(&entry.task_id)?;
+                if let Some(during) = &entry.during_task {
+                    task_id(during)?;
+                }
+                field("source_id", entry.source_id.as_deref().unwrap_or(""), false)?;
+                field("description", &entry.description, true)?;
+            }
+            ExportRecord::SentryMapping(mapping) => {
+                task_id(&mapping.knecht_task_id)?;
+                field("sentry_issue_id", &mapping.sentry_issue_id, false)?;
+            }
+            ExportRecord::History(entry) => {
+                task_ref(&entry.task_id)?;
+                field("event", &entry.event, false)?;
+                field("actor", &entry.actor, false)?;
+                field("detail", entry.detail.as_deref().unwrap_or(""), true)?;
+            }
+            ExportRecord::Session(session) => {
+                task_ref(&session.task_id)?;
+                field("actor", &session.actor, true)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_task_id(line: usize, id: &str) -> Result<(), KnechtError> {
+    match is_valid_task_id(id) {
+        true => Ok(()),
+        false => Err(KnechtError::InvalidRecord { line, message: format!("invalid task id '{}'", id) }),
+    }
+}
+
+fn check_log_field(line: usize, name: &str, value: &str, last_column: bool) -> Result<(), KnechtError> {
+    if value.contains(['\n', '\r']) || (!last_column && value.contains('|')) {
+        return Err(KnechtError::InvalidRecord { line, message: format!("{} {:?} would break the log's line format", name, value) });
+    }
+    Ok(())
+}
+
+fn write_blockers_with_fs(blockers: &[(String, String)], fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    let mut writer = fs.create(&PathBuf::from(".knecht/blockers"))?;
+    for (task_id, blocker_id) in blockers {
+        writeln!(writer, "task-{}|task-{}", task_id, blocker_id)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{FileSystem, KnechtError};
+
+/// A line of the append-only `.knecht/history` log:
+/// `task_id|timestamp|event|actor|detail`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub task_id: String,
+    pub timestamp: u64,
+    pub event: String,
+    pub actor: String,
+    pub detail: Option<String>,
+}
+
+impl HistoryEntry {
+    /// An entry for `event` on `task_id`, stamped with the current time and actor
+    pub fn now(task_id: &str, event: &str, detail: Option<&str>) -> Self {
+        HistoryEntry {
+            task_id: task_id.to_string(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            event: event.to_string(),
+            actor: current_actor(),
+            detail: detail.map(str::to_string),
+        }
+    }
+}
+
+/// Who is running knecht: `KNECHT_AGENT`, else the login name, else "unknown"
+pub fn current_actor() -> String {
+    ["KNECHT_AGENT", "USER", "USERNAME"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|value| value.trim().to_string())
+        .find(|value| !value.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Append an entry to the history log. Pipes in the actor and line breaks in the
+/// detail are replaced so each entry stays on one line.
+pub fn append_history_entry_with_fs(entry: &HistoryEntry, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    let mut writer = fs.append(Path::new(".knecht/history"))?;
+    writeln!(writer, "{}|{}|{}|{}|{}",
+        entry.task_id,
+        entry.timestamp,
+        entry.event,
+        entry.actor.replace('|', "/"),
+        entry.detail.as_deref().unwrap_or("").replace(['\r', '\n'], " "))?;
+    Ok(())
+}
+
+/// Read the whole history log, oldest first
+pub fn read_history_with_fs(fs: &dyn FileSystem) -> Result<Vec<HistoryEntry>, KnechtError> {
+    let path = Path::new(".knecht/history");
+
+    if !fs.exists(path) {
+        return Ok(Vec::new());
+    }
+
+    let reader = fs.open(path)?;
+    let mut entries = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.is_empty() { continue; }
+
+        let parts: Vec<&str> = line.splitn(5, '|').collect();
+        if parts.len() >= 5 {
+            entries.push(HistoryEntry {
+                task_id: parts[0].to_string(),
+                timestamp: parts[1].parse().unwrap_or(0),
+                event: parts[2].to_string(),
+                actor: parts[3].to_string(),
+                detail: if parts[4].is_empty() { None } else { Some(parts[4].to_string()) },
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// The most recent `claimed` entry of each task, keyed by task ID
+pub fn latest_claims_with_fs(fs: &dyn FileSystem) -> Result<HashMap<String, HistoryEntry>, KnechtError> {
+    Ok(read_history_with_fs(fs)?.into_iter()
+        .filter(|e| e.event == "claimed")
+        .map(|e| (e.task_id.clone(), e))
+        .collect())
+}
+
+/// History entries for a single task, oldest first
+pub fn get_history_for_task(task_id: &str, fs: &dyn FileSystem) -> Result<Vec<HistoryEntry>, KnechtError> {
+    Ok(read_history_with_fs(fs)?.into_iter().filter(|e| e.task_id == task_id).collect())
+}
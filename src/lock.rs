@@ -0,0 +1,105 @@
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use crate::{now_secs, FileSystem, KnechtError};
+
+/// How long `acquire_lock_with_fs` waits for another knecht to finish
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A lock older than this was left behind by a knecht that crashed
+const STALE_LOCK_SECS: u64 = 60;
+
+const LOCK_PATH: &str = ".knecht/lock";
+
+/// Taken exclusively by whoever breaks a stale lock, so only one waiter removes it
+const BREAK_PATH: &str = ".knecht/lock.break";
+
+/// Holds `.knecht/lock` until dropped
+pub struct RepoLock<'a> {
+    fs: &'a dyn FileSystem,
+    holder: String,
+}
+
+impl Drop for RepoLock<'_> {
+    fn drop(&mut self) {
+        // A lock held past the stale limit may have been broken and taken by someone else
+        let path = Path::new(LOCK_PATH);
+        if read_holder(path, self.fs) == self.holder {
+            let _ = self.fs.remove_file(path);
+        }
+    }
+}
+
+/// Take the repository lock, an exclusively created `.knecht/lock` file holding the process
+/// ID and time. Waits up to `timeout` for another holder, and breaks locks older than a minute.
+pub fn acquire_lock_with_fs(timeout: Duration, fs: &dyn FileSystem) -> Result<RepoLock<'_>, KnechtError> {
+    let path = Path::new(LOCK_PATH);
+    let poll = Duration::from_millis(20);
+    let mut waited = Duration::ZERO;
+
+    loop {
+        match fs.create_new(path) {
+            Ok(mut writer) => {
+                let holder = format!("{} {}", std::process::id(), now_secs());
+                writeln!(writer, "{}", holder)?;
+                writer.flush()?;
+                return Ok(RepoLock { fs, holder });
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let holder = read_holder(path, fs);
+        if is_stale(&holder) && break_stale_lock(&holder, fs)? {
+            continue;
+        }
+        if waited >= timeout {
+            return Err(KnechtError::Locked { holder });
+        }
+        thread::sleep(poll);
+        waited += poll;
+    }
+}
+
+/// Whether a `pid timestamp` holder line is older than `STALE_LOCK_SECS`
+fn is_stale(holder: &str) -> bool {
+    let taken_at = holder.split_whitespace().nth(1).and_then(|t| t.parse::<u64>().ok());
+    taken_at.is_some_and(|at| at.saturating_add(STALE_LOCK_SECS) < now_secs())
+}
+
+/// Remove the lock if it still holds `stale`, returning whether it did. Two waiters can both
+/// see the same stale lock; taking `.knecht/lock.break` exclusively and re-reading the holder
+/// keeps the slower one from removing the lock the faster one has just taken.
+fn break_stale_lock(stale: &str, fs: &dyn FileSystem) -> Result<bool, KnechtError> {
+    let break_path = Path::new(BREAK_PATH);
+    match fs.create_new(break_path) {
+        Ok(mut writer) => writeln!(writer, "{} {}", std::process::id(), now_secs())?,
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            // Someone else is breaking it, or crashed while doing so
+            if is_stale(&read_holder(break_path, fs)) {
+                let _ = fs.remove_file(break_path);
+            }
+            return Ok(false);
+        }
+        Err(err) => return Err(err.into()),
+    }
+
+    let broken = read_holder(Path::new(LOCK_PATH), fs) == stale;
+    if broken {
+        let _ = fs.remove_file(Path::new(LOCK_PATH));
+    }
+    let _ = fs.remove_file(break_path);
+    Ok(broken)
+}
+
+/// The lock file's contents, `pid timestamp`; empty if it vanished meanwhile
+fn read_holder(path: &Path, fs: &dyn FileSystem) -> String {
+    fs.open(path).ok()
+        .and_then(|mut reader| {
+            let mut line = String::new();
+            reader.read_line(&mut line).ok().map(|_| line.trim().to_string())
+        })
+        .unwrap_or_default()
+}
@@ -1,12 +1,23 @@
 use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
-use clap::{Parser, Subcommand};
-use knecht::{add_task_with_fs, delete_task_with_fs, find_next_task_with_fs, find_task_by_id_with_fs, get_all_pain_counts, get_pain_count_for_task, get_pain_entries_for_task, increment_pain_count_with_fs, mark_task_claimed_with_fs, mark_task_delivered_with_fs, mark_task_done_with_fs, read_tasks_with_fs, update_task_with_fs, RealFileSystem};
+use clap::{Parser, Subcommand, ValueEnum};
+use knecht::{resolve_repo_root_with_fs, generate_random_id_with_length, check_criteria_items_with_fs, criteria_items, force_task_done_with_fs, list_templates_with_fs, render_template_with_fs, format_task_for_editing, parse_edited_task, EditedTask, migrate_task_format_with_fs, export_records_with_fs, import_records_with_fs, read_jsonl, write_jsonl, ImportMode, KnechtError, Task, add_task_with_fs, delete_task_with_fs, explain_next_task_with_fs, rank_tasks_with_fs, claim_next_task_with_fs, PriorityStrategy, QueueEntry, NextExplanation, ScoredTask, PainSourceType, priority_strategy_with_fs, strategy_for_name, STRATEGY_NAMES, estimate_task_with_fs, find_task_by_id_with_fs, get_all_pain_counts, get_all_pain_scores, pain_half_life_secs_with_fs, get_pain_count_for_task, get_pain_entries_for_task, record_pain_with_fs, retract_pain_entry_with_fs, move_pain_entry_with_fs, compact_pain_log_with_fs, current_task_for_actor_with_fs, mark_task_claimed_with_fs, mark_task_delivered_with_fs, mark_task_done_with_fs, read_tasks_with_fs, get_history_for_task, verify_task_with_fs, current_actor, config_value_with_fs, done_banner_with_fs, list_statuses_with_fs, config_flag_with_fs, required_fields_with_fs, set_config_value_with_fs, set_user_config_value_with_fs, user_config_path, validate_config_value, move_task_with_fs, workflow_with_fs, check_wip_limits_with_fs, format_duration, format_timestamp, now_secs, read_sessions_with_fs, stop_sessions_with_fs, WorkSession, flow_report_with_fs, update_task_with_fs, RealFileSystem};
 
 #[derive(Parser)]
 #[command(name = "knecht")]
 #[command(about = "A git-native task tracker for AI agents", long_about = None)]
 struct Cli {
+    /// Run as if knecht was started in <DIR>
+    #[arg(short = 'C', global = true, value_name = "DIR")]
+    directory: Option<PathBuf>,
+    /// Use this repository instead of searching parent directories for .knecht
+    #[arg(long = "knecht-dir", env = "KNECHT_DIR", global = true, value_name = "DIR")]
+    knecht_dir: Option<PathBuf>,
+    /// Print a single JSON object instead of human-readable text
+    #[arg(long, global = true)]
+    json: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -18,14 +29,38 @@ enum Commands {
     /// Add a new task
     Add {
         /// Task title (can be multiple words)
-        #[arg(required = true, num_args = 1..)]
+        #[arg(required_unless_present_any = ["edit", "template"], num_args = 1..)]
         title: Vec<String>,
         /// Task description
         #[arg(short, long = "description")]
         d: Option<String>,
         /// Acceptance criteria
-        #[arg(short, long = "acceptance-criteria")]
+        #[arg(short, long = "acceptance-criteria", allow_hyphen_values = true)]
         a: Option<String>,
+        /// Write the task in $VISUAL/$EDITOR, prefilled with any other arguments
+        #[arg(long)]
+        edit: bool,
+        /// Start from .knecht/templates/<NAME>; -d and -a override the template's sections
+        #[arg(long, value_name = "NAME")]
+        template: Option<String>,
+        /// Value for a template placeholder (repeatable)
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_template_var)]
+        vars: Vec<(String, String)>,
+    },
+    /// Work with task templates in .knecht/templates
+    Templates {
+        #[command(subcommand)]
+        command: TemplatesCommand,
+    },
+    /// Read or change settings in .knecht/config
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Edit a task's title, description and acceptance criteria in $VISUAL/$EDITOR
+    Edit {
+        /// Task ID (e.g., task-1 or 1)
+        task_id: String,
     },
     /// List tasks (open tasks by default)
     List {
@@ -37,11 +72,46 @@ enum Commands {
     Done {
         /// Task ID (e.g., task-1 or 1)
         task_id: String,
+        /// Complete the task even though acceptance criteria are unchecked
+        #[arg(long, requires = "reason")]
+        force: bool,
+        /// Why the task is done despite unchecked criteria (recorded in .knecht/history)
+        #[arg(long, requires = "force")]
+        reason: Option<String>,
+    },
+    /// Check off acceptance criteria items
+    Check {
+        /// Task ID (e.g., task-1 or 1)
+        task_id: String,
+        /// Item numbers, as listed by `knecht show`
+        #[arg(required_unless_present = "all")]
+        items: Vec<usize>,
+        /// Check every item
+        #[arg(long, conflicts_with = "items")]
+        all: bool,
+        /// Clear the checkmark instead
+        #[arg(long)]
+        uncheck: bool,
     },
     /// Mark a task as delivered
     Deliver {
         /// Task ID (e.g., task-1 or 1)
         task_id: String,
+        /// How the work was verified: commands run, commit hash, manual steps
+        #[arg(long)]
+        evidence: Option<String>,
+    },
+    /// Sign off a delivered task and mark it done
+    Verify {
+        /// Task ID (e.g., task-1 or 1)
+        task_id: String,
+    },
+    /// Move a task to any status the configured workflow allows
+    Move {
+        /// Task ID (e.g., task-1 or 1)
+        task_id: String,
+        /// Target status (see `statuses` in .knecht/config)
+        status: String,
     },
     /// Delete a task
     Delete {
@@ -58,17 +128,84 @@ enum Commands {
         /// Task ID (e.g., task-1 or 1)
         task_id: String,
     },
-    /// Increment pain count for a task
-    Pain {
+    /// Pause your work session on a task without changing its status
+    Stop {
         /// Task ID (e.g., task-1 or 1)
-        #[arg(short = 't', required = true)]
         task_id: String,
-        /// Description of the pain instance
-        #[arg(short, required = true)]
-        d: String,
+    },
+    /// Show time worked on a task, or a per-task summary
+    Time {
+        /// Task ID (e.g., task-1 or 1); omit for a summary of every task
+        task_id: Option<String>,
+        /// Only count time since this long ago, e.g. 7d, 12h or 30m
+        #[arg(long, value_parser = parse_since)]
+        since: Option<u64>,
+    },
+    /// Flow metrics: weekly throughput, lead and cycle time, verification wait and open work aging
+    Report {
+        /// Weeks of throughput to show
+        #[arg(long, default_value_t = 8)]
+        weeks: usize,
+    },
+    /// Increment pain count for a task, or retract or move a pain entry
+    Pain {
+        /// Task ID (e.g., task-1 or 1)
+        #[arg(short = 't', required_unless_present_any = ["retract", "move_entry"], conflicts_with_all = ["retract", "move_entry"])]
+        task_id: Option<String>,
+        /// Description of the pain instance, or why it is retracted
+        #[arg(short, required_unless_present_any = ["retract", "move_entry"], conflicts_with = "move_entry")]
+        d: Option<String>,
+        /// Task you were working on when it hurt (default: the task you claimed last)
+        #[arg(long, value_name = "TASK_ID", conflicts_with_all = ["retract", "move_entry"])]
+        during: Option<String>,
+        /// Retract a pain entry, numbered as in `knecht show` (e.g., task-1:2)
+        #[arg(long, value_name = "ENTRY", value_parser = parse_pain_entry_ref, conflicts_with = "move_entry")]
+        retract: Option<(String, usize)>,
+        /// Move a pain entry to another task
+        #[arg(long = "move", num_args = 2, value_names = ["ENTRY", "TASK"])]
+        move_entry: Option<Vec<String>>,
     },
     /// Get the next suggested task to work on
-    Next,
+    Next {
+        /// Prioritization strategy, overriding `next_strategy` (pain, age, pain-per-estimate, blend)
+        #[arg(long)]
+        strategy: Option<String>,
+        /// Show the candidates, their scores, the blocker chain and the pain behind the suggestion
+        #[arg(long, conflicts_with = "count")]
+        explain: bool,
+        /// Suggest the top N tasks instead of one, as `knecht queue -n N` does
+        #[arg(short = 'n', value_name = "N")]
+        count: Option<usize>,
+    },
+    /// List the work queue: distinct, unblocked tasks in the order `next` would suggest them
+    Queue {
+        /// Show at most N tasks
+        #[arg(short = 'n', long = "limit", value_name = "N")]
+        limit: Option<usize>,
+        /// Prioritization strategy, overriding `next_strategy` (pain, age, pain-per-estimate, blend)
+        #[arg(long)]
+        strategy: Option<String>,
+        /// Only include tasks with this status (repeatable), e.g. `--status open` to leave out delivered work
+        #[arg(long = "status", value_name = "STATUS")]
+        statuses: Vec<String>,
+    },
+    /// Pick the next task from the queue and claim it in one locked step
+    ClaimNext {
+        /// Identity to claim as (default: $KNECHT_AGENT, else the login name)
+        #[arg(long)]
+        agent: Option<String>,
+        /// Prioritization strategy, overriding `next_strategy` (pain, age, pain-per-estimate, blend)
+        #[arg(long)]
+        strategy: Option<String>,
+    },
+    /// Estimate a task's size, for the pain-per-estimate strategy
+    Estimate {
+        /// Task ID (e.g., task-1 or 1)
+        task_id: String,
+        /// Size in points, any positive number
+        #[arg(value_parser = parse_points)]
+        points: f64,
+    },
     /// Update a task's title or description
     Update {
         /// Task ID (e.g., task-1 or 1)
@@ -80,7 +217,7 @@ enum Commands {
         #[arg(short, long = "description")]
         d: Option<String>,
         /// Acceptance criteria
-        #[arg(short, long = "acceptance-criteria")]
+        #[arg(short, long = "acceptance-criteria", allow_hyphen_values = true)]
         a: Option<String>,
     },
     /// Mark a task as blocked by another task
@@ -103,6 +240,87 @@ enum Commands {
         /// Blocker task ID to remove (e.g., task-2 or 2)
         blocker_id: String,
     },
+    /// Export tasks, blockers, pain log, Sentry mappings and history
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value = "jsonl")]
+        format: ExportFormat,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import a JSON Lines export
+    Import {
+        /// Export file to read, or - for stdin
+        file: PathBuf,
+        /// How to treat data already in this repository
+        #[arg(long, value_enum, default_value = "merge")]
+        mode: ImportModeArg,
+        /// Give imported tasks fresh IDs and rewrite all references to them
+        #[arg(long)]
+        remap_ids: bool,
+    },
+    /// Fold runs of identical pain log lines into weighted entries
+    CompactPain,
+    /// Convert every task file to another format
+    Migrate {
+        /// Task file format to convert to
+        #[arg(long, value_enum)]
+        to: TaskFormat,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// One JSON record per line with a "type" discriminator
+    Jsonl,
+}
+
+#[derive(Subcommand)]
+enum TemplatesCommand {
+    /// List available templates and the placeholders they use
+    List,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the effective value of a setting
+    Get {
+        key: String,
+    },
+    /// Change a setting in .knecht/config
+    Set {
+        key: String,
+        value: String,
+        /// Write to the per-user config (~/.config/knecht/config), which overrides the repository
+        #[arg(long)]
+        user: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TaskFormat {
+    /// One CSV record per file
+    Csv,
+    /// Frontmatter metadata with a Markdown description
+    Markdown,
+}
+
+impl TaskFormat {
+    fn name(self) -> &'static str {
+        match self {
+            TaskFormat::Csv => "csv",
+            TaskFormat::Markdown => "markdown",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ImportModeArg {
+    /// Keep existing data; imported tasks overwrite tasks with the same ID
+    Merge,
+    /// Discard existing tasks, blockers, pain, Sentry mappings and history first
+    Replace,
 }
 
 /// Parses a task ID argument, stripping the "task-" prefix if present.
@@ -111,103 +329,580 @@ fn parse_task_id(task_arg: &str) -> &str {
     task_arg.strip_prefix("task-").unwrap_or(task_arg)
 }
 
+/// Parses a pain entry reference: a task ID and the entry's number in `knecht show`
+fn parse_pain_entry_ref(arg: &str) -> Result<(String, usize), String> {
+    let (task_arg, entry) = arg.rsplit_once(':')
+        .ok_or_else(|| format!("expected <task-id>:<n>, got '{}'", arg))?;
+    let entry = entry.parse::<usize>()
+        .map_err(|_| format!("expected a pain entry number after ':', got '{}'", entry))?;
+    Ok((parse_task_id(task_arg).to_string(), entry))
+}
+
+/// Parses a `--var key=value` argument
+fn parse_template_var(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", arg)),
+    }
+}
+
+/// Parses a `--since` age such as `7d`, `12h`, `30m` or `90s` into seconds
+fn parse_since(arg: &str) -> Result<u64, String> {
+    let split = arg.find(|c: char| !c.is_ascii_digit()).unwrap_or(arg.len());
+    let (amount, unit) = arg.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| format!("expected an age like 7d or 12h, got '{}'", arg))?;
+    let unit_secs = match unit {
+        "w" => 604_800,
+        "d" => 86_400,
+        "h" => 3_600,
+        "m" => 60,
+        "s" | "" => 1,
+        _ => return Err(format!("unknown unit '{}' in '{}' (use w, d, h, m or s)", unit, arg)),
+    };
+    Ok(amount.saturating_mul(unit_secs))
+}
+
+/// Parses an estimate: a positive number of points
+fn parse_points(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(points) if points.is_finite() && points > 0.0 => Ok(points),
+        _ => Err(format!("expected a positive number, got '{}'", arg)),
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
+    let json = cli.json;
+
+    if let Some(dir) = &cli.directory
+        && let Err(e) = std::env::set_current_dir(dir)
+    {
+        exit_with_error(json, "E_IO", EXIT_IO, &format!("Error: cannot change to '{}': {}", dir.display(), e));
+    }
+
+    // Paths given on the command line are relative to where knecht was started
+    let invocation_dir = std::env::current_dir().unwrap_or_default();
+
+    // Every command except init operates on the repository found above the working directory
+    if !matches!(cli.command, Commands::Init) {
+        enter_repository(cli.knecht_dir.as_deref(), json);
+    }
 
     match cli.command {
-        Commands::Init => cmd_init(),
-        Commands::Add { title, d, a } => cmd_add(&title.join(" "), d, a),
-        Commands::List { all } => cmd_list(all),
-        Commands::Done { task_id } => cmd_done(&task_id),
-        Commands::Deliver { task_id } => cmd_deliver(&task_id),
-        Commands::Delete { task_id } => cmd_delete(&task_id),
-        Commands::Show { task_id } => cmd_show(&task_id),
-        Commands::Start { task_id } => cmd_start(&task_id),
-        Commands::Pain { task_id, d } => cmd_pain(&task_id, &d),
-        Commands::Next => cmd_next(),
-        Commands::Update { task_id, title, d, a } => cmd_update(&task_id, title, d, a),
-        Commands::Block { task_id, by: _, blocker_id } => cmd_block(&task_id, &blocker_id),
-        Commands::Unblock { task_id, from: _, blocker_id } => cmd_unblock(&task_id, &blocker_id),
+        Commands::Init => cmd_init(cli.knecht_dir.as_deref(), json),
+        Commands::Add { title, d, a, edit, template, vars } => {
+            let (title, d, a) = match template {
+                Some(name) => apply_template(&name, &title.join(" "), d, a, vars, json),
+                None => (title.join(" "), d, a),
+            };
+            if edit {
+                cmd_add_with_editor(&title, d, a, json)
+            } else {
+                cmd_add(&title, d, a, json)
+            }
+        }
+        Commands::Templates { command: TemplatesCommand::List } => cmd_templates_list(json),
+        Commands::Config { command: ConfigCommand::Get { key } } => cmd_config_get(&key, json),
+        Commands::Config { command: ConfigCommand::Set { key, value, user } } => cmd_config_set(&key, &value, user, json),
+        Commands::Edit { task_id } => cmd_edit(&task_id, json),
+        Commands::List { all } => cmd_list(all, json),
+        Commands::Done { task_id, force: _, reason } => cmd_done(&task_id, reason.as_deref(), json),
+        Commands::Check { task_id, items, all: _, uncheck } => cmd_check(&task_id, &items, !uncheck, json),
+        Commands::Deliver { task_id, evidence } => cmd_deliver(&task_id, evidence.as_deref(), json),
+        Commands::Verify { task_id } => cmd_verify(&task_id, json),
+        Commands::Move { task_id, status } => cmd_move(&task_id, &status, json),
+        Commands::Delete { task_id } => cmd_delete(&task_id, json),
+        Commands::Show { task_id } => cmd_show(&task_id, json),
+        Commands::Start { task_id } => cmd_start(&task_id, json),
+        Commands::Stop { task_id } => cmd_stop(&task_id, json),
+        Commands::Time { task_id, since } => cmd_time(task_id.as_deref(), since, json),
+        Commands::Report { weeks } => cmd_report(weeks, json),
+        Commands::Pain { retract: Some((task_id, entry)), d, .. } => cmd_retract_pain(&task_id, entry, d.as_deref(), json),
+        Commands::Pain { move_entry: Some(args), .. } => cmd_move_pain(&args[0], &args[1], json),
+        Commands::Pain { task_id, d, during, .. } => {
+            cmd_pain(&task_id.unwrap_or_default(), &d.unwrap_or_default(), during.as_deref(), json)
+        }
+        Commands::Next { strategy, explain, count } => cmd_next(strategy.as_deref(), explain, count, json),
+        Commands::Queue { limit, strategy, statuses } => cmd_queue(strategy.as_deref(), limit, &statuses, json),
+        Commands::ClaimNext { agent, strategy } => cmd_claim_next(agent, strategy.as_deref(), json),
+        Commands::Estimate { task_id, points } => cmd_estimate(&task_id, points, json),
+        Commands::Update { task_id, title, d, a } => cmd_update(&task_id, title, d, a, json),
+        Commands::Block { task_id, by: _, blocker_id } => cmd_block(&task_id, &blocker_id, json),
+        Commands::Unblock { task_id, from: _, blocker_id } => cmd_unblock(&task_id, &blocker_id, json),
+        Commands::Export { format, output } => cmd_export(format, output.map(|p| invocation_dir.join(p)), json),
+        Commands::Import { file, mode, remap_ids } => cmd_import(&invocation_dir.join(file), mode, remap_ids, json),
+        Commands::CompactPain => cmd_compact_pain(json),
+        Commands::Migrate { to } => cmd_migrate(to, json),
     }
 }
 
-fn cmd_init() {
-    if let Err(e) = fs::create_dir_all(".knecht/tasks") {
-        eprintln!("Failed to create .knecht/tasks directory: {}", e);
-        std::process::exit(1);
+/// Exit statuses for failures detected by the CLI itself; library errors use
+/// `KnechtError::exit_code`. Both are listed in the README.
+const EXIT_USAGE: i32 = 2;
+const EXIT_BLOCKED: i32 = 7;
+const EXIT_NOT_BLOCKED: i32 = 8;
+const EXIT_IO: i32 = 9;
+const EXIT_EDIT_ABORTED: i32 = 13;
+const EXIT_HOOK_FAILED: i32 = 19;
+const EXIT_QUEUE_EMPTY: i32 = 24;
+
+/// Reports a failure and exits with `status`. With --json the error is printed to stdout as
+/// `{"error": {"code": ..., "message": ...}}`, otherwise `message` goes to stderr tagged with the code.
+fn exit_with_error(json: bool, code: &str, status: i32, message: &str) -> ! {
+    if json {
+        let message = message.strip_prefix("Error: ").unwrap_or(message);
+        println!("{}", serde_json::json!({ "error": { "code": code, "message": message } }));
+    } else {
+        eprintln!("{} [{}]", message, code);
     }
+    std::process::exit(status);
+}
 
-    println!("Initialized knecht");
+/// Reports a library error with the given human-readable prefix and exits
+fn exit_with_knecht_error(json: bool, prefix: &str, err: &KnechtError) -> ! {
+    exit_with_error(json, err.code(), err.exit_code(), &format!("{}: {}", prefix, err))
 }
 
-fn cmd_add(title: &str, description: Option<String>, acceptance_criteria: Option<String>) {
+fn print_json(value: serde_json::Value) {
+    println!("{}", value);
+}
+
+/// Locates the repository and makes its root the working directory, so the relative
+/// `.knecht/...` paths used throughout resolve against it.
+fn enter_repository(knecht_dir: Option<&Path>, json: bool) {
+    let cwd = match std::env::current_dir() {
+        Ok(cwd) => cwd,
+        Err(e) => exit_with_error(json, "E_IO", EXIT_IO, &format!("Error: cannot determine current directory: {}", e)),
+    };
+
+    let root = match resolve_repo_root_with_fs(&cwd, knecht_dir, &RealFileSystem) {
+        Ok(root) => root,
+        Err(e) => exit_with_knecht_error(json, "Error", &e),
+    };
+
+    if let Err(e) = std::env::set_current_dir(&root) {
+        exit_with_error(json, "E_IO", EXIT_IO, &format!("Error: cannot change to '{}': {}", root.display(), e));
+    }
+}
+
+fn cmd_init(knecht_dir: Option<&Path>, json: bool) {
+    // init never searches upwards: it creates the repository here, or where KNECHT_DIR points
+    let root = match knecht_dir {
+        Some(dir) if dir.file_name().is_some_and(|name| name == ".knecht") => dir.parent().unwrap_or(Path::new(".")),
+        Some(dir) => dir,
+        None => Path::new("."),
+    };
+
+    if let Err(e) = fs::create_dir_all(root.join(".knecht/tasks")) {
+        exit_with_error(json, "E_IO", EXIT_IO, &format!("Failed to create .knecht/tasks directory: {}", e));
+    }
+
+    if json {
+        print_json(serde_json::json!({ "initialized": root.join(".knecht") }));
+    } else {
+        println!("Initialized knecht");
+    }
+}
+
+fn cmd_add(title: &str, description: Option<String>, acceptance_criteria: Option<String>, json: bool) {
     if title.is_empty() {
-        eprintln!("Error: Title cannot be empty");
-        std::process::exit(1);
+        exit_with_error(json, "E_USAGE", EXIT_USAGE, "Error: Title cannot be empty");
     }
 
-    if acceptance_criteria.is_none() {
-        eprintln!("Error: Acceptance criteria is required. Use -a to specify criteria.");
+    let required = match required_fields_with_fs(&RealFileSystem) {
+        Ok(required) => required,
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    };
+    if required.iter().any(|field| field == "description") && description.is_none() {
+        exit_with_error(json, "E_USAGE", EXIT_USAGE, "Error: Description is required by required_fields in .knecht/config. Use -d to specify one.");
+    }
+
+    if acceptance_criteria.is_none() && required.iter().any(|field| field == "acceptance_criteria") {
+        if json {
+            exit_with_error(json, "E_USAGE", EXIT_USAGE, "Acceptance criteria is required. Use -a to specify criteria.");
+        }
+        eprintln!("Error: Acceptance criteria is required. Use -a to specify criteria. [E_USAGE]");
         eprintln!();
         eprintln!("Example:");
         eprintln!("  knecht add \"Task title\" -a \"Criteria that defines done\"");
-        std::process::exit(1);
+        std::process::exit(EXIT_USAGE);
     }
 
+    run_pre_hook("pre-add", &serde_json::json!({
+        "title": title,
+        "description": description,
+        "acceptance_criteria": acceptance_criteria,
+    }), json);
+
     match add_task_with_fs(title.to_string(), description, acceptance_criteria, &RealFileSystem) {
         Ok(task_id) => {
-            println!("Created task-{}", task_id);
-            println!("To make another task blocked by this: knecht block <task> by task-{}", task_id);
+            let task = match find_task_by_id_with_fs(&task_id, &RealFileSystem) {
+                Ok(task) => task,
+                Err(e) => exit_with_knecht_error(json, "Error", &e),
+            };
+            if json {
+                print_json(serde_json::json!({ "task": task }));
+            } else {
+                println!("Created task-{}", task_id);
+                println!("To make another task blocked by this: knecht block <task> by task-{}", task_id);
+            }
+            run_post_hook("post-add", &task, json);
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+        Err(e) => exit_with_knecht_error(json, "Error", &e),
+    }
+}
+
+/// Renders a template into the title, description and criteria for `add`.
+/// Explicit -d/-a values win over the template's sections.
+fn apply_template(name: &str, title: &str, description: Option<String>, acceptance_criteria: Option<String>, vars: Vec<(String, String)>, json: bool) -> (String, Option<String>, Option<String>) {
+    let title = (!title.is_empty()).then_some(title);
+    match render_template_with_fs(name, title, &vars.into_iter().collect(), &RealFileSystem) {
+        Ok(task) => (
+            task.title,
+            description.or(task.description),
+            acceptance_criteria.or(task.acceptance_criteria),
+        ),
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    }
+}
+
+fn cmd_config_get(key: &str, json: bool) {
+    match config_value_with_fs(key, &RealFileSystem) {
+        Ok((value, source)) => {
+            if json {
+                print_json(serde_json::json!({ "key": key, "value": value, "source": source }));
+                return;
+            }
+            println!("{}", value);
         }
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
     }
 }
 
-fn cmd_list(show_all: bool) {
-    let tasks = match read_tasks_with_fs(&RealFileSystem) {
-        Ok(tasks) => tasks,
-        Err(e) => {
-            eprintln!("Error reading tasks: {}", e);
-            std::process::exit(1);
+fn cmd_config_set(key: &str, value: &str, user: bool, json: bool) {
+    // Existing task files would no longer parse; migrate rewrites them and sets the key
+    if key == "format" {
+        exit_with_error(json, "E_USAGE", EXIT_USAGE, &format!("Error: use 'knecht migrate --to {}' to change the task file format", value));
+    }
+
+    if let Err(err) = validate_config_value(key, value) {
+        exit_with_knecht_error(json, "Error", &err);
+    }
+
+    let result = if user {
+        set_user_config_value_with_fs(key, value, &RealFileSystem)
+    } else {
+        set_config_value_with_fs(key, value, &RealFileSystem)
+    };
+    if let Err(err) = result {
+        exit_with_knecht_error(json, "Error", &err);
+    }
+
+    let file = if user {
+        user_config_path().map(|path| path.display().to_string()).unwrap_or_default()
+    } else {
+        ".knecht/config".to_string()
+    };
+    if json {
+        print_json(serde_json::json!({ "key": key, "value": value, "file": file }));
+        return;
+    }
+    println!("Set {} = {} in {}", key, value, file);
+}
+
+fn cmd_templates_list(json: bool) {
+    let templates = match list_templates_with_fs(&RealFileSystem) {
+        Ok(templates) => templates,
+        Err(err) => exit_with_knecht_error(json, "Error reading templates", &err),
+    };
+
+    if json {
+        print_json(serde_json::json!({ "templates": templates }));
+        return;
+    }
+
+    if templates.is_empty() {
+        println!("No templates. Add files to .knecht/templates to create some.");
+        return;
+    }
+
+    let width = templates.iter().map(|t| t.name.len()).max().unwrap_or(0);
+    for template in &templates {
+        print!("{:width$}  {}", template.name, template.title, width = width);
+        if !template.variables.is_empty() {
+            print!("  (vars: {})", template.variables.join(", "));
+        }
+        println!();
+    }
+}
+
+fn cmd_add_with_editor(title: &str, description: Option<String>, acceptance_criteria: Option<String>, json: bool) {
+    let initial = format_task_for_editing(title, description.as_deref(), acceptance_criteria.as_deref());
+    let required = match required_fields_with_fs(&RealFileSystem) {
+        Ok(required) => required,
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    };
+    let edited = edit_until_valid(initial, json, |task| {
+        if task.acceptance_criteria.is_none() && required.iter().any(|field| field == "acceptance_criteria") {
+            return Err("acceptance criteria is required".to_string());
+        }
+        if task.description.is_none() && required.iter().any(|field| field == "description") {
+            return Err("description is required".to_string());
+        }
+        Ok(())
+    });
+
+    cmd_add(&edited.title, edited.description, edited.acceptance_criteria, json);
+}
+
+fn cmd_edit(task_arg: &str, json: bool) {
+    let task_id = parse_task_id(task_arg);
+    let task = match find_task_by_id_with_fs(task_id, &RealFileSystem) {
+        Ok(task) => task,
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    };
+
+    let initial = format_task_for_editing(&task.title, task.description.as_deref(), task.acceptance_criteria.as_deref());
+    let edited = edit_until_valid(initial, json, |_| Ok(()));
+
+    let unchanged = edited.title == task.title
+        && edited.description == task.description
+        && edited.acceptance_criteria == task.acceptance_criteria;
+    if unchanged {
+        if json {
+            print_json(serde_json::json!({ "task": task }));
+        } else {
+            println!("No changes to task-{}", task.id);
+        }
+        return;
+    }
+
+    match update_task_with_fs(task_id, Some(edited.title), Some(edited.description), Some(edited.acceptance_criteria), &RealFileSystem) {
+        Ok(task) => {
+            if json {
+                print_json(serde_json::json!({ "task": task }));
+                return;
+            }
+            println!("Updated task-{}", task.id);
+        }
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    }
+}
+
+/// A new directory under the system temp dir that only the current user can access. It is
+/// created exclusively under a random name, so nothing else can have planted a file or symlink in it.
+fn private_temp_dir() -> std::io::Result<PathBuf> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+
+    loop {
+        let dir = std::env::temp_dir().join(format!("knecht-edit-{}", generate_random_id_with_length(16)));
+        match builder.create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
         }
+    }
+}
+
+/// Opens `content` in $VISUAL/$EDITOR (falling back to vi) until it parses and passes `validate`.
+/// Problems are shown as `# Error:` lines at the top of the buffer; saving it unchanged aborts.
+fn edit_until_valid(mut content: String, json: bool, validate: impl Fn(&EditedTask) -> Result<(), String>) -> EditedTask {
+    let editor = ["VISUAL", "EDITOR"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let dir = match private_temp_dir() {
+        Ok(dir) => dir,
+        Err(e) => exit_with_error(json, "E_IO", EXIT_IO, &format!("Error: cannot create a temporary directory: {}", e)),
+    };
+    let path = dir.join("task.txt");
+    let fail = |code: &str, status: i32, message: &str| -> ! {
+        let _ = fs::remove_dir_all(&dir);
+        exit_with_error(json, code, status, message)
     };
+    let mut shown_error = false;
+
+    loop {
+        if let Err(e) = fs::write(&path, &content) {
+            fail("E_IO", EXIT_IO, &format!("Error: cannot write {}: {}", path.display(), e));
+        }
+
+        // Run through the shell so editors configured with arguments ("code --wait") work
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("knecht-editor")
+            .arg(&path)
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => fail("E_EDIT_ABORTED", EXIT_EDIT_ABORTED, &format!("Error: editor '{}' exited with {}", editor, status)),
+            Err(e) => fail("E_IO", EXIT_IO, &format!("Error: cannot run editor '{}': {}", editor, e)),
+        }
+
+        let edited = match fs::read_to_string(&path) {
+            Ok(edited) => edited,
+            Err(e) => fail("E_IO", EXIT_IO, &format!("Error: cannot read {}: {}", path.display(), e)),
+        };
 
-    // Filter to open tasks unless --all flag is provided
-    let filtered_tasks: Vec<_> = if show_all {
-        tasks
+        if shown_error && edited == content {
+            fail("E_EDIT_ABORTED", EXIT_EDIT_ABORTED, "Error: edit aborted, the file was saved without fixing the error");
+        }
+
+        let problem = match parse_edited_task(&edited) {
+            Ok(task) => match validate(&task) {
+                Ok(()) => {
+                    let _ = fs::remove_dir_all(&dir);
+                    return task;
+                }
+                Err(message) => message,
+            },
+            Err(err) => err.to_string(),
+        };
+
+        let rest: String = edited.lines()
+            .skip_while(|line| line.starts_with("# Error:"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        content = format!("# Error: {}\n{}", problem, rest);
+        shown_error = true;
+    }
+}
+
+/// Runs the executable `.knecht/hooks/<hook>`, if there is one, with `{"event": hook, "task": task}`
+/// on stdin. Returns whether a hook ran, or the failure (including the hook's stderr).
+fn run_hook(hook: &str, task: &impl serde::Serialize, json: bool) -> Result<bool, String> {
+    use std::io::Write;
+
+    let path = Path::new(".knecht/hooks").join(hook);
+    let Ok(metadata) = fs::metadata(&path) else { return Ok(false) };
+    if !metadata.is_file() {
+        return Ok(false);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            eprintln!("Warning: ignoring {}: not executable", path.display());
+            return Ok(false);
+        }
+    }
+
+    let mut child = std::process::Command::new(&path)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot run {}: {}", path.display(), e))?;
+
+    // A hook that ignores its input may exit before reading it; that is not a failure
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = writeln!(stdin, "{}", serde_json::json!({ "event": hook, "task": task }));
+    }
+    let output = child.wait_with_output().map_err(|e| format!("cannot run {}: {}", path.display(), e))?;
+
+    // Keep stdout parseable in --json mode
+    let hook_stdout = String::from_utf8_lossy(&output.stdout);
+    if json {
+        eprint!("{}", hook_stdout);
+    } else {
+        print!("{}", hook_stdout);
+    }
+
+    let hook_stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() {
+        eprint!("{}", hook_stderr);
+        Ok(true)
     } else {
-        tasks.into_iter().filter(|t| !t.is_done() && t.status != "delivered").collect()
+        Err(format!("{} exited with {}\n{}", path.display(), output.status, hook_stderr.trim_end()).trim_end().to_string())
+    }
+}
+
+/// Runs a pre-hook; a failing hook aborts the command before anything is changed
+fn run_pre_hook(hook: &str, task: &impl serde::Serialize, json: bool) {
+    if let Err(message) = run_hook(hook, task, json) {
+        exit_with_error(json, "E_HOOK_FAILED", EXIT_HOOK_FAILED, &format!("Error: {} hook aborted the operation: {}", hook, message));
+    }
+}
+
+/// Runs a post-hook. The change is already made, so a failure is only reported.
+/// Returns whether a hook ran.
+fn run_post_hook(hook: &str, task: &impl serde::Serialize, json: bool) -> bool {
+    match run_hook(hook, task, json) {
+        Ok(ran) => ran,
+        Err(message) => {
+            eprintln!("Warning: {} hook failed: {}", hook, message);
+            true
+        }
+    }
+}
+
+fn cmd_list(show_all: bool, json: bool) {
+    let tasks = match read_tasks_with_fs(&RealFileSystem) {
+        Ok(tasks) => tasks,
+        Err(e) => exit_with_knecht_error(json, "Error reading tasks", &e),
     };
 
-    // Get all pain counts from the pain log (efficient bulk read)
+    let statuses = match list_statuses_with_fs(&RealFileSystem) {
+        Ok(statuses) => statuses,
+        Err(e) => exit_with_knecht_error(json, "Error", &e),
+    };
+    let workflow = match workflow_with_fs(&RealFileSystem) {
+        Ok(workflow) => workflow,
+        Err(e) => exit_with_knecht_error(json, "Error", &e),
+    };
+
+    // Filter to open tasks (or the configured list_statuses) unless --all flag is provided
+    let filtered_tasks: Vec<_> = match (show_all, &statuses) {
+        (true, _) => tasks,
+        (false, Some(statuses)) => tasks.into_iter().filter(|t| statuses.contains(&t.status)).collect(),
+        (false, None) => tasks.into_iter().filter(|t| !workflow.is_resolved(&t.status) && t.status != "delivered").collect(),
+    };
+
+    // Get all pain counts and decayed scores from the pain log (efficient bulk read)
     let pain_counts = get_all_pain_counts(&RealFileSystem).unwrap_or_default();
+    let pain_scores = get_all_pain_scores(now_secs(), &RealFileSystem).unwrap_or_default();
+    let decays = ["manual", "skip", "sentry"].iter()
+        .any(|source| pain_half_life_secs_with_fs(source, &RealFileSystem).ok().flatten().is_some());
+
+    if json {
+        let tasks: Vec<serde_json::Value> = filtered_tasks.into_iter()
+            .map(|mut task| {
+                task.pain_count = Some(pain_counts.get(&task.id).copied().unwrap_or(0));
+                let pain_score = pain_scores.get(&task.id).copied().unwrap_or(0.0);
+                let mut value = serde_json::to_value(task).unwrap_or_default();
+                value["pain_score"] = serde_json::json!(pain_score);
+                value
+            })
+            .collect();
+        print_json(serde_json::json!({ "tasks": tasks }));
+        return;
+    }
 
     for task in &filtered_tasks {
-        let checkbox = if task.is_done() {
-            "[x]"
-        } else if task.status == "delivered" {
-            "[>]"
-        } else if task.status == "claimed" {
-            "[~]"
-        } else {
-            "[ ]"
-        };
+        let checkbox = workflow.glyph(&task.status);
         let pain_count = pain_counts.get(&task.id).copied().unwrap_or(0);
-        let pain_suffix = if pain_count > 0 {
-            format!(" (pain count: {})", pain_count)
-        } else {
-            String::new()
+        let pain_suffix = match (pain_count, decays) {
+            (0, _) => String::new(),
+            (_, true) => format!(" (pain count: {}, decayed: {:.1})", pain_count, pain_scores.get(&task.id).copied().unwrap_or(0.0)),
+            (_, false) => format!(" (pain count: {})", pain_count),
         };
         println!("{} task-{}  {}{}", checkbox, task.id, task.title, pain_suffix);
     }
 
+    if !config_flag_with_fs("list_footer", true, &RealFileSystem).unwrap_or(true) {
+        return;
+    }
+
     // Print usage instructions for agents
     println!();
     if !show_all {
-        println!("Showing open tasks only. Use --all to see all tasks.");
+        match &statuses {
+            Some(statuses) => println!("Showing {} tasks only. Use --all to see all tasks.", statuses.join(", ")),
+            None => println!("Showing open tasks only. Use --all to see all tasks."),
+        }
         println!();
     }
     println!("Usage instructions:");
@@ -217,208 +912,758 @@ fn cmd_list(show_all: bool) {
     println!("  knecht next            - Get suggestion for what to work on next");
 }
 
-fn cmd_deliver(task_arg: &str) {
+fn cmd_deliver(task_arg: &str, evidence: Option<&str>, json: bool) {
     let task_id = parse_task_id(task_arg);
 
-    match mark_task_delivered_with_fs(task_id, &RealFileSystem) {
+    if evidence.is_some_and(|e| e.trim().is_empty()) {
+        exit_with_error(json, "E_USAGE", EXIT_USAGE, "Error: --evidence cannot be empty");
+    }
+
+    match mark_task_delivered_with_fs(task_id, evidence, &RealFileSystem) {
         Ok(task) => {
-            println!("✓ task-{}: {}", task.id, task.title);
+            if json {
+                print_json(serde_json::json!({ "task": task, "evidence": evidence }));
+            } else {
+                println!("✓ task-{}: {}", task.id, task.title);
+            }
+            run_post_hook("post-deliver", &task, json);
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+        Err(e) => exit_with_knecht_error(json, "Error", &e),
+    }
+}
+
+fn cmd_verify(task_arg: &str, json: bool) {
+    let task_id = parse_task_id(task_arg);
+
+    if let Ok(task) = find_task_by_id_with_fs(task_id, &RealFileSystem) {
+        run_pre_hook("pre-done", &task, json);
+    }
+
+    match verify_task_with_fs(task_id, &RealFileSystem) {
+        Ok(task) => {
+            if json {
+                print_json(serde_json::json!({ "task": task, "verifier": current_actor() }));
+            } else {
+                println!("✓ task-{}: {} (verified by {})", task.id, task.title, current_actor());
+            }
+            run_post_hook("post-done", &task, json);
         }
+        Err(e) => exit_with_knecht_error(json, "Error", &e),
     }
 }
 
-fn cmd_done(task_arg: &str) {
+fn cmd_move(task_arg: &str, status: &str, json: bool) {
     let task_id = parse_task_id(task_arg);
 
-    match mark_task_done_with_fs(task_id, &RealFileSystem) {
+    let from = match find_task_by_id_with_fs(task_id, &RealFileSystem) {
+        Ok(task) => task,
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    };
+    if status == "done" {
+        run_pre_hook("pre-done", &from, json);
+    }
+
+    match move_task_with_fs(task_id, status, &RealFileSystem) {
         Ok(task) => {
-            println!("✓ task-{}: {}", task.id, task.title);
-            print!("
-================================================================================
-REFLECTION REQUIRED
-================================================================================
+            if json {
+                print_json(serde_json::json!({ "task": task, "from": from.status }));
+            } else {
+                println!("✓ task-{}: {} → {}", task.id, from.status, task.status);
+            }
+            let hook = match status {
+                "done" => Some("post-done"),
+                "claimed" => Some("post-start"),
+                "delivered" => Some("post-deliver"),
+                _ => None,
+            };
+            if let Some(hook) = hook {
+                run_post_hook(hook, &task, json);
+            }
+        }
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    }
+}
 
-Run: /reflect
+fn cmd_done(task_arg: &str, force_reason: Option<&str>, json: bool) {
+    let task_id = parse_task_id(task_arg);
 
-This loads the reflection skill which will guide you through required questions
-about this work session. You MUST complete reflection before continuing.
+    if force_reason.is_some_and(|reason| reason.trim().is_empty()) {
+        exit_with_error(json, "E_USAGE", EXIT_USAGE, "Error: --reason cannot be empty");
+    }
 
-================================================================================
-");
+    if let Ok(task) = find_task_by_id_with_fs(task_id, &RealFileSystem) {
+        run_pre_hook("pre-done", &task, json);
+    }
+
+    let result = match force_reason {
+        Some(reason) => force_task_done_with_fs(task_id, reason, &RealFileSystem),
+        None => mark_task_done_with_fs(task_id, &RealFileSystem),
+    };
+
+    match result {
+        Ok(task) => {
+            if json {
+                print_json(serde_json::json!({ "task": task }));
+                run_post_hook("post-done", &task, json);
+                return;
+            }
+            println!("✓ task-{}: {}", task.id, task.title);
+            // A post-done hook replaces the done_banner text
+            if run_post_hook("post-done", &task, json) {
+                return;
+            }
+            match done_banner_with_fs(&RealFileSystem) {
+                Ok(Some(banner)) => print!("{}{}", banner, if banner.ends_with('\n') { "" } else { "\n" }),
+                Ok(None) => {}
+                Err(err) => eprintln!("Warning: {}", err),
+            }
         }
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            std::process::exit(1);
+        Err(KnechtError::UncheckedCriteria { task_id, unchecked }) if !json => {
+            eprintln!("Error: task-{} has unchecked acceptance criteria [E_UNCHECKED_CRITERIA]:", task_id);
+            for (number, text) in &unchecked {
+                eprintln!("  [ ] {}. {}", number, text);
+            }
+            eprintln!();
+            eprintln!("Verify each item, then: knecht check task-{} <n>...", task_id);
+            eprintln!("Or, if it really is done: knecht done task-{} --force --reason \"...\"", task_id);
+            std::process::exit(KnechtError::UncheckedCriteria { task_id, unchecked }.exit_code());
         }
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
     }
 }
 
-fn cmd_show(task_arg: &str) {
+fn cmd_check(task_arg: &str, items: &[usize], checked: bool, json: bool) {
     let task_id = parse_task_id(task_arg);
 
-    match find_task_by_id_with_fs(task_id, &RealFileSystem) {
+    match check_criteria_items_with_fs(task_id, items, checked, &RealFileSystem) {
         Ok(task) => {
-            println!("Task: task-{}", task.id);
-            println!("Status: {}", task.status);
-            println!("Title: {}", task.title);
-            if let Some(desc) = &task.description {
-                println!("Description: {}", desc);
+            let criteria = criteria_items(&task);
+            if json {
+                print_json(serde_json::json!({ "task": task, "criteria": criteria }));
+                return;
             }
-            if let Some(criteria) = &task.acceptance_criteria {
-                println!("Acceptance Criteria:\n{}", criteria);
+            let done = criteria.iter().filter(|item| item.checked).count();
+            println!("task-{}: {}/{} acceptance criteria checked", task.id, done, criteria.len());
+            for (index, item) in criteria.iter().enumerate() {
+                println!("  [{}] {}. {}", if item.checked { "x" } else { " " }, index + 1, item.text);
             }
+        }
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    }
+}
 
-            // Display blockers
-            let blockers = get_blockers_for_task(task_id);
-            if !blockers.is_empty() {
-                println!("Blocked by:");
-                for blocker_id in &blockers {
-                    if let Ok(blocker_task) = find_task_by_id_with_fs(blocker_id, &RealFileSystem) {
-                        println!("  - task-{} ({}): {}", blocker_task.id, blocker_task.status, blocker_task.title);
-                    }
-                }
+fn cmd_show(task_arg: &str, json: bool) {
+    let task_id = parse_task_id(task_arg);
+
+    let task = match find_task_by_id_with_fs(task_id, &RealFileSystem) {
+        Ok(task) => task,
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    };
+
+    let blocked_by: Vec<Task> = get_blockers_for_task(task_id).iter()
+        .filter_map(|blocker_id| find_task_by_id_with_fs(blocker_id, &RealFileSystem).ok())
+        .collect();
+    let blocks: Vec<Task> = get_tasks_blocked_by(task_id).iter()
+        .filter_map(|blocked_id| find_task_by_id_with_fs(blocked_id, &RealFileSystem).ok())
+        .collect();
+    let pain_entries = get_pain_entries_for_task(task_id, &RealFileSystem).unwrap_or_default();
+    let history = get_history_for_task(task_id, &RealFileSystem).unwrap_or_default();
+
+    if json {
+        print_json(serde_json::json!({
+            "task": task,
+            "criteria": criteria_items(&task),
+            "blocked_by": blocked_by,
+            "blocks": blocks,
+            "pain": pain_entries,
+            "history": history,
+        }));
+        return;
+    }
+
+    println!("Task: task-{}", task.id);
+    println!("Status: {}", task.status);
+    println!("Title: {}", task.title);
+    if let Some(desc) = &task.description {
+        println!("Description: {}", desc);
+    }
+    let criteria = criteria_items(&task);
+    if !criteria.is_empty() {
+        println!("Acceptance Criteria:");
+        for (index, item) in criteria.iter().enumerate() {
+            println!("  [{}] {}. {}", if item.checked { "x" } else { " " }, index + 1, item.text);
+        }
+    }
+
+    // Display blockers
+    if !blocked_by.is_empty() {
+        println!("Blocked by:");
+        for blocker_task in &blocked_by {
+            println!("  - task-{} ({}): {}", blocker_task.id, blocker_task.status, blocker_task.title);
+        }
+    }
+
+    // Display what this task blocks
+    if !blocks.is_empty() {
+        println!("Blocks:");
+        for blocked_task in &blocks {
+            println!("  - task-{} ({}): {}", blocked_task.id, blocked_task.status, blocked_task.title);
+        }
+    }
+
+    // Display pain history from pain log
+    if !pain_entries.is_empty() {
+        let instances: u32 = pain_entries.iter().map(|e| e.count).sum();
+        println!("Pain ({} instance{}):", instances, if instances == 1 { "" } else { "s" });
+        for (index, entry) in pain_entries.iter().enumerate() {
+            let times = if entry.count == 1 { String::new() } else { format!(" (×{})", entry.count) };
+            match &entry.during_task {
+                Some(during) => println!("  {}. {}{} (while on task-{})", index + 1, entry.description, times, during),
+                None => println!("  {}. {}{}", index + 1, entry.description, times),
             }
+        }
+    }
 
-            // Display what this task blocks
-            let blocks = get_tasks_blocked_by(task_id);
-            if !blocks.is_empty() {
-                println!("Blocks:");
-                for blocked_id in &blocks {
-                    if let Ok(blocked_task) = find_task_by_id_with_fs(blocked_id, &RealFileSystem) {
-                        println!("  - task-{} ({}): {}", blocked_task.id, blocked_task.status, blocked_task.title);
-                    }
-                }
+    if !history.is_empty() {
+        println!("History:");
+        for entry in &history {
+            match &entry.detail {
+                Some(detail) => println!("  {} by {}: {}", entry.event, entry.actor, detail),
+                None => println!("  {} by {}", entry.event, entry.actor),
             }
+        }
+    }
+}
+
+fn cmd_start(task_arg: &str, json: bool) {
+    let task_id = parse_task_id(task_arg);
+
+    if let Err(err) = find_task_by_id_with_fs(task_id, &RealFileSystem) {
+        exit_with_knecht_error(json, "Error", &err);
+    }
+
+    let workflow = match workflow_with_fs(&RealFileSystem) {
+        Ok(workflow) => workflow,
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    };
+
+    // Check for open blockers
+    let blockers = get_blockers_for_task(task_id);
+    let mut open_blockers = Vec::new();
 
-            // Display pain history from pain log
-            if let Ok(pain_entries) = get_pain_entries_for_task(task_id, &RealFileSystem) {
-                if !pain_entries.is_empty() {
-                    println!("Pain ({} instance{}):", pain_entries.len(), if pain_entries.len() == 1 { "" } else { "s" });
-                    for entry in &pain_entries {
-                        println!("  {}", entry.description);
-                    }
+    for blocker_id in &blockers {
+        if let Ok(blocker_task) = find_task_by_id_with_fs(blocker_id, &RealFileSystem)
+            && !workflow.is_resolved(&blocker_task.status)
+        {
+            open_blockers.push(blocker_task);
+        }
+    }
+
+    if !open_blockers.is_empty() {
+        if json {
+            print_json(serde_json::json!({
+                "error": {
+                    "code": "E_BLOCKED",
+                    "message": format!("Cannot start task-{}. It is blocked by open tasks", task_id),
+                    "blocked_by": open_blockers,
                 }
-            }
+            }));
+            std::process::exit(EXIT_BLOCKED);
         }
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            std::process::exit(1);
+        eprintln!("Error: Cannot start task-{}. It is blocked by the following open tasks [E_BLOCKED]:", task_id);
+        for blocker_task in &open_blockers {
+            eprintln!("  - task-{} ({}): {}", blocker_task.id, blocker_task.status, blocker_task.title);
         }
+        eprintln!();
+        eprintln!("Complete the blocking tasks first, or use 'knecht unblock' to remove the blocker.");
+        std::process::exit(EXIT_BLOCKED);
     }
+
+    // Claim the task by changing status to "claimed"
+    match mark_task_claimed_with_fs(task_id, &RealFileSystem) {
+        Ok(claimed_task) => {
+            if json {
+                print_json(serde_json::json!({ "task": claimed_task }));
+            } else {
+                println!("Starting work on task-{}: {}", claimed_task.id, claimed_task.title);
+                if let Some(desc) = &claimed_task.description {
+                    println!();
+                    println!("Description:");
+                    println!("{}", desc);
+                }
+            }
+            run_post_hook("post-start", &claimed_task, json);
+        }
+        Err(err @ KnechtError::WipLimitExceeded { .. }) => exit_with_wip_limit(json, &err),
+        Err(err) => exit_with_knecht_error(json, "Error claiming task", &err),
+    }
+}
+
+/// Reports a refused claim with the held work that counts against the limit
+fn exit_with_wip_limit(json: bool, err: &KnechtError) -> ! {
+    if json {
+        print_json(serde_json::json!({
+            "error": { "code": err.code(), "message": err.to_string(), "held": held_tasks(err) }
+        }));
+    } else {
+        eprintln!("Error claiming task: {} [{}]", err, err.code());
+        eprint!("{}", held_work_hint(err));
+    }
+    std::process::exit(err.exit_code());
+}
+
+/// The tasks counting against a WIP limit
+fn held_tasks(err: &KnechtError) -> &[Task] {
+    match err {
+        KnechtError::WipLimitExceeded { held, .. } => held,
+        _ => &[],
+    }
+}
+
+/// Lists the tasks counting against a WIP limit and how to free one up
+fn held_work_hint(err: &KnechtError) -> String {
+    let mut hint = String::new();
+    for task in held_tasks(err) {
+        hint.push_str(&format!("  - task-{} ({}): {}\n", task.id, task.status, task.title));
+    }
+    hint.push_str("\nFinish held work with 'knecht deliver' or 'knecht done', or release it with 'knecht move <task> open'.\n");
+    hint
 }
 
-fn cmd_start(task_arg: &str) {
+fn cmd_stop(task_arg: &str, json: bool) {
     let task_id = parse_task_id(task_arg);
 
-    match find_task_by_id_with_fs(task_id, &RealFileSystem) {
-        Ok(_task) => {
-            // Check for open blockers
-            let blockers = get_blockers_for_task(task_id);
-            let mut open_blockers = Vec::new();
+    if let Err(err) = find_task_by_id_with_fs(task_id, &RealFileSystem) {
+        exit_with_knecht_error(json, "Error", &err);
+    }
 
-            for blocker_id in &blockers {
-                if let Ok(blocker_task) = find_task_by_id_with_fs(blocker_id, &RealFileSystem)
-                    && blocker_task.status != "done" {
-                        open_blockers.push((blocker_id.clone(), blocker_task));
-                    }
+    match stop_sessions_with_fs(task_id, false, &RealFileSystem) {
+        Ok(stopped) => {
+            if json {
+                print_json(serde_json::json!({ "task_id": task_id, "stopped": stopped }));
+            } else if let Some(session) = stopped.first() {
+                println!("Stopped work on task-{} after {}", task_id, format_duration(session.duration_secs(now_secs())));
+            } else {
+                println!("No running session on task-{}", task_id);
             }
+        }
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    }
+}
 
-            if !open_blockers.is_empty() {
-                eprintln!("Error: Cannot start task-{}. It is blocked by the following open tasks:", task_id);
-                for (blocker_id, blocker_task) in &open_blockers {
-                    eprintln!("  - task-{} ({}): {}", blocker_id, blocker_task.status, blocker_task.title);
-                }
-                eprintln!();
-                eprintln!("Complete the blocking tasks first, or use 'knecht unblock' to remove the blocker.");
-                std::process::exit(1);
-            }
-
-            // Claim the task by changing status to "claimed"
-            match mark_task_claimed_with_fs(task_id, &RealFileSystem) {
-                Ok(claimed_task) => {
-                    println!("Starting work on task-{}: {}", claimed_task.id, claimed_task.title);
-                    if let Some(desc) = &claimed_task.description {
-                        println!();
-                        println!("Description:");
-                        println!("{}", desc);
-                    }
-                }
-                Err(err) => {
-                    eprintln!("Error claiming task: {}", err);
-                    std::process::exit(1);
+fn cmd_time(task_arg: Option<&str>, since_secs: Option<u64>, json: bool) {
+    let now = now_secs();
+    let since = since_secs.map_or(0, |secs| now.saturating_sub(secs));
+
+    let sessions = match read_sessions_with_fs(&RealFileSystem) {
+        Ok(sessions) => sessions,
+        Err(err) => exit_with_knecht_error(json, "Error reading sessions", &err),
+    };
+    // Sessions that overlap the window
+    let sessions: Vec<WorkSession> = sessions.into_iter()
+        .filter(|s| s.stop.is_none_or(|stop| stop > since))
+        .collect();
+
+    match task_arg {
+        Some(task_arg) => {
+            let task_id = parse_task_id(task_arg);
+            let task = match find_task_by_id_with_fs(task_id, &RealFileSystem) {
+                Ok(task) => task,
+                Err(err) => exit_with_knecht_error(json, "Error", &err),
+            };
+            let sessions: Vec<WorkSession> = sessions.into_iter().filter(|s| s.task_id == task.id).collect();
+            let total: u64 = sessions.iter().map(|s| s.duration_since(since, now)).sum();
+
+            if json {
+                let sessions: Vec<serde_json::Value> = sessions.iter()
+                    .map(|s| serde_json::json!({ "session": s, "duration_secs": s.duration_since(since, now) }))
+                    .collect();
+                print_json(serde_json::json!({ "task": task, "total_secs": total, "sessions": sessions }));
+                return;
+            }
+
+            println!("task-{}: {}", task.id, task.title);
+            if sessions.is_empty() {
+                println!("No work sessions recorded");
+                return;
+            }
+            for session in &sessions {
+                let stop = session.stop.map_or_else(|| "running".to_string(), format_timestamp);
+                println!("  {} – {}  {:>8}  {}", format_timestamp(session.start), stop, format_duration(session.duration_since(since, now)), session.actor);
+            }
+            println!("Total: {}", format_duration(total));
+        }
+        None => {
+            let mut totals: Vec<(String, u64)> = Vec::new();
+            for session in &sessions {
+                let duration = session.duration_since(since, now);
+                match totals.iter_mut().find(|(id, _)| *id == session.task_id) {
+                    Some((_, total)) => *total += duration,
+                    None => totals.push((session.task_id.clone(), duration)),
                 }
             }
+            totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            let tasks = read_tasks_with_fs(&RealFileSystem).unwrap_or_default();
+            let title = |id: &str| tasks.iter().find(|t| t.id == id).map(|t| t.title.clone()).unwrap_or_default();
+
+            if json {
+                let summary: Vec<serde_json::Value> = totals.iter()
+                    .map(|(id, total)| serde_json::json!({ "task_id": id, "title": title(id), "total_secs": total }))
+                    .collect();
+                print_json(serde_json::json!({ "since": since_secs.map(|_| since), "tasks": summary }));
+                return;
+            }
+
+            if totals.is_empty() {
+                println!("No work sessions recorded");
+                return;
+            }
+            for (id, total) in &totals {
+                println!("{:>8}  task-{}  {}", format_duration(*total), id, title(id));
+            }
+            println!("Total: {}", format_duration(totals.iter().map(|(_, total)| total).sum()));
+        }
+    }
+}
+
+fn cmd_report(weeks: usize, json: bool) {
+    let report = match flow_report_with_fs(weeks, now_secs(), &RealFileSystem) {
+        Ok(report) => report,
+        Err(err) => exit_with_knecht_error(json, "Error building report", &err),
+    };
+
+    if json {
+        print_json(serde_json::json!({ "report": report }));
+        return;
+    }
+
+    println!("Throughput (tasks done per week)");
+    for week in &report.throughput {
+        println!("  {}  {:>3}", &format_timestamp(week.week_start)[..10], week.done);
+    }
+
+    println!();
+    println!("{:<42} {:>4} {:>8} {:>8} {:>8} {:>8}", "", "n", "p50", "p85", "p95", "max");
+    let rows = [
+        ("Lead time (created → done)", &report.lead_time),
+        ("Cycle time (claimed → done)", &report.cycle_time),
+        ("Awaiting verification (delivered → done)", &report.awaiting_verification),
+    ];
+    for (label, stats) in rows {
+        match stats {
+            Some(s) => println!("{:<42} {:>4} {:>8} {:>8} {:>8} {:>8}", label, s.count,
+                format_duration(s.p50), format_duration(s.p85), format_duration(s.p95), format_duration(s.max)),
+            None => println!("{:<42} {:>4}", label, 0),
         }
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            std::process::exit(1);
+    }
+
+    println!();
+    println!("Open work aging");
+    if report.aging.is_empty() {
+        println!("  No open work");
+    }
+    for task in &report.aging {
+        let age = task.age_secs.map_or_else(|| "?".to_string(), format_duration);
+        println!("  {:>8}  task-{}  {:<10} {}", age, task.task_id, task.status, task.title);
+    }
+
+    if !report.friction.is_empty() {
+        println!();
+        println!("Friction (pain felt while working on a task)");
+        for source in &report.friction {
+            println!("  {:>4} pain on {:>3} task{}  task-{}  {}", source.pain, source.tasks,
+                if source.tasks == 1 { " " } else { "s" }, source.task_id, source.title.as_deref().unwrap_or("(deleted)"));
         }
     }
 }
 
-fn cmd_pain(task_arg: &str, description: &str) {
+fn cmd_pain(task_arg: &str, description: &str, during_arg: Option<&str>, json: bool) {
     let task_id = parse_task_id(task_arg);
+    let during = match during_arg {
+        Some(arg) => Some(parse_task_id(arg).to_string()),
+        None => current_task_for_actor_with_fs(&current_actor(), &RealFileSystem).unwrap_or(None),
+    };
 
-    match increment_pain_count_with_fs(task_id, Some(description), &RealFileSystem) {
+    match record_pain_with_fs(task_id, Some(description), during.as_deref(), &RealFileSystem) {
         Ok(task) => {
-            println!("Incremented pain count for task-{}: {}", task.id, task.title);
+            let pain_count = get_pain_count_for_task(&task.id, &RealFileSystem).unwrap_or(0);
+            let task = Task { pain_count: Some(pain_count), ..task };
+            if json {
+                print_json(serde_json::json!({ "task": task }));
+            } else {
+                println!("Incremented pain count for task-{}: {}", task.id, task.title);
+            }
+            run_post_hook("post-pain", &task, json);
         }
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            std::process::exit(1);
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    }
+}
+
+fn cmd_retract_pain(task_id: &str, entry: usize, reason: Option<&str>, json: bool) {
+    match retract_pain_entry_with_fs(task_id, entry, reason, &RealFileSystem) {
+        Ok(retracted) => {
+            if json {
+                print_json(serde_json::json!({ "retracted": retracted }));
+            } else {
+                println!("Retracted pain entry {} from task-{}: {}", entry, task_id, retracted.description);
+            }
         }
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
     }
 }
 
-fn cmd_delete(task_arg: &str) {
+fn cmd_move_pain(entry_arg: &str, to_arg: &str, json: bool) {
+    let (task_id, entry) = match parse_pain_entry_ref(entry_arg) {
+        Ok(reference) => reference,
+        Err(message) => exit_with_error(json, "E_USAGE", EXIT_USAGE, &format!("Error: {}", message)),
+    };
+    let to_task = parse_task_id(to_arg);
+
+    match move_pain_entry_with_fs(&task_id, entry, to_task, &RealFileSystem) {
+        Ok(moved) => {
+            if json {
+                print_json(serde_json::json!({ "moved": moved }));
+            } else {
+                println!("Moved pain entry {} from task-{} to task-{}: {}", entry, task_id, to_task, moved.description);
+            }
+        }
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    }
+}
+
+fn cmd_delete(task_arg: &str, json: bool) {
     let task_id = parse_task_id(task_arg);
 
     match delete_task_with_fs(task_id, &RealFileSystem) {
         Ok(task) => {
+            if json {
+                print_json(serde_json::json!({ "task": task }));
+                return;
+            }
             println!("Deleted task-{}: {}", task.id, task.title);
         }
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            std::process::exit(1);
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    }
+}
+
+fn cmd_estimate(task_arg: &str, points: f64, json: bool) {
+    let task_id = parse_task_id(task_arg);
+
+    match estimate_task_with_fs(task_id, points, &RealFileSystem) {
+        Ok(task) => {
+            if json {
+                print_json(serde_json::json!({ "task": task, "estimate": points }));
+            } else {
+                println!("Estimated task-{} at {} point{}: {}", task.id, points, if points == 1.0 { "" } else { "s" }, task.title);
+            }
+        }
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    }
+}
+
+/// The strategy picked by `--strategy`, else `next_strategy`
+fn selected_strategy(strategy_name: Option<&str>, json: bool) -> Box<dyn PriorityStrategy> {
+    if let Some(name) = strategy_name
+        && strategy_for_name(name).is_none() {
+            exit_with_error(json, "E_USAGE", EXIT_USAGE, &format!("Error: unknown strategy '{}' (strategies: {})", name, STRATEGY_NAMES.join(", ")));
         }
+    match priority_strategy_with_fs(strategy_name, &RealFileSystem) {
+        Ok(strategy) => strategy,
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
     }
 }
 
-fn cmd_next() {
-    match find_next_task_with_fs(&RealFileSystem) {
+fn cmd_queue(strategy_name: Option<&str>, limit: Option<usize>, statuses: &[String], json: bool) {
+    let strategy = selected_strategy(strategy_name, json);
+    print_queue(strategy.as_ref(), limit, statuses, json);
+}
+
+fn print_queue(strategy: &dyn PriorityStrategy, limit: Option<usize>, statuses: &[String], json: bool) {
+    let queue: Vec<QueueEntry> = match rank_tasks_with_fs(strategy, &RealFileSystem) {
+        Ok(queue) => queue.into_iter()
+            .filter(|entry| statuses.is_empty() || statuses.contains(&entry.task.status))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect(),
+        Err(err) => exit_with_knecht_error(json, "Error reading tasks", &err),
+    };
+
+    if json {
+        print_json(serde_json::json!({ "strategy": strategy.name(), "queue": queue }));
+        return;
+    }
+
+    if queue.is_empty() {
+        println!("No open tasks");
+        return;
+    }
+    for (position, entry) in queue.iter().enumerate() {
+        let unblocks = entry.unblocks.as_ref().map(|id| format!(" (unblocks task-{})", id)).unwrap_or_default();
+        let status = if entry.task.status == "delivered" { " [delivered]" } else { "" };
+        println!("{:>3}. task-{}  {:>8.2}  {}{}{}", position + 1, entry.task.id, entry.score, entry.task.title, status, unblocks);
+    }
+}
+
+fn cmd_claim_next(agent: Option<String>, strategy_name: Option<&str>, json: bool) {
+    let agent = agent.map(|a| a.trim().to_string()).unwrap_or_else(current_actor);
+    if agent.is_empty() {
+        exit_with_error(json, "E_USAGE", EXIT_USAGE, "Error: --agent cannot be empty");
+    }
+    let strategy = selected_strategy(strategy_name, json);
+
+    match claim_next_task_with_fs(strategy.as_ref(), &agent, &RealFileSystem) {
         Ok(Some(task)) => {
+            if json {
+                print_json(serde_json::json!({ "task": task, "agent": agent }));
+            } else {
+                println!("{} claimed task-{}: {}", agent, task.id, task.title);
+                if let Some(desc) = &task.description {
+                    println!();
+                    println!("Description:");
+                    println!("{}", desc);
+                }
+            }
+            run_post_hook("post-start", &task, json);
+        }
+        Ok(None) => exit_with_error(json, "E_QUEUE_EMPTY", EXIT_QUEUE_EMPTY, "No unclaimed, unblocked tasks to claim"),
+        Err(err @ KnechtError::WipLimitExceeded { .. }) => exit_with_wip_limit(json, &err),
+        Err(err) => exit_with_knecht_error(json, "Error claiming task", &err),
+    }
+}
+
+fn cmd_next(strategy_name: Option<&str>, explain: bool, count: Option<usize>, json: bool) {
+    let strategy = selected_strategy(strategy_name, json);
+
+    // At the WIP limit the next step is to verify, finish or release held work, not to start more
+    let wip_limit = match check_wip_limits_with_fs(&current_actor(), &RealFileSystem) {
+        Ok(()) => None,
+        Err(err @ KnechtError::WipLimitExceeded { .. }) => Some(err),
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
+    };
+
+    if count.is_some() {
+        match &wip_limit {
+            Some(err) => {
+                if !json {
+                    print!("{}", wip_limit_notice(err));
+                    println!();
+                }
+                print_queue(strategy.as_ref(), count, &["delivered".to_string()], json);
+            }
+            None => print_queue(strategy.as_ref(), count, &[], json),
+        }
+        return;
+    }
+
+    let explanation = match explain_next_task_with_fs(strategy.as_ref(), &RealFileSystem) {
+        Ok(explanation) => explanation,
+        Err(err) => exit_with_knecht_error(json, "Error reading tasks", &err),
+    };
+
+    // Delivered tasks are still suggested: verifying them frees up WIP
+    let suggestion = explanation.suggestion.as_ref()
+        .filter(|task| wip_limit.is_none() || task.status == "delivered");
+    match suggestion {
+        Some(task) => {
+            let pain_count = get_pain_count_for_task(&task.id, &RealFileSystem).unwrap_or(0);
+            if json {
+                let task = Task { pain_count: Some(pain_count), ..task.clone() };
+                match explain {
+                    true => print_json(serde_json::json!({ "task": task, "explanation": explanation })),
+                    false => print_json(serde_json::json!({ "task": task })),
+                }
+                return;
+            }
             println!("Suggested next task: task-{}", task.id);
             println!("Title: {}", task.title);
             if let Some(desc) = &task.description {
                 println!("\nDescription:\n{}", desc);
             }
-            let pain_count = get_pain_count_for_task(&task.id, &RealFileSystem).unwrap_or(0);
             if pain_count > 0 {
                 println!("\n(pain count: {})", pain_count);
             }
         }
-        Ok(None) => {
-            println!("No open tasks");
+        None => {
+            if json {
+                let mut value = serde_json::json!({ "task": null });
+                if let Some(err) = &wip_limit {
+                    value["wip_limit"] = serde_json::json!({ "message": err.to_string(), "held": held_tasks(err) });
+                }
+                if explain {
+                    value["explanation"] = serde_json::json!(explanation);
+                }
+                print_json(value);
+                return;
+            }
+            match &wip_limit {
+                Some(err) => print!("{}", wip_limit_notice(err)),
+                None => println!("No open tasks"),
+            }
+        }
+    }
+
+    if explain {
+        println!();
+        print!("{}", format_next_explanation(&explanation));
+    }
+}
+
+/// What `knecht next` says instead of suggesting new work at a WIP limit
+fn wip_limit_notice(err: &KnechtError) -> String {
+    format!("WIP limit reached: {}\n\n{}", err, held_work_hint(err))
+}
+
+/// The reasoning behind a `knecht next` suggestion, for `--explain`
+fn format_next_explanation(explanation: &NextExplanation) -> String {
+    let mut out = format!("Why (strategy: {}):\n", explanation.strategy);
+    let line = |task: &ScoredTask| format!("  {:>8.2}  task-{}  {}{}\n", task.score, task.task_id, task.title, if task.blocked { " (blocked)" } else { "" });
+
+    if !explanation.delivered.is_empty() {
+        out.push_str("\nDelivered tasks await verification and come before open work:\n");
+        explanation.delivered.iter().for_each(|task| out.push_str(&line(task)));
+    }
+
+    out.push_str("\nOpen candidates, best first:\n");
+    if explanation.candidates.is_empty() {
+        out.push_str("  (none)\n");
+    }
+    explanation.candidates.iter().for_each(|task| out.push_str(&line(task)));
+
+    if let Some((top, blockers)) = explanation.blocker_chain.split_first() {
+        let chain: Vec<String> = explanation.blocker_chain.iter().map(|t| format!("task-{}", t.task_id)).collect();
+        out.push_str(&format!("\ntask-{} is blocked; walked to its best open blocker: {}\n", top.task_id, chain.join(" → ")));
+        blockers.iter().for_each(|task| out.push_str(&line(task)));
+    }
+
+    if let Some(task) = &explanation.suggestion {
+        if explanation.pain.is_empty() {
+            out.push_str(&format!("\nNo pain recorded on task-{}\n", task.id));
+        } else {
+            out.push_str(&format!("\nPain on task-{}:\n", task.id));
         }
-        Err(err) => {
-            eprintln!("Error reading tasks: {}", err);
-            std::process::exit(1);
+        for contribution in &explanation.pain {
+            let entry = &contribution.entry;
+            let source = match entry.source_type {
+                PainSourceType::Manual => "manual",
+                PainSourceType::Skip => "skip",
+                PainSourceType::Sentry => "sentry",
+            };
+            out.push_str(&format!("  {:>8.2}  {}  {:<6}  {}\n", contribution.weight, format_timestamp(entry.timestamp), source, entry.description));
         }
     }
+    out
 }
 
-fn cmd_update(task_arg: &str, new_title: Option<String>, new_description: Option<String>, new_acceptance_criteria: Option<String>) {
+fn cmd_update(task_arg: &str, new_title: Option<String>, new_description: Option<String>, new_acceptance_criteria: Option<String>, json: bool) {
     let task_id = parse_task_id(task_arg);
 
     // Check that at least one flag was provided
     if new_title.is_none() && new_description.is_none() && new_acceptance_criteria.is_none() {
-        eprintln!("Error: Must provide at least one of --title, --description, or --acceptance-criteria");
+        if json {
+            exit_with_error(json, "E_USAGE", EXIT_USAGE, "Must provide at least one of --title, --description, or --acceptance-criteria");
+        }
+        eprintln!("Error: Must provide at least one of --title, --description, or --acceptance-criteria [E_USAGE]");
         eprintln!("Usage: knecht update <task-id> [--title <title>] [--description <description>] [--acceptance-criteria <criteria>]");
-        std::process::exit(1);
+        std::process::exit(EXIT_USAGE);
     }
 
     // Convert Option<String> to Option<Option<String>> for description
@@ -441,28 +1686,27 @@ fn cmd_update(task_arg: &str, new_title: Option<String>, new_description: Option
 
     match update_task_with_fs(task_id, new_title, desc_update, criteria_update, &RealFileSystem) {
         Ok(task) => {
+            if json {
+                print_json(serde_json::json!({ "task": task }));
+                return;
+            }
             println!("Updated task-{}", task.id);
         }
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            std::process::exit(1);
-        }
+        Err(err) => exit_with_knecht_error(json, "Error", &err),
     }
 }
 
-fn cmd_block(blocked_task_arg: &str, blocker_task_arg: &str) {
+fn cmd_block(blocked_task_arg: &str, blocker_task_arg: &str, json: bool) {
     let blocked_task_id = parse_task_id(blocked_task_arg);
     let blocker_task_id = parse_task_id(blocker_task_arg);
 
     // Verify both tasks exist
     if let Err(err) = find_task_by_id_with_fs(blocked_task_id, &RealFileSystem) {
-        eprintln!("Error: {}", err);
-        std::process::exit(1);
+        exit_with_knecht_error(json, "Error", &err);
     }
 
     if let Err(err) = find_task_by_id_with_fs(blocker_task_id, &RealFileSystem) {
-        eprintln!("Error: {}", err);
-        std::process::exit(1);
+        exit_with_knecht_error(json, "Error", &err);
     }
 
     // Add blocker relationship
@@ -473,33 +1717,33 @@ fn cmd_block(blocked_task_arg: &str, blocker_task_arg: &str) {
     content.push_str(&blocker_line);
 
     if let Err(e) = fs::write(blockers_path, content) {
-        eprintln!("Failed to write blockers file: {}", e);
-        std::process::exit(1);
+        exit_with_error(json, "E_IO", EXIT_IO, &format!("Failed to write blockers file: {}", e));
     }
 
+    if json {
+        print_json(serde_json::json!({ "blocked": blocked_task_id, "blocker": blocker_task_id }));
+        return;
+    }
     println!("Blocker added: task-{} is blocked by task-{}", blocked_task_id, blocker_task_id);
 }
 
-fn cmd_unblock(blocked_task_arg: &str, blocker_task_arg: &str) {
+fn cmd_unblock(blocked_task_arg: &str, blocker_task_arg: &str, json: bool) {
     let blocked_task_id = parse_task_id(blocked_task_arg);
     let blocker_task_id = parse_task_id(blocker_task_arg);
+    let not_blocked = format!("Error: task-{} is not blocked by task-{}", blocked_task_id, blocker_task_id);
 
     // Read blockers file
     let blockers_path = ".knecht/blockers";
     let content = match fs::read_to_string(blockers_path) {
         Ok(c) => c,
-        Err(_) => {
-            eprintln!("Error: task-{} is not blocked by task-{}", blocked_task_id, blocker_task_id);
-            std::process::exit(1);
-        }
+        Err(_) => exit_with_error(json, "E_NOT_BLOCKED", EXIT_NOT_BLOCKED, &not_blocked),
     };
 
     let blocker_line = format!("task-{}|task-{}", blocked_task_id, blocker_task_id);
 
     // Check if the relationship exists
     if !content.contains(&blocker_line) {
-        eprintln!("Error: task-{} is not blocked by task-{}", blocked_task_id, blocker_task_id);
-        std::process::exit(1);
+        exit_with_error(json, "E_NOT_BLOCKED", EXIT_NOT_BLOCKED, &not_blocked);
     }
 
     // Remove the blocker line
@@ -516,13 +1760,107 @@ fn cmd_unblock(blocked_task_arg: &str, blocker_task_arg: &str) {
     };
 
     if let Err(e) = fs::write(blockers_path, new_content) {
-        eprintln!("Failed to write blockers file: {}", e);
-        std::process::exit(1);
+        exit_with_error(json, "E_IO", EXIT_IO, &format!("Failed to write blockers file: {}", e));
     }
 
+    if json {
+        print_json(serde_json::json!({ "unblocked": blocked_task_id, "blocker": blocker_task_id }));
+        return;
+    }
     println!("Blocker removed: task-{} is no longer blocked by task-{}", blocked_task_id, blocker_task_id);
 }
 
+fn cmd_export(format: ExportFormat, output: Option<PathBuf>, json: bool) {
+    let records = match export_records_with_fs(&RealFileSystem) {
+        Ok(records) => records,
+        Err(err) => exit_with_knecht_error(json, "Error exporting", &err),
+    };
+
+    let writer: Box<dyn io::Write> = match &output {
+        Some(path) => match fs::File::create(path) {
+            Ok(file) => Box::new(io::BufWriter::new(file)),
+            Err(e) => exit_with_knecht_error(json, "Error exporting", &KnechtError::from(e)),
+        },
+        None => Box::new(io::stdout().lock()),
+    };
+    let result = match format {
+        ExportFormat::Jsonl => write_jsonl(&records, writer),
+    };
+    if let Err(err) = result {
+        exit_with_knecht_error(json, "Error exporting", &err);
+    }
+
+    if let Some(path) = output {
+        if json {
+            print_json(serde_json::json!({ "exported": records.len(), "path": path }));
+        } else {
+            println!("Exported {} records to {}", records.len(), path.display());
+        }
+    }
+}
+
+fn cmd_import(file: &Path, mode: ImportModeArg, remap_ids: bool, json: bool) {
+    let records = if file.ends_with("-") {
+        read_jsonl(io::stdin().lock())
+    } else {
+        fs::File::open(file)
+            .map_err(KnechtError::from)
+            .and_then(|f| read_jsonl(io::BufReader::new(f)))
+    };
+    let records = match records {
+        Ok(records) => records,
+        Err(err) => exit_with_knecht_error(json, "Error reading import", &err),
+    };
+
+    let mode = match mode {
+        ImportModeArg::Merge => ImportMode::Merge,
+        ImportModeArg::Replace => ImportMode::Replace,
+    };
+
+    match import_records_with_fs(&records, mode, remap_ids, &RealFileSystem) {
+        Ok(summary) => {
+            if json {
+                print_json(serde_json::json!({ "imported": summary }));
+                return;
+            }
+            println!("Imported {} tasks, {} blockers, {} pain entries, {} Sentry mappings, {} history entries, {} sessions",
+                summary.tasks, summary.blockers, summary.pain_entries, summary.sentry_mappings, summary.history_entries, summary.sessions);
+            for (old_id, new_id) in &summary.remapped_ids {
+                println!("  task-{} -> task-{}", old_id, new_id);
+            }
+        }
+        Err(err) => exit_with_knecht_error(json, "Error importing", &err),
+    }
+}
+
+fn cmd_compact_pain(json: bool) {
+    match compact_pain_log_with_fs(&RealFileSystem) {
+        Ok((before, after)) => {
+            if json {
+                print_json(serde_json::json!({ "before": before, "after": after }));
+            } else if before == after {
+                println!("Pain log already compact ({} entries)", before);
+            } else {
+                println!("Compacted pain log from {} to {} entries", before, after);
+            }
+        }
+        Err(err) => exit_with_knecht_error(json, "Error compacting pain log", &err),
+    }
+}
+
+fn cmd_migrate(to: TaskFormat, json: bool) {
+    match migrate_task_format_with_fs(to.name(), &RealFileSystem) {
+        Ok(count) => {
+            if json {
+                print_json(serde_json::json!({ "migrated": count, "format": to.name() }));
+            } else {
+                println!("Migrated {} tasks to {} format", count, to.name());
+            }
+        }
+        Err(err) => exit_with_knecht_error(json, "Error migrating", &err),
+    }
+}
+
 /// Returns a list of task IDs that block the given task
 fn get_blockers_for_task(task_id: &str) -> Vec<String> {
     let blockers_path = ".knecht/blockers";
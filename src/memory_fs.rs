@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{self, BufRead, Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::FileSystem;
+
+/// Filesystem operations that can be made to fail with [`MemoryFileSystem::fail_nth`]
+/// and friends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FsOperation {
+    Open,
+    /// Reading from a reader returned by `open`; counted once per opened reader
+    Read,
+    Create,
+    CreateDir,
+    Append,
+    /// A single `write` call on any writer returned by `create` or `append`
+    Write,
+    ReadDir,
+    RemoveFile,
+}
+
+#[derive(Debug, Clone)]
+enum Trigger {
+    Always,
+    Nth(usize),
+    Path(PathBuf),
+}
+
+#[derive(Default)]
+struct State {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    dirs: BTreeSet<PathBuf>,
+    faults: Vec<(FsOperation, Trigger)>,
+    calls: HashMap<FsOperation, usize>,
+}
+
+impl State {
+    /// Counts the call and returns an error if a configured fault matches it
+    fn check(&mut self, op: FsOperation, path: &Path) -> io::Result<()> {
+        let count = self.calls.entry(op).or_insert(0);
+        *count += 1;
+        let count = *count;
+
+        let triggered = self.faults.iter().any(|(fault_op, trigger)| {
+            *fault_op == op && match trigger {
+                Trigger::Always => true,
+                Trigger::Nth(n) => *n == count,
+                Trigger::Path(p) => p == path,
+            }
+        });
+
+        if triggered {
+            return Err(io::Error::other(format!("injected {:?} failure at {}", op, path.display())));
+        }
+        Ok(())
+    }
+
+    fn parent_exists(&self, path: &Path) -> bool {
+        match path.parent() {
+            // The working directory and the filesystem root always exist
+            Some(parent) if !parent.as_os_str().is_empty() && parent.parent().is_some() => self.dirs.contains(parent),
+            _ => true,
+        }
+    }
+}
+
+/// In-memory [`FileSystem`] for tests and tools built on knecht.
+///
+/// Behaves like a real filesystem: `create` truncates, `append` extends, writing into a
+/// missing directory fails, and `read_dir` lists the direct children of a directory in
+/// sorted order. Faults can be injected per operation to exercise error paths, e.g.
+/// `MemoryFileSystem::new().fail_nth(FsOperation::Write, 2)` makes the second write fail
+/// and leaves the first one in place. Clones share the same contents.
+#[derive(Clone, Default)]
+pub struct MemoryFileSystem {
+    state: Arc<Mutex<State>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, creating its parent directories
+    pub fn with_file(self, path: impl AsRef<Path>, content: &str) -> Self {
+        let path = normalize(path.as_ref());
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(parent) = path.parent() {
+                insert_dir_all(&mut state.dirs, parent);
+            }
+            state.files.insert(path, content.as_bytes().to_vec());
+        }
+        self
+    }
+
+    /// Adds a directory and its parents
+    pub fn with_dir(self, path: impl AsRef<Path>) -> Self {
+        insert_dir_all(&mut self.state.lock().unwrap().dirs, &normalize(path.as_ref()));
+        self
+    }
+
+    /// Makes every call of `op` fail
+    pub fn fail_always(self, op: FsOperation) -> Self {
+        self.state.lock().unwrap().faults.push((op, Trigger::Always));
+        self
+    }
+
+    /// Makes the `n`th call of `op` (counting from 1) fail; other calls succeed
+    pub fn fail_nth(self, op: FsOperation, n: usize) -> Self {
+        self.state.lock().unwrap().faults.push((op, Trigger::Nth(n)));
+        self
+    }
+
+    /// Makes `op` fail whenever it targets `path`
+    pub fn fail_on_path(self, op: FsOperation, path: impl AsRef<Path>) -> Self {
+        self.state.lock().unwrap().faults.push((op, Trigger::Path(normalize(path.as_ref()))));
+        self
+    }
+
+    /// Removes all injected faults, e.g. to inspect state after a failure
+    pub fn clear_faults(&self) {
+        self.state.lock().unwrap().faults.clear();
+    }
+
+    /// Returns how many times `op` has been called
+    pub fn call_count(&self, op: FsOperation) -> usize {
+        self.state.lock().unwrap().calls.get(&op).copied().unwrap_or(0)
+    }
+
+    /// Returns the contents of a file, if it exists
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> Option<String> {
+        self.state.lock().unwrap().files.get(&normalize(path.as_ref()))
+            .map(|content| String::from_utf8_lossy(content).into_owned())
+    }
+
+    /// Returns the paths of all files, sorted
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.state.lock().unwrap().files.keys().cloned().collect()
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.components().filter(|c| *c != Component::CurDir).collect()
+}
+
+fn insert_dir_all(dirs: &mut BTreeSet<PathBuf>, path: &Path) {
+    for ancestor in path.ancestors() {
+        if ancestor.as_os_str().is_empty() {
+            break;
+        }
+        dirs.insert(ancestor.to_path_buf());
+    }
+}
+
+struct MemoryReader {
+    inner: Cursor<Vec<u8>>,
+    fail: bool,
+}
+
+impl Read for MemoryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.fail {
+            return Err(io::Error::other("injected Read failure"));
+        }
+        self.inner.read(buf)
+    }
+}
+
+impl BufRead for MemoryReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.fail {
+            return Err(io::Error::other("injected Read failure"));
+        }
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
+struct MemoryWriter {
+    state: Arc<Mutex<State>>,
+    path: PathBuf,
+}
+
+impl Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        state.check(FsOperation::Write, &self.path)?;
+        state.files.entry(self.path.clone()).or_default().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn exists(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        let state = self.state.lock().unwrap();
+        state.files.contains_key(&path) || state.dirs.contains(&path)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn BufRead>> {
+        let path = normalize(path);
+        let mut state = self.state.lock().unwrap();
+        state.check(FsOperation::Open, &path)?;
+        if state.dirs.contains(&path) {
+            return Err(io::Error::other(format!("{} is a directory", path.display())));
+        }
+        let content = state.files.get(&path).cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display())))?;
+        let fail = state.check(FsOperation::Read, &path).is_err();
+        Ok(Box::new(MemoryReader { inner: Cursor::new(content), fail }))
+    }
+
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write>> {
+        let path = normalize(path);
+        let mut state = self.state.lock().unwrap();
+        state.check(FsOperation::Create, &path)?;
+        if state.dirs.contains(&path) {
+            return Err(io::Error::other(format!("{} is a directory", path.display())));
+        }
+        if !state.parent_exists(&path) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("parent directory of {} not found", path.display())));
+        }
+        state.files.insert(path.clone(), Vec::new());
+        Ok(Box::new(MemoryWriter { state: Arc::clone(&self.state), path }))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut state = self.state.lock().unwrap();
+        state.check(FsOperation::CreateDir, &path)?;
+        if let Some(file) = path.ancestors().find(|a| state.files.contains_key(*a)) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} is a file", file.display())));
+        }
+        insert_dir_all(&mut state.dirs, &path);
+        Ok(())
+    }
+
+    fn create_new(&self, path: &Path) -> io::Result<Box<dyn Write>> {
+        let path = normalize(path);
+        let mut state = self.state.lock().unwrap();
+        state.check(FsOperation::Create, &path)?;
+        if state.files.contains_key(&path) || state.dirs.contains(&path) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", path.display())));
+        }
+        if !state.parent_exists(&path) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("parent directory of {} not found", path.display())));
+        }
+        state.files.insert(path.clone(), Vec::new());
+        Ok(Box::new(MemoryWriter { state: Arc::clone(&self.state), path }))
+    }
+
+    fn append(&self, path: &Path) -> io::Result<Box<dyn Write>> {
+        let path = normalize(path);
+        let mut state = self.state.lock().unwrap();
+        state.check(FsOperation::Append, &path)?;
+        if state.dirs.contains(&path) {
+            return Err(io::Error::other(format!("{} is a directory", path.display())));
+        }
+        if !state.parent_exists(&path) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("parent directory of {} not found", path.display())));
+        }
+        state.files.entry(path.clone()).or_default();
+        Ok(Box::new(MemoryWriter { state: Arc::clone(&self.state), path }))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.state.lock().unwrap().dirs.contains(&normalize(path))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.state.lock().unwrap().files.contains_key(&normalize(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let path = normalize(path);
+        let mut state = self.state.lock().unwrap();
+        state.check(FsOperation::ReadDir, &path)?;
+        if !state.dirs.contains(&path) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("directory {} not found", path.display())));
+        }
+        let mut entries: Vec<PathBuf> = state.files.keys()
+            .chain(state.dirs.iter())
+            .filter(|p| p.parent() == Some(path.as_path()))
+            .cloned()
+            .collect();
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut state = self.state.lock().unwrap();
+        state.check(FsOperation::RemoveFile, &path)?;
+        if state.dirs.contains(&path) {
+            return Err(io::Error::other(format!("{} is a directory", path.display())));
+        }
+        match state.files.remove(&path) {
+            Some(_) => Ok(()),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display()))),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use crate::config::invalid;
+use crate::{
+    get_all_pain_scores, read_blockers_with_fs, read_config_with_fs, read_history_with_fs, read_pain_entries_with_fs,
+    read_tasks_with_fs, workflow_with_fs, FileSystem, HistoryEntry, KnechtError, PainEntry, Task, Workflow,
+};
+
+/// Everything a strategy may look at when scoring tasks for `knecht next`
+pub struct PriorityContext {
+    pub tasks: Vec<Task>,
+    pub pain_entries: Vec<PainEntry>,
+    /// Pain per task, decayed by `pain_half_life`
+    pub pain_scores: HashMap<String, f64>,
+    /// (blocked task ID, blocker task ID) pairs
+    pub blockers: Vec<(String, String)>,
+    pub history: Vec<HistoryEntry>,
+    pub workflow: Workflow,
+    pub now: u64,
+    created: HashMap<String, u64>,
+    estimates: HashMap<String, f64>,
+}
+
+impl PriorityContext {
+    pub fn load_with_fs(now: u64, fs: &dyn FileSystem) -> Result<Self, KnechtError> {
+        let history = read_history_with_fs(fs)?;
+
+        let mut created = HashMap::new();
+        let mut estimates = HashMap::new();
+        for entry in &history {
+            match entry.event.as_str() {
+                "created" => { created.entry(entry.task_id.clone()).or_insert(entry.timestamp); }
+                "estimated" => {
+                    if let Some(points) = entry.detail.as_deref().and_then(|d| d.parse::<f64>().ok()).filter(|p| *p > 0.0) {
+                        estimates.insert(entry.task_id.clone(), points);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(PriorityContext {
+            tasks: read_tasks_with_fs(fs)?,
+            pain_entries: read_pain_entries_with_fs(fs)?,
+            pain_scores: get_all_pain_scores(now, fs)?,
+            blockers: read_blockers_with_fs(fs)?,
+            history,
+            workflow: workflow_with_fs(fs)?,
+            now,
+            created,
+            estimates,
+        })
+    }
+
+    /// When the task was created; tasks older than the history log count as created when
+    /// the log began
+    pub fn created_at(&self, task_id: &str) -> u64 {
+        self.created.get(task_id).copied()
+            .or_else(|| self.history.first().map(|e| e.timestamp))
+            .unwrap_or(self.now)
+    }
+
+    /// The task's latest `knecht estimate`, 1 when it has none
+    pub fn estimate(&self, task_id: &str) -> f64 {
+        self.estimates.get(task_id).copied().unwrap_or(1.0)
+    }
+}
+
+/// Scores tasks for `knecht next`: the highest score wins, ties go to the smaller ID
+pub trait PriorityStrategy {
+    /// Name used for `next_strategy` in `.knecht/config` and `knecht next --strategy`
+    fn name(&self) -> &'static str;
+
+    fn score(&self, task: &Task, context: &PriorityContext) -> f64;
+
+    /// Every task's score, by task ID. Strategies that weigh tasks against each other override
+    /// this to do that work once per ranking rather than once per task.
+    fn score_all(&self, context: &PriorityContext) -> HashMap<String, f64> {
+        context.tasks.iter().map(|t| (t.id.clone(), self.score(t, context))).collect()
+    }
+}
+
+/// Names of the built-in strategies
+pub const STRATEGY_NAMES: &[&str] = &["pain", "age", "pain-per-estimate", "blend"];
+
+/// Look up a strategy by name; `blend` uses equal weights
+pub fn strategy_for_name(name: &str) -> Option<Box<dyn PriorityStrategy>> {
+    match name {
+        "pain" => Some(Box::new(PainStrategy)),
+        "age" => Some(Box::new(AgeStrategy)),
+        "pain-per-estimate" => Some(Box::new(PainPerEstimateStrategy)),
+        "blend" => Some(Box::new(BlendStrategy::default())),
+        _ => None,
+    }
+}
+
+/// Most (decayed) pain first
+pub struct PainStrategy;
+
+impl PriorityStrategy for PainStrategy {
+    fn name(&self) -> &'static str {
+        "pain"
+    }
+
+    fn score(&self, task: &Task, context: &PriorityContext) -> f64 {
+        context.pain_scores.get(&task.id).copied().unwrap_or(0.0)
+    }
+}
+
+/// Oldest first, by days since creation
+pub struct AgeStrategy;
+
+impl PriorityStrategy for AgeStrategy {
+    fn name(&self) -> &'static str {
+        "age"
+    }
+
+    fn score(&self, task: &Task, context: &PriorityContext) -> f64 {
+        context.now.saturating_sub(context.created_at(&task.id)) as f64 / 86_400.0
+    }
+}
+
+/// Pain divided by estimated size, like WSJF's cost of delay over job size
+pub struct PainPerEstimateStrategy;
+
+impl PriorityStrategy for PainPerEstimateStrategy {
+    fn name(&self) -> &'static str {
+        "pain-per-estimate"
+    }
+
+    fn score(&self, task: &Task, context: &PriorityContext) -> f64 {
+        PainStrategy.score(task, context) / context.estimate(&task.id)
+    }
+}
+
+/// Weighted sum of other strategies, each scaled so the top unresolved task scores 1
+pub struct BlendStrategy {
+    pub weights: Vec<(Box<dyn PriorityStrategy>, f64)>,
+}
+
+impl Default for BlendStrategy {
+    fn default() -> Self {
+        BlendStrategy {
+            weights: vec![
+                (Box::new(PainStrategy), 1.0),
+                (Box::new(AgeStrategy), 1.0),
+                (Box::new(PainPerEstimateStrategy), 1.0),
+            ],
+        }
+    }
+}
+
+impl BlendStrategy {
+    /// Each weighted strategy's top score over the tasks not resolved under the workflow
+    fn maxima(&self, context: &PriorityContext) -> Vec<f64> {
+        self.weights.iter()
+            .map(|(strategy, _)| context.tasks.iter()
+                .filter(|t| !context.workflow.is_resolved(&t.status))
+                .map(|t| strategy.score(t, context))
+                .fold(0.0, f64::max))
+            .collect()
+    }
+
+    fn blended(&self, task: &Task, maxima: &[f64], context: &PriorityContext) -> f64 {
+        self.weights.iter().zip(maxima)
+            .map(|((strategy, weight), &max)| if max > 0.0 { weight * strategy.score(task, context) / max } else { 0.0 })
+            .sum()
+    }
+}
+
+impl PriorityStrategy for BlendStrategy {
+    fn name(&self) -> &'static str {
+        "blend"
+    }
+
+    fn score(&self, task: &Task, context: &PriorityContext) -> f64 {
+        self.blended(task, &self.maxima(context), context)
+    }
+
+    fn score_all(&self, context: &PriorityContext) -> HashMap<String, f64> {
+        let maxima = self.maxima(context);
+        context.tasks.iter().map(|t| (t.id.clone(), self.blended(t, &maxima, context))).collect()
+    }
+}
+
+/// The strategy named by `next_strategy` (default `pain`). `blend` takes its weights from
+/// `blend.pain`, `blend.age` and `blend.pain-per-estimate` (default 1 each).
+pub fn priority_strategy_with_fs(name: Option<&str>, fs: &dyn FileSystem) -> Result<Box<dyn PriorityStrategy>, KnechtError> {
+    let config = read_config_with_fs(fs)?;
+    let name = name.or(config.get("next_strategy").map(String::as_str)).filter(|name| !name.is_empty()).unwrap_or("pain");
+    if name != "blend" {
+        return strategy_for_name(name).ok_or_else(|| unknown_strategy(name));
+    }
+
+    let mut weights: Vec<(Box<dyn PriorityStrategy>, f64)> = Vec::new();
+    for part in ["pain", "age", "pain-per-estimate"] {
+        let key = format!("blend.{}", part);
+        let weight = match config.get(&key) {
+            None => 1.0,
+            Some(value) => parse_weight(&key, value)?,
+        };
+        if let Some(strategy) = strategy_for_name(part) {
+            weights.push((strategy, weight));
+        }
+    }
+    Ok(Box::new(BlendStrategy { weights }))
+}
+
+/// A non-negative blend weight
+pub(crate) fn parse_weight(key: &str, value: &str) -> Result<f64, KnechtError> {
+    match value.parse::<f64>() {
+        Ok(weight) if weight.is_finite() && weight >= 0.0 => Ok(weight),
+        _ => Err(invalid(key, format!("expected a non-negative number, got '{}'", value))),
+    }
+}
+
+pub(crate) fn unknown_strategy(name: &str) -> KnechtError {
+    invalid("next_strategy", format!("unknown strategy '{}' (strategies: {})", name, STRATEGY_NAMES.join(", ")))
+}
@@ -0,0 +1,175 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::{read_history_with_fs, read_pain_entries_with_fs, read_tasks_with_fs, workflow_with_fs, FileSystem, HistoryEntry, KnechtError};
+
+const WEEK_SECS: u64 = 7 * 86_400;
+
+/// Flow metrics computed from the history log, as printed by `knecht report`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FlowReport {
+    pub generated_at: u64,
+    /// Tasks completed per week, oldest week first
+    pub throughput: Vec<WeeklyThroughput>,
+    /// `created` to `done`
+    pub lead_time: Option<DurationStats>,
+    /// First `claimed` to `done`
+    pub cycle_time: Option<DurationStats>,
+    /// Last `delivered` to `done`
+    pub awaiting_verification: Option<DurationStats>,
+    /// Unresolved tasks, oldest first; tasks created before history was kept come last
+    pub aging: Vec<AgingTask>,
+    /// Tasks during which pain was recorded against other tasks, most pain first
+    pub friction: Vec<FrictionSource>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WeeklyThroughput {
+    /// Monday 00:00 UTC
+    pub week_start: u64,
+    pub done: usize,
+}
+
+/// Nearest-rank percentiles of a set of durations, in seconds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DurationStats {
+    pub count: usize,
+    pub p50: u64,
+    pub p85: u64,
+    pub p95: u64,
+    pub max: u64,
+}
+
+impl DurationStats {
+    /// None when there are no durations
+    pub fn from_durations(mut durations: Vec<u64>) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort_unstable();
+        let percentile = |p: usize| durations[(durations.len() * p).div_ceil(100).max(1) - 1];
+        Some(DurationStats {
+            count: durations.len(),
+            p50: percentile(50),
+            p85: percentile(85),
+            p95: percentile(95),
+            max: durations[durations.len() - 1],
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AgingTask {
+    pub task_id: String,
+    pub title: String,
+    pub status: String,
+    /// None when the task has no `created` event
+    pub age_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FrictionSource {
+    pub task_id: String,
+    /// None when the task has since been deleted
+    pub title: Option<String>,
+    /// Pain entries recorded while working on the task
+    pub pain: usize,
+    /// Distinct tasks that pain was recorded against
+    pub tasks: usize,
+}
+
+/// Monday 00:00 UTC of the week containing `timestamp` (the epoch fell on a Thursday, so
+/// the first days clamp to the epoch itself)
+pub fn week_start(timestamp: u64) -> u64 {
+    let days = timestamp / 86_400;
+    days.saturating_sub((days + 3) % 7) * 86_400
+}
+
+/// Build the flow report over the last `weeks` weeks of throughput
+pub fn flow_report_with_fs(weeks: usize, now: u64, fs: &dyn FileSystem) -> Result<FlowReport, KnechtError> {
+    let tasks = read_tasks_with_fs(fs)?;
+    let workflow = workflow_with_fs(fs)?;
+
+    // A timestamp that failed to parse reads as 0; such entries say nothing about flow
+    let mut by_task: HashMap<String, Vec<HistoryEntry>> = HashMap::new();
+    for entry in read_history_with_fs(fs)?.into_iter().filter(|e| e.timestamp > 0) {
+        by_task.entry(entry.task_id.clone()).or_default().push(entry);
+    }
+    let first = |id: &str, event: &str| by_task.get(id)
+        .and_then(|entries| entries.iter().find(|e| e.event == event))
+        .map(|e| e.timestamp);
+
+    let mut completions = Vec::new();
+    let (mut lead, mut cycle, mut waiting) = (Vec::new(), Vec::new(), Vec::new());
+    for (task_id, entries) in &by_task {
+        // A task reopened since its last completion is not done
+        if tasks.iter().any(|t| &t.id == task_id && t.status != "done") {
+            continue;
+        }
+        let Some(done) = entries.iter().rev().find(|e| e.event == "done").map(|e| e.timestamp) else { continue };
+        completions.push(done);
+
+        let before_done = |event: &'static str| entries.iter().filter(move |e| e.event == event && e.timestamp <= done);
+        if let Some(created) = before_done("created").next() {
+            lead.push(done - created.timestamp);
+        }
+        if let Some(claimed) = before_done("claimed").next() {
+            cycle.push(done - claimed.timestamp);
+        }
+        if let Some(delivered) = before_done("delivered").next_back() {
+            waiting.push(done - delivered.timestamp);
+        }
+    }
+
+    let current_week = week_start(now);
+    let throughput = (0..weeks as u64).rev()
+        .map(|back| current_week.saturating_sub(back * WEEK_SECS))
+        .map(|start| WeeklyThroughput {
+            week_start: start,
+            done: completions.iter().filter(|&&at| week_start(at) == start).count(),
+        })
+        .collect();
+
+    let mut aging: Vec<AgingTask> = tasks.iter()
+        .filter(|t| t.status != "done" && !workflow.is_resolved(&t.status))
+        .map(|t| AgingTask {
+            task_id: t.id.clone(),
+            title: t.title.clone(),
+            status: t.status.clone(),
+            age_secs: first(&t.id, "created").map(|created| now.saturating_sub(created)),
+        })
+        .collect();
+    aging.sort_by(|a, b| match (a.age_secs, b.age_secs) {
+        (Some(a_age), Some(b_age)) => b_age.cmp(&a_age),
+        (a_age, b_age) => a_age.is_none().cmp(&b_age.is_none()),
+    }.then_with(|| a.task_id.cmp(&b.task_id)));
+
+    let mut felt_during: HashMap<String, (usize, HashSet<String>)> = HashMap::new();
+    for entry in read_pain_entries_with_fs(fs)? {
+        if let Some(during) = entry.during_task {
+            let (pain, pained) = felt_during.entry(during).or_default();
+            *pain += entry.count as usize;
+            pained.insert(entry.task_id);
+        }
+    }
+    let mut friction: Vec<FrictionSource> = felt_during.into_iter()
+        .map(|(task_id, (pain, pained))| FrictionSource {
+            title: tasks.iter().find(|t| t.id == task_id).map(|t| t.title.clone()),
+            task_id,
+            pain,
+            tasks: pained.len(),
+        })
+        .collect();
+    friction.sort_by(|a, b| b.pain.cmp(&a.pain).then_with(|| a.task_id.cmp(&b.task_id)));
+
+    Ok(FlowReport {
+        generated_at: now,
+        throughput,
+        lead_time: DurationStats::from_durations(lead),
+        cycle_time: DurationStats::from_durations(cycle),
+        awaiting_verification: DurationStats::from_durations(waiting),
+        aging,
+        friction,
+    })
+}
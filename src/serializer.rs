@@ -3,9 +3,44 @@ use csv::{ReaderBuilder, WriterBuilder};
 
 use crate::{Task, KnechtError};
 
+/// Reads and writes the contents of a single per-task file in `.knecht/tasks/`
+pub trait TaskSerializer {
+    /// Name used for the `format` key in `.knecht/config`
+    fn name(&self) -> &'static str;
+
+    /// Parse a task file; `Ok(None)` if the file holds no task
+    fn read_task(&self, reader: &mut dyn BufRead) -> Result<Option<Task>, KnechtError>;
+
+    /// Write a task as the complete contents of its file
+    fn write_task(&self, task: &Task, writer: &mut dyn Write) -> Result<(), KnechtError>;
+}
+
+/// Look up a serializer by its format name
+pub fn serializer_for_format(name: &str) -> Option<Box<dyn TaskSerializer>> {
+    match name {
+        "csv" => Some(Box::new(CsvSerializer)),
+        "markdown" => Some(Box::new(MarkdownSerializer)),
+        _ => None,
+    }
+}
+
 /// Handles CSV serialization/deserialization of tasks
 pub struct CsvSerializer;
 
+impl TaskSerializer for CsvSerializer {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn read_task(&self, reader: &mut dyn BufRead) -> Result<Option<Task>, KnechtError> {
+        Ok(CsvSerializer::read(reader)?.into_iter().next())
+    }
+
+    fn write_task(&self, task: &Task, writer: &mut dyn Write) -> Result<(), KnechtError> {
+        CsvSerializer::write(std::slice::from_ref(task), writer)
+    }
+}
+
 impl CsvSerializer {
     /// Read tasks from a CSV reader
     pub fn read(reader: impl BufRead) -> Result<Vec<Task>, KnechtError> {
@@ -99,3 +134,128 @@ impl CsvSerializer {
         Ok(())
     }
 }
+
+const ACCEPTANCE_CRITERIA_HEADING: &str = "## Acceptance Criteria";
+
+/// Markdown task files: metadata in a `---` frontmatter block, the description as the body
+/// and acceptance criteria under a heading, so descriptions diff line by line
+pub struct MarkdownSerializer;
+
+impl TaskSerializer for MarkdownSerializer {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn read_task(&self, reader: &mut dyn BufRead) -> Result<Option<Task>, KnechtError> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        if content.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let invalid = |line: usize, message: &str| KnechtError::InvalidRecord { line, message: message.to_string() };
+
+        let mut lines = content.split('\n');
+        let opening = lines.next().unwrap_or("");
+        if opening.trim_end() != "---" {
+            return Err(invalid(1, "missing frontmatter"));
+        }
+
+        let (mut id, mut status, mut title, mut pain_count) = (None, None, None, None);
+        let mut body_start = None;
+        let mut offset = opening.len() + 1;
+        for (index, line) in lines.enumerate() {
+            offset += line.len() + 1;
+            let line = line.trim_end();
+            if line == "---" {
+                body_start = Some(offset);
+                break;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(invalid(index + 2, "expected 'key: value' in frontmatter"));
+            };
+            let value = frontmatter_value(value.trim());
+            match key.trim() {
+                "id" => id = Some(value),
+                "status" => status = Some(value),
+                "title" => title = Some(value),
+                "pain_count" => pain_count = value.parse::<u32>().ok(),
+                _ => {}
+            }
+        }
+
+        let Some(body_start) = body_start else {
+            return Err(invalid(1, "unterminated frontmatter"));
+        };
+        let (Some(id), Some(status), Some(title)) = (id, status, title) else {
+            return Err(invalid(1, "frontmatter needs id, status and title"));
+        };
+
+        let body = content.get(body_start..).unwrap_or("");
+        let (description, acceptance_criteria) = match body.rfind(&format!("\n{}\n", ACCEPTANCE_CRITERIA_HEADING)) {
+            Some(pos) => (&body[..pos], Some(&body[pos + ACCEPTANCE_CRITERIA_HEADING.len() + 2..])),
+            None => (body, None),
+        };
+        let section = |text: &str| {
+            let text = text.trim_matches('\n');
+            (!text.is_empty()).then(|| text.to_string())
+        };
+
+        Ok(Some(Task {
+            id,
+            status,
+            title,
+            description: section(description),
+            pain_count,
+            acceptance_criteria: acceptance_criteria.and_then(section),
+        }))
+    }
+
+    fn write_task(&self, task: &Task, writer: &mut dyn Write) -> Result<(), KnechtError> {
+        writeln!(writer, "---")?;
+        writeln!(writer, "id: {}", quote_frontmatter_value(&task.id))?;
+        writeln!(writer, "status: {}", quote_frontmatter_value(&task.status))?;
+        writeln!(writer, "title: {}", quote_frontmatter_value(&task.title))?;
+        if let Some(pain_count) = task.pain_count {
+            writeln!(writer, "pain_count: {}", pain_count)?;
+        }
+        writeln!(writer, "---")?;
+        if let Some(description) = &task.description {
+            write!(writer, "\n{}\n", description)?;
+        }
+        // The last heading starts the criteria, so a description quoting the heading still
+        // needs one after it even without criteria
+        let description_has_heading = task.description.as_deref()
+            .is_some_and(|d| d.lines().any(|line| line.trim_end() == ACCEPTANCE_CRITERIA_HEADING));
+        match &task.acceptance_criteria {
+            Some(criteria) => write!(writer, "\n{}\n\n{}\n", ACCEPTANCE_CRITERIA_HEADING, criteria)?,
+            None if description_has_heading => write!(writer, "\n{}\n", ACCEPTANCE_CRITERIA_HEADING)?,
+            None => {}
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// A frontmatter value as written: plain unless a plain value would not read back the same (line
+/// breaks, surrounding whitespace, a leading quote), in which case it is a JSON string
+fn quote_frontmatter_value(value: &str) -> String {
+    let needs_quoting = value.chars().any(char::is_control)
+        || value.starts_with('"')
+        || value.trim() != value;
+    if needs_quoting {
+        serde_json::to_string(value).unwrap_or_else(|_| value.to_string())
+    } else {
+        value.to_string()
+    }
+}
+
+/// Read back a value written by `quote_frontmatter_value`
+fn frontmatter_value(value: &str) -> String {
+    if value.starts_with('"')
+        && let Ok(unquoted) = serde_json::from_str::<String>(value)
+    {
+        return unquoted;
+    }
+    value.to_string()
+}
@@ -0,0 +1,139 @@
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{current_actor, FileSystem, KnechtError};
+
+/// A stretch of work on a task by one identity, paired from the `start` and `stop` lines of the
+/// append-only `.knecht/sessions` log: `task_id|timestamp|start|actor`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkSession {
+    pub task_id: String,
+    pub actor: String,
+    pub start: u64,
+    /// None while the session is still running
+    pub stop: Option<u64>,
+}
+
+impl WorkSession {
+    /// Seconds worked; a running session counts up to `now`
+    pub fn duration_secs(&self, now: u64) -> u64 {
+        self.stop.unwrap_or(now).saturating_sub(self.start)
+    }
+
+    /// Seconds of this session that fall between `since` and `now`
+    pub fn duration_since(&self, since: u64, now: u64) -> u64 {
+        self.stop.unwrap_or(now).saturating_sub(self.start.max(since))
+    }
+}
+
+/// Seconds since the Unix epoch
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn append_session_line(task_id: &str, timestamp: u64, event: &str, actor: &str, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    let mut writer = fs.append(Path::new(".knecht/sessions"))?;
+    writeln!(writer, "{}|{}|{}|{}", task_id, timestamp, event, actor.replace(['|', '\r', '\n'], "/"))?;
+    Ok(())
+}
+
+/// Append a whole session, as its `start` line and, once stopped, its `stop` line
+pub fn append_session_with_fs(session: &WorkSession, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    append_session_line(&session.task_id, session.start, "start", &session.actor, fs)?;
+    if let Some(stop) = session.stop {
+        append_session_line(&session.task_id, stop, "stop", &session.actor, fs)?;
+    }
+    Ok(())
+}
+
+/// Read every session, in order of their start
+pub fn read_sessions_with_fs(fs: &dyn FileSystem) -> Result<Vec<WorkSession>, KnechtError> {
+    let path = Path::new(".knecht/sessions");
+    if !fs.exists(path) {
+        return Ok(Vec::new());
+    }
+
+    let mut sessions: Vec<WorkSession> = Vec::new();
+    for line in fs.open(path)?.lines() {
+        let line = line?;
+        let parts: Vec<&str> = line.splitn(4, '|').collect();
+        if parts.len() < 4 {
+            continue;
+        }
+        let (task_id, timestamp, event, actor) = (parts[0], parts[1].parse().unwrap_or(0), parts[2], parts[3]);
+
+        let running = sessions.iter_mut()
+            .find(|s| s.task_id == task_id && s.actor == actor && s.stop.is_none());
+        match (event, running) {
+            ("start", None) => sessions.push(WorkSession { task_id: task_id.to_string(), actor: actor.to_string(), start: timestamp, stop: None }),
+            ("stop", Some(session)) => session.stop = Some(timestamp),
+            // A second start while running, or a stop without a start, changes nothing
+            _ => {}
+        }
+    }
+    Ok(sessions)
+}
+
+/// Sessions on one task, in order of their start
+pub fn sessions_for_task_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<Vec<WorkSession>, KnechtError> {
+    Ok(read_sessions_with_fs(fs)?.into_iter().filter(|s| s.task_id == task_id).collect())
+}
+
+/// Start a session for the current actor, unless one is already running
+pub fn start_session_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    start_session_as_with_fs(task_id, &current_actor(), fs)
+}
+
+/// Start a session for `actor`, unless one is already running
+pub fn start_session_as_with_fs(task_id: &str, actor: &str, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    let running = sessions_for_task_with_fs(task_id, fs)?.iter().any(|s| s.actor == actor && s.stop.is_none());
+    if !running {
+        append_session_line(task_id, now_secs(), "start", actor, fs)?;
+    }
+    Ok(())
+}
+
+/// Stop the running sessions on a task: the current actor's, or everyone's when `all` (the work
+/// is delivered or done). Returns the sessions that were stopped.
+pub fn stop_sessions_with_fs(task_id: &str, all: bool, fs: &dyn FileSystem) -> Result<Vec<WorkSession>, KnechtError> {
+    let actor = current_actor();
+    let now = now_secs();
+    let mut stopped = Vec::new();
+    for session in sessions_for_task_with_fs(task_id, fs)? {
+        if session.stop.is_none() && (all || session.actor == actor) {
+            append_session_line(task_id, now, "stop", &session.actor, fs)?;
+            stopped.push(WorkSession { stop: Some(now), ..session });
+        }
+    }
+    Ok(stopped)
+}
+
+/// A duration as `2d 3h`, `1h 5m`, `12m` or `40s`
+pub fn format_duration(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, secs % 86_400 / 3_600, secs % 3_600 / 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
+
+/// A Unix timestamp as `YYYY-MM-DD HH:MM` in UTC
+pub fn format_timestamp(secs: u64) -> String {
+    // Civil-from-days (Howard Hinnant's algorithm) for the proleptic Gregorian calendar
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, secs % 86_400 / 3_600, secs % 3_600 / 60)
+}
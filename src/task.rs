@@ -4,8 +4,51 @@ use std::path::{Path, PathBuf};
 use std::fmt;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use serde::{Deserialize, Serialize};
+
+mod checklist;
+mod config;
+mod edit;
+mod export;
+mod history;
+mod lock;
+mod memory_fs;
+mod priority;
+mod report;
 mod serializer;
-pub use serializer::CsvSerializer;
+mod sessions;
+mod template;
+mod wip;
+mod workflow;
+pub use checklist::{check_criteria_items_with_fs, criteria_items, parse_criteria_items, CriteriaItem};
+pub use config::{
+    claim_lease_secs_with_fs, config_flag_with_fs, config_limit_with_fs, config_value_with_fs, done_banner_with_fs, id_length_with_fs, list_statuses_with_fs,
+    pain_half_life_secs_with_fs,
+    read_config_with_fs, read_repo_config_with_fs, required_fields_with_fs, set_config_value_with_fs, set_user_config_value_with_fs,
+    task_serializer_with_fs, user_config_path, validate_config_value, ConfigSource, CONFIG_SETTINGS, DEFAULT_DONE_BANNER, USER_CONFIG_KEYS,
+};
+pub use edit::{format_task_for_editing, parse_edited_task, EditedTask};
+pub use export::{
+    append_sentry_mapping_with_fs, export_records_with_fs, import_records_with_fs, read_jsonl,
+    read_sentry_mappings_with_fs, write_jsonl, ExportRecord, ImportMode, ImportSummary, SentryMapping,
+    EXPORT_FORMAT_VERSION,
+};
+pub use history::{append_history_entry_with_fs, current_actor, get_history_for_task, latest_claims_with_fs, read_history_with_fs, HistoryEntry};
+pub use lock::{acquire_lock_with_fs, RepoLock, LOCK_TIMEOUT};
+pub use memory_fs::{FsOperation, MemoryFileSystem};
+pub use priority::{
+    priority_strategy_with_fs, strategy_for_name, AgeStrategy, BlendStrategy, PainPerEstimateStrategy, PainStrategy, PriorityContext,
+    PriorityStrategy, STRATEGY_NAMES,
+};
+pub use report::{flow_report_with_fs, week_start, AgingTask, DurationStats, FlowReport, FrictionSource, WeeklyThroughput};
+pub use serializer::{serializer_for_format, CsvSerializer, MarkdownSerializer, TaskSerializer};
+pub use sessions::{
+    append_session_with_fs, format_duration, format_timestamp, now_secs, read_sessions_with_fs, sessions_for_task_with_fs, start_session_as_with_fs, start_session_with_fs,
+    stop_sessions_with_fs, WorkSession,
+};
+pub use template::{list_templates_with_fs, render_template_with_fs, TemplateInfo};
+pub use wip::{check_wip_limits_with_fs, tasks_held_by_with_fs};
+pub use workflow::{workflow_with_fs, Workflow, DEFAULT_STATUSES};
 
 /// Trait for filesystem operations to allow dependency injection in tests
 pub trait FileSystem {
@@ -18,6 +61,15 @@ pub trait FileSystem {
     fn is_file(&self, path: &Path) -> bool;
     fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
     fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Create a file that must not exist yet, failing with `AlreadyExists` otherwise.
+    /// Implementations should make the check and the creation one atomic step.
+    fn create_new(&self, path: &Path) -> io::Result<Box<dyn Write>> {
+        if self.exists(path) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", path.display())));
+        }
+        self.create(path)
+    }
 }
 
 /// Real filesystem implementation
@@ -69,17 +121,142 @@ impl FileSystem for RealFileSystem {
     fn remove_file(&self, path: &Path) -> io::Result<()> {
         fs::remove_file(path)
     }
+
+    fn create_new(&self, path: &Path) -> io::Result<Box<dyn Write>> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        Ok(Box::new(file))
+    }
+}
+
+/// Walks up from `start` looking for a directory that contains `.knecht`, the way git finds `.git`
+pub fn find_repo_root_with_fs(start: &Path, fs: &dyn FileSystem) -> Option<PathBuf> {
+    let mut current = Some(start);
+    while let Some(dir) = current {
+        if fs.is_dir(&dir.join(".knecht")) {
+            return Some(dir.to_path_buf());
+        }
+        current = dir.parent();
+    }
+    None
 }
 
+/// Resolves the repository root for a command.
+///
+/// An explicit `knecht_dir` (from `KNECHT_DIR` or `--knecht-dir`) wins and may name either the
+/// repository root or its `.knecht` directory. Otherwise parent directories of `start` are searched.
+pub fn resolve_repo_root_with_fs(start: &Path, knecht_dir: Option<&Path>, fs: &dyn FileSystem) -> Result<PathBuf, KnechtError> {
+    if let Some(dir) = knecht_dir {
+        let dir = start.join(dir);
+        let root = match dir.file_name() {
+            Some(name) if name == ".knecht" => dir.parent().map(Path::to_path_buf).unwrap_or_default(),
+            _ => dir,
+        };
+        if fs.is_dir(&root.join(".knecht")) {
+            return Ok(root);
+        }
+        return Err(KnechtError::RepoNotFound(root));
+    }
 
+    find_repo_root_with_fs(start, fs).ok_or_else(|| KnechtError::RepoNotFound(start.to_path_buf()))
+}
 
+/// Errors returned by the library.
+///
+/// Each variant has a stable [`code`](KnechtError::code) and a distinct process
+/// [`exit_code`](KnechtError::exit_code), so scripts can branch on failures without parsing text.
 #[derive(Debug)]
 pub enum KnechtError {
+    /// `E_IO`, exit code 9
     IoError(io::Error),
+    /// `E_PARSE`, exit code 10
     CsvError(csv::Error),
+    /// `E_NOT_FOUND`, exit code 4
     TaskNotFound(String),
+    /// `E_ALREADY_DELIVERED`, exit code 6
     TaskAlreadyDelivered(String),
+    /// `E_ALREADY_DONE`, exit code 5
     TaskAlreadyDone(String),
+    /// `E_NO_REPO`, exit code 3
+    RepoNotFound(PathBuf),
+    /// `E_INVALID_RECORD`, exit code 11
+    InvalidRecord { line: usize, message: String },
+    /// `E_CONFIG`, exit code 12
+    InvalidConfig { key: String, message: String },
+    /// `E_TEMPLATE`, exit code 14
+    TemplateError { name: String, message: String },
+    /// `E_UNCHECKED_CRITERIA`, exit code 15. Items are 1-based numbers with their text.
+    UncheckedCriteria { task_id: String, unchecked: Vec<(usize, String)> },
+    /// `E_NO_SUCH_ITEM`, exit code 16
+    CriteriaItemNotFound { task_id: String, item: usize, count: usize },
+    /// `E_NOT_DELIVERED`, exit code 17
+    TaskNotDelivered(String),
+    /// `E_SELF_VERIFICATION`, exit code 18. Carries the actor who both claimed and verified.
+    SelfVerification { task_id: String, actor: String },
+    /// `E_INVALID_TRANSITION`, exit code 20. Carries the statuses the task may move to instead.
+    InvalidTransition { task_id: String, from: String, to: String, allowed: Vec<String> },
+    /// `E_UNKNOWN_STATUS`, exit code 21
+    UnknownStatus { status: String, known: Vec<String> },
+    /// `E_WIP_LIMIT`, exit code 22. `actor` is set when the per-agent limit was hit, None for
+    /// the repository-wide one; `held` are the tasks counting against it.
+    WipLimitExceeded { actor: Option<String>, limit: usize, held: Vec<Task> },
+    /// `E_LOCKED`, exit code 23. `holder` is the contents of the lock file.
+    Locked { holder: String },
+    /// `E_NO_SUCH_PAIN`, exit code 25
+    PainEntryNotFound { task_id: String, entry: usize, count: usize },
+}
+
+impl KnechtError {
+    /// Stable machine-readable identifier for this kind of error
+    pub fn code(&self) -> &'static str {
+        match self {
+            KnechtError::IoError(_) => "E_IO",
+            KnechtError::CsvError(_) => "E_PARSE",
+            KnechtError::TaskNotFound(_) => "E_NOT_FOUND",
+            KnechtError::TaskAlreadyDelivered(_) => "E_ALREADY_DELIVERED",
+            KnechtError::TaskAlreadyDone(_) => "E_ALREADY_DONE",
+            KnechtError::RepoNotFound(_) => "E_NO_REPO",
+            KnechtError::InvalidRecord { .. } => "E_INVALID_RECORD",
+            KnechtError::InvalidConfig { .. } => "E_CONFIG",
+            KnechtError::TemplateError { .. } => "E_TEMPLATE",
+            KnechtError::UncheckedCriteria { .. } => "E_UNCHECKED_CRITERIA",
+            KnechtError::CriteriaItemNotFound { .. } => "E_NO_SUCH_ITEM",
+            KnechtError::TaskNotDelivered(_) => "E_NOT_DELIVERED",
+            KnechtError::SelfVerification { .. } => "E_SELF_VERIFICATION",
+            KnechtError::InvalidTransition { .. } => "E_INVALID_TRANSITION",
+            KnechtError::UnknownStatus { .. } => "E_UNKNOWN_STATUS",
+            KnechtError::WipLimitExceeded { .. } => "E_WIP_LIMIT",
+            KnechtError::Locked { .. } => "E_LOCKED",
+            KnechtError::PainEntryNotFound { .. } => "E_NO_SUCH_PAIN",
+        }
+    }
+
+    /// Process exit status the CLI uses for this error.
+    /// 1 is reserved for unexpected failures and 2 for usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            KnechtError::RepoNotFound(_) => 3,
+            KnechtError::TaskNotFound(_) => 4,
+            KnechtError::TaskAlreadyDone(_) => 5,
+            KnechtError::TaskAlreadyDelivered(_) => 6,
+            KnechtError::IoError(_) => 9,
+            KnechtError::CsvError(_) => 10,
+            KnechtError::InvalidRecord { .. } => 11,
+            KnechtError::InvalidConfig { .. } => 12,
+            KnechtError::TemplateError { .. } => 14,
+            KnechtError::UncheckedCriteria { .. } => 15,
+            KnechtError::CriteriaItemNotFound { .. } => 16,
+            KnechtError::TaskNotDelivered(_) => 17,
+            KnechtError::SelfVerification { .. } => 18,
+            KnechtError::InvalidTransition { .. } => 20,
+            KnechtError::UnknownStatus { .. } => 21,
+            KnechtError::WipLimitExceeded { .. } => 22,
+            KnechtError::Locked { .. } => 23,
+            KnechtError::PainEntryNotFound { .. } => 25,
+        }
+    }
 }
 
 impl fmt::Display for KnechtError {
@@ -90,6 +267,41 @@ impl fmt::Display for KnechtError {
             KnechtError::TaskNotFound(id) => write!(f, "task-{} not found", id),
             KnechtError::TaskAlreadyDelivered(id) => write!(f, "task-{} is already delivered", id),
             KnechtError::TaskAlreadyDone(id) => write!(f, "task-{} is already done", id),
+            KnechtError::RepoNotFound(path) => write!(f, "not a knecht repository (or any parent directory): {}. Run 'knecht init' first.", path.display()),
+            KnechtError::InvalidRecord { line, message } => write!(f, "invalid record on line {}: {}", line, message),
+            KnechtError::InvalidConfig { key, message } => write!(f, "invalid config '{}': {}", key, message),
+            KnechtError::TemplateError { name, message } => write!(f, "template '{}': {}", name, message),
+            KnechtError::UncheckedCriteria { task_id, unchecked } => {
+                let items: Vec<String> = unchecked.iter().map(|(n, text)| format!("{}. {}", n, text)).collect();
+                write!(f, "task-{} has unchecked acceptance criteria: {}", task_id, items.join("; "))
+            }
+            KnechtError::CriteriaItemNotFound { task_id, item, count } => {
+                write!(f, "task-{} has no acceptance criterion {} (it has {})", task_id, item, count)
+            }
+            KnechtError::TaskNotDelivered(id) => write!(f, "task-{} has not been delivered", id),
+            KnechtError::SelfVerification { task_id, actor } => {
+                write!(f, "task-{} was claimed by {}, who cannot also verify it", task_id, actor)
+            }
+            KnechtError::InvalidTransition { task_id, from, to, allowed } if allowed.is_empty() => {
+                write!(f, "task-{} cannot move from {} to {}; {} is a final status", task_id, from, to, from)
+            }
+            KnechtError::InvalidTransition { task_id, from, to, allowed } => {
+                write!(f, "task-{} cannot move from {} to {} (allowed: {})", task_id, from, to, allowed.join(", "))
+            }
+            KnechtError::UnknownStatus { status, known } => {
+                write!(f, "unknown status '{}' (statuses: {})", status, known.join(", "))
+            }
+            KnechtError::WipLimitExceeded { actor, limit, held } => {
+                let held: Vec<String> = held.iter().map(|t| format!("task-{}", t.id)).collect();
+                match actor {
+                    Some(actor) => write!(f, "{} already holds {} claimed task(s), the limit is {}: {}", actor, held.len(), limit, held.join(", ")),
+                    None => write!(f, "{} tasks are claimed or delivered, the limit is {}: {}", held.len(), limit, held.join(", ")),
+                }
+            }
+            KnechtError::Locked { holder } => write!(f, "the repository is locked by another knecht ({}); remove .knecht/lock if it is stale", holder),
+            KnechtError::PainEntryNotFound { task_id, entry, count } => {
+                write!(f, "task-{} has no pain entry {} (it has {})", task_id, entry, count)
+            }
         }
     }
 }
@@ -106,7 +318,7 @@ impl From<csv::Error> for KnechtError {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: String,
     pub status: String,
@@ -117,16 +329,27 @@ pub struct Task {
 }
 
 /// A single pain instance recorded in the append-only pain log
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PainEntry {
     pub task_id: String,
     pub timestamp: u64,
     pub source_type: PainSourceType,
     pub source_id: Option<String>,
     pub description: String,
+    /// The task being worked on when the pain was felt
+    #[serde(default)]
+    pub during_task: Option<String>,
+    /// How many identical pain instances this entry stands for
+    #[serde(default = "one")]
+    pub count: u32,
 }
 
-#[derive(Debug, Clone)]
+fn one() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PainSourceType {
     Manual,
     Skip,
@@ -161,12 +384,14 @@ pub fn read_tasks_with_fs(fs: &dyn FileSystem) -> Result<Vec<Task>, KnechtError>
     // Check if it's a directory (new format) or file (old format)
     if fs.is_dir(path) {
         // New directory-based format: read each file as a single task
+        let serializer = task_serializer_with_fs(fs)?;
         let entries = fs.read_dir(path)?;
         let mut tasks = Vec::new();
         for entry in entries {
-            let reader = fs.open(&entry)?;
-            let mut file_tasks = CsvSerializer::read(reader)?;
-            tasks.append(&mut file_tasks);
+            let mut reader = fs.open(&entry)?;
+            if let Some(task) = serializer.read_task(&mut reader)? {
+                tasks.push(task);
+            }
         }
         Ok(tasks)
     } else {
@@ -196,15 +421,38 @@ pub fn migrate_to_directory_format(fs: &dyn FileSystem) -> Result<(), KnechtErro
     fs.create_dir_all(path)?;
 
     // Write each task to individual file
+    let serializer = task_serializer_with_fs(fs)?;
     for task in &tasks {
         let task_path = path.join(&task.id);
-        let file = fs.create(&task_path)?;
-        CsvSerializer::write(std::slice::from_ref(task), file)?;
+        let mut file = fs.create(&task_path)?;
+        serializer.write_task(task, &mut file)?;
     }
 
     Ok(())
 }
 
+/// Rewrite every task file in another format and record it as the repository's `format`.
+/// Returns the number of tasks converted.
+pub fn migrate_task_format_with_fs(format: &str, fs: &dyn FileSystem) -> Result<usize, KnechtError> {
+    let target = serializer_for_format(format).ok_or_else(|| KnechtError::InvalidConfig {
+        key: "format".to_string(),
+        message: format!("unknown task format '{}'", format),
+    })?;
+
+    migrate_to_directory_format(fs)?;
+    let tasks = read_tasks_with_fs(fs)?;
+
+    fs.create_dir_all(Path::new(".knecht/tasks"))?;
+    for task in &tasks {
+        let task_path = PathBuf::from(".knecht/tasks").join(&task.id);
+        let mut file = fs.create(&task_path)?;
+        target.write_task(task, &mut file)?;
+    }
+
+    set_config_value_with_fs("format", target.name(), fs)?;
+    Ok(tasks.len())
+}
+
 pub fn write_tasks_with_fs(tasks: &[Task], fs: &dyn FileSystem) -> Result<(), KnechtError> {
     // Migrate from old file format if needed
     migrate_to_directory_format(fs)?;
@@ -213,10 +461,11 @@ pub fn write_tasks_with_fs(tasks: &[Task], fs: &dyn FileSystem) -> Result<(), Kn
     fs.create_dir_all(Path::new(".knecht/tasks"))?;
 
     // Write each task to its own file
+    let serializer = task_serializer_with_fs(fs)?;
     for task in tasks {
         let task_path = PathBuf::from(".knecht/tasks").join(&task.id);
-        let file = fs.create(&task_path)?;
-        CsvSerializer::write(std::slice::from_ref(task), file)?;
+        let mut file = fs.create(&task_path)?;
+        serializer.write_task(task, &mut file)?;
     }
     Ok(())
 }
@@ -226,15 +475,31 @@ pub fn write_task_with_fs(task: &Task, fs: &dyn FileSystem) -> Result<(), Knecht
     // Ensure .knecht/tasks directory exists
     fs.create_dir_all(Path::new(".knecht/tasks"))?;
 
+    let serializer = task_serializer_with_fs(fs)?;
     let task_path = PathBuf::from(".knecht/tasks").join(&task.id);
-    let file = fs.create(&task_path)?;
-    CsvSerializer::write(std::slice::from_ref(task), file)?;
+    let mut file = fs.create(&task_path)?;
+    serializer.write_task(task, &mut file)?;
     Ok(())
 }
 
+/// Whether `id` can name a task file: a single plain path component, so it cannot point
+/// outside `.knecht/tasks`, without the `|` and `;` that separate fields in the logs
+pub(crate) fn is_valid_task_id(id: &str) -> bool {
+    !id.is_empty()
+        && !id.starts_with('.')
+        && !id.contains(['/', '\\', '|', ';'])
+        && !id.contains("..")
+        && !id.chars().any(char::is_control)
+}
+
 /// Generates a 6-character random alphanumeric ID using timestamp and process ID for entropy.
 /// This avoids merge conflicts when parallel agents create tasks.
 pub fn generate_random_id() -> String {
+    generate_random_id_with_length(6)
+}
+
+/// Like `generate_random_id`, with `length` characters (see `id_length` in `.knecht/config`)
+pub fn generate_random_id_with_length(length: usize) -> String {
     const CHARS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
     let nanos = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -245,8 +510,8 @@ pub fn generate_random_id() -> String {
     // Combine nanos and pid for entropy
     let mut seed = nanos as u64 ^ ((pid as u64) << 32);
 
-    let mut id = String::with_capacity(6);
-    for _ in 0..6 {
+    let mut id = String::with_capacity(length);
+    for _ in 0..length {
         // Simple LCG-style mixing
         seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
         let idx = ((seed >> 32) as usize) % CHARS.len();
@@ -256,7 +521,7 @@ pub fn generate_random_id() -> String {
 }
 
 pub fn add_task_with_fs(title: String, description: Option<String>, acceptance_criteria: Option<String>, fs: &dyn FileSystem) -> Result<String, KnechtError> {
-    let new_id = generate_random_id();
+    let new_id = generate_random_id_with_length(id_length_with_fs(fs)?);
 
     // Migrate from old file format if needed
     migrate_to_directory_format(fs)?;
@@ -274,9 +539,11 @@ pub fn add_task_with_fs(title: String, description: Option<String>, acceptance_c
     };
 
     // Create individual file for the new task
+    let serializer = task_serializer_with_fs(fs)?;
     let task_path = PathBuf::from(".knecht/tasks").join(&new_id);
-    let file = fs.create(&task_path)?;
-    CsvSerializer::write(std::slice::from_ref(&task), file)?;
+    let mut file = fs.create(&task_path)?;
+    serializer.write_task(&task, &mut file)?;
+    append_history_entry_with_fs(&HistoryEntry::now(&new_id, "created", None), fs)?;
 
     Ok(new_id)
 }
@@ -288,9 +555,8 @@ pub fn find_task_by_id_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<Tas
     if fs.is_dir(path) {
         let task_path = path.join(task_id);
         if fs.exists(&task_path) {
-            let reader = fs.open(&task_path)?;
-            let tasks = CsvSerializer::read(reader)?;
-            if let Some(task) = tasks.into_iter().next() {
+            let mut reader = fs.open(&task_path)?;
+            if let Some(task) = task_serializer_with_fs(fs)?.read_task(&mut reader)? {
                 return Ok(task);
             }
         }
@@ -308,19 +574,55 @@ pub fn find_task_by_id_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<Tas
     Err(KnechtError::TaskNotFound(task_id.to_string()))
 }
 
+/// Marks a task done. Refuses while any acceptance criteria item is unchecked, and for a
+/// delivered task, when its claimant may not verify it (`require_independent_verifier`).
 pub fn mark_task_done_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
+    complete_task_with_fs(task_id, None, fs)
+}
+
+/// Marks a task done even with unchecked acceptance criteria, recording `reason`
+/// as a `forced_done` event in the history log
+pub fn force_task_done_with_fs(task_id: &str, reason: &str, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
+    complete_task_with_fs(task_id, Some(reason), fs)
+}
+
+fn complete_task_with_fs(task_id: &str, force_reason: Option<&str>, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
     let mut tasks = read_tasks_with_fs(fs)?;
 
     // Check if task exists and is already done
     let existing_task = tasks.iter().find(|t| t.id == task_id);
     if let Some(task) = existing_task
-        && task.status == "done" {
-            return Err(KnechtError::TaskAlreadyDone(task_id.to_string()));
+        && task.status == "done"
+    {
+        return Err(KnechtError::TaskAlreadyDone(task_id.to_string()));
+    }
+
+    let workflow = workflow_with_fs(fs)?;
+    if let Some(task) = existing_task {
+        workflow.check_move(task_id, &task.status, "done")?;
+    }
+
+    // Completing a delivered task signs it off, so it needs the same verifier as `verify`
+    if existing_task.is_some_and(|task| task.status == "delivered") {
+        check_independent_verifier_with_fs(task_id, fs)?;
+    }
+
+    if let Some(task) = existing_task
+        && force_reason.is_none()
+    {
+        let unchecked: Vec<(usize, String)> = criteria_items(task).into_iter()
+            .enumerate()
+            .filter(|(_, item)| !item.checked)
+            .map(|(index, item)| (index + 1, item.text))
+            .collect();
+        if !unchecked.is_empty() {
+            return Err(KnechtError::UncheckedCriteria { task_id: task_id.to_string(), unchecked });
         }
+    }
 
     // Find the first open task (by string comparison for consistent ordering)
     let oldest_open_task_id = tasks.iter()
-        .filter(|t| t.status == "open")
+        .filter(|t| workflow.is_active(&t.status))
         .min_by(|a, b| a.id.cmp(&b.id))
         .map(|t| t.id.clone());
 
@@ -334,23 +636,30 @@ pub fn mark_task_done_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<Task
             let completed_task = task.clone();
 
             // If we skipped the top task, log pain to append-only pain log
-            if should_increment_skip {
-                if let Some(ref skipped_id) = skipped_task_id {
-                    let entry = PainEntry {
-                        task_id: skipped_id.clone(),
-                        timestamp: SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs(),
-                        source_type: PainSourceType::Skip,
-                        source_id: Some(task_id.to_string()),
-                        description: format!("Skip: task-{} completed instead", task_id),
-                    };
-                    append_pain_entry_with_fs(&entry, fs)?;
-                }
+            if should_increment_skip
+                && let Some(ref skipped_id) = skipped_task_id
+            {
+                let entry = PainEntry {
+                    task_id: skipped_id.clone(),
+                    timestamp: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs(),
+                    source_type: PainSourceType::Skip,
+                    source_id: Some(task_id.to_string()),
+                    description: format!("Skip: task-{} completed instead", task_id),
+                    during_task: None,
+                    count: 1,
+                };
+                append_pain_entry_with_fs(&entry, fs)?;
             }
 
             write_tasks_with_fs(&tasks, fs)?;
+            stop_sessions_with_fs(task_id, true, fs)?;
+            append_history_entry_with_fs(&HistoryEntry::now(task_id, "done", None), fs)?;
+            if let Some(reason) = force_reason {
+                append_history_entry_with_fs(&HistoryEntry::now(task_id, "forced_done", Some(reason)), fs)?;
+            }
             return Ok(completed_task);
         }
     }
@@ -358,7 +667,9 @@ pub fn mark_task_done_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<Task
     Err(KnechtError::TaskNotFound(task_id.to_string()))
 }
 
-pub fn mark_task_delivered_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
+/// Marks a task delivered, recording a `delivered` history event with `evidence` of how
+/// the work was verified (commands run, commit hash, manual steps)
+pub fn mark_task_delivered_with_fs(task_id: &str, evidence: Option<&str>, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
     // Optimized: read and write single task file
     let mut task = find_task_by_id_with_fs(task_id, fs)?;
 
@@ -368,20 +679,148 @@ pub fn mark_task_delivered_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result
     if task.status == "done" {
         return Err(KnechtError::TaskAlreadyDone(task_id.to_string()));
     }
+    workflow_with_fs(fs)?.check_move(task_id, &task.status, "delivered")?;
 
     task.mark_delivered();
     write_task_with_fs(&task, fs)?;
+    stop_sessions_with_fs(task_id, true, fs)?;
+    append_history_entry_with_fs(&HistoryEntry::now(task_id, "delivered", evidence), fs)?;
     Ok(task)
 }
 
 pub fn mark_task_claimed_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
+    claim_task_as_with_fs(task_id, &current_actor(), fs)
+}
+
+fn claim_task_as_with_fs(task_id: &str, actor: &str, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
     // Optimized: read and write single task file
     let mut task = find_task_by_id_with_fs(task_id, fs)?;
+    workflow_with_fs(fs)?.check_move(task_id, &task.status, "claimed")?;
+    // Re-claiming your own task is free; taking over someone else's claim counts against you
+    let held_by_actor = task.status == "claimed"
+        && latest_claims_with_fs(fs)?.get(task_id).is_some_and(|claim| claim.actor == actor);
+    if !held_by_actor {
+        wip::check_claim_limits_with_fs(actor, Some(&task), fs)?;
+    }
     task.mark_claimed();
     write_task_with_fs(&task, fs)?;
+    let entry = HistoryEntry { actor: actor.to_string(), ..HistoryEntry::now(task_id, "claimed", None) };
+    append_history_entry_with_fs(&entry, fs)?;
+    start_session_as_with_fs(task_id, actor, fs)?;
+    Ok(task)
+}
+
+/// Select the first task of the work queue that is not awaiting verification or claimed by
+/// someone else (unless their lease ran out) and claim it for `actor`, holding `.knecht/lock`
+/// so concurrent agents never get the same task.
+/// Returns None when there is nothing left to claim.
+pub fn claim_next_task_with_fs(strategy: &dyn PriorityStrategy, actor: &str, fs: &dyn FileSystem) -> Result<Option<Task>, KnechtError> {
+    let _lock = acquire_lock_with_fs(LOCK_TIMEOUT, fs)?;
+    let queue = rank_tasks_with_fs(strategy, fs)?;
+
+    // Never take over a claim someone else still holds
+    let queued: Vec<Task> = queue.iter().map(|entry| entry.task.clone()).collect();
+    let expired = expired_claims_with_fs(&queued, fs)?;
+    let claims = latest_claims_with_fs(fs)?;
+    let free = |task: &Task| task.status != "claimed"
+        || expired.contains(&task.id)
+        || claims.get(&task.id).is_some_and(|claim| claim.actor == actor);
+
+    let next = queue.into_iter()
+        .find(|entry| entry.task.status != "delivered" && free(&entry.task));
+    match next {
+        Some(entry) => claim_task_as_with_fs(&entry.task.id, actor, fs).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Records an estimate of the task's size in points, as an `estimated` history event;
+/// the `pain-per-estimate` strategy divides pain by it
+pub fn estimate_task_with_fs(task_id: &str, points: f64, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
+    let task = find_task_by_id_with_fs(task_id, fs)?;
+    append_history_entry_with_fs(&HistoryEntry::now(task_id, "estimated", Some(&points.to_string())), fs)?;
     Ok(task)
 }
 
+/// Signs off a delivered task as the current actor and marks it done.
+///
+/// With `require_independent_verifier = true` in `.knecht/config`, the verifier must differ
+/// from whoever claimed the task (or delivered it, when it was never claimed).
+pub fn verify_task_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
+    let task = find_task_by_id_with_fs(task_id, fs)?;
+    match task.status.as_str() {
+        "delivered" => {}
+        "done" => return Err(KnechtError::TaskAlreadyDone(task_id.to_string())),
+        _ => return Err(KnechtError::TaskNotDelivered(task_id.to_string())),
+    }
+
+    let task = complete_task_with_fs(task_id, None, fs)?;
+    append_history_entry_with_fs(&HistoryEntry::now(task_id, "verified", None), fs)?;
+    Ok(task)
+}
+
+/// With `require_independent_verifier = true`, refuse to let the current actor sign off a task
+/// they claimed (or delivered, when it was never claimed)
+fn check_independent_verifier_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    if !config_flag_with_fs("require_independent_verifier", false, fs)? {
+        return Ok(());
+    }
+
+    let verifier = current_actor();
+    let history = get_history_for_task(task_id, fs)?;
+    let claimant = history.iter().rev().find(|e| e.event == "claimed")
+        .or_else(|| history.iter().rev().find(|e| e.event == "delivered"))
+        .map(|e| e.actor.as_str());
+    if claimant == Some(verifier.as_str()) {
+        return Err(KnechtError::SelfVerification { task_id: task_id.to_string(), actor: verifier });
+    }
+    Ok(())
+}
+
+/// Moves a task to `status`, as allowed by the configured workflow. Moving to done, claimed
+/// or delivered goes through the same checks and history events as `done`, `start` and `deliver`.
+pub fn move_task_with_fs(task_id: &str, status: &str, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
+    match status {
+        "done" => mark_task_done_with_fs(task_id, fs),
+        "claimed" => mark_task_claimed_with_fs(task_id, fs),
+        "delivered" => mark_task_delivered_with_fs(task_id, None, fs),
+        _ => {
+            let mut task = find_task_by_id_with_fs(task_id, fs)?;
+            workflow_with_fs(fs)?.check_move(task_id, &task.status, status)?;
+
+            let detail = format!("{} -> {}", task.status, status);
+            task.status = status.to_string();
+            write_task_with_fs(&task, fs)?;
+            append_history_entry_with_fs(&HistoryEntry::now(task_id, "moved", Some(&detail)), fs)?;
+            // Work stops when the task leaves claimed, e.g. released back to open
+            stop_sessions_with_fs(task_id, true, fs)?;
+            Ok(task)
+        }
+    }
+}
+
+/// Read all blocker relationships as (blocked task ID, blocker task ID) pairs, in file order
+pub fn read_blockers_with_fs(fs: &dyn FileSystem) -> Result<Vec<(String, String)>, KnechtError> {
+    let blockers_path = Path::new(".knecht/blockers");
+
+    if !fs.exists(blockers_path) {
+        return Ok(Vec::new());
+    }
+
+    let reader = fs.open(blockers_path)?;
+    let mut blockers = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if let Some((blocked, blocker)) = line.split_once('|') {
+            blockers.push((
+                blocked.trim_start_matches("task-").to_string(),
+                blocker.trim_start_matches("task-").to_string(),
+            ));
+        }
+    }
+    Ok(blockers)
+}
+
 /// Returns a list of task IDs that block the given task (i.e., tasks that must be completed first)
 fn get_blockers_for_task(task_id: &str, fs: &dyn FileSystem) -> Vec<String> {
     let blockers_path = Path::new(".knecht/blockers");
@@ -408,151 +847,390 @@ fn get_blockers_for_task(task_id: &str, fs: &dyn FileSystem) -> Vec<String> {
 }
 
 /// Returns true if the task has any open blockers (tasks that must be completed before this one)
-fn has_open_blockers(task_id: &str, tasks: &[Task], fs: &dyn FileSystem) -> bool {
+fn has_open_blockers(task_id: &str, tasks: &[Task], workflow: &Workflow, fs: &dyn FileSystem) -> bool {
     let blockers = get_blockers_for_task(task_id, fs);
     
     for blocker_id in blockers {
         if let Some(blocker_task) = tasks.iter().find(|t| t.id == blocker_id)
-            && blocker_task.status == "open" {
-                return true;
-            }
+            && !workflow.is_resolved(&blocker_task.status)
+        {
+            return true;
+        }
     }
     
     false
 }
 
-/// Recursively finds the best unblocked blocker task to work on
-fn find_best_blocker(task_id: &str, tasks: &[Task], pain_counts: &HashMap<String, u32>, fs: &dyn FileSystem) -> Option<Task> {
+/// Whether `task` is free to suggest: in an active status, or claimed under a lease that ran out
+fn is_claimable(task: &Task, workflow: &Workflow, expired: &HashSet<String>) -> bool {
+    if task.status == "claimed" {
+        expired.contains(&task.id)
+    } else {
+        workflow.is_active(&task.status)
+    }
+}
+
+/// Recursively finds the best claimable leaf blocker to work on, appending each blocker it
+/// walks through to `chain`. Blockers someone holds (claimed, delivered or otherwise in
+/// progress) still block but are walked past. `chain` also marks the tasks already on the
+/// path, so a blocker cycle ends the walk. None when every path leads to held work.
+fn find_best_blocker(task_id: &str, tasks: &[Task], pain_scores: &HashMap<String, f64>, workflow: &Workflow, expired: &HashSet<String>, chain: &mut Vec<Task>, fs: &dyn FileSystem) -> Option<Task> {
     let blockers = get_blockers_for_task(task_id, fs);
+    let score = |task: &Task| pain_scores.get(&task.id).copied().unwrap_or(0.0);
 
-    // Get open blocker tasks
-    let open_blockers: Vec<&Task> = tasks.iter()
-        .filter(|t| t.status == "open" && blockers.contains(&t.id))
+    // Unresolved blockers not yet on the path, best first by pain score with consistent
+    // tiebreaking by ID
+    let mut open_blockers: Vec<&Task> = tasks.iter()
+        .filter(|t| !workflow.is_resolved(&t.status) && blockers.contains(&t.id))
+        .filter(|t| !chain.iter().any(|walked| walked.id == t.id))
         .collect();
-
-    // Find best blocker by pain count with consistent tiebreaking by ID
-    let best_blocker = open_blockers.iter()
-        .max_by(|a, b| {
-            let pain_a = pain_counts.get(&a.id).copied().unwrap_or(0);
-            let pain_b = pain_counts.get(&b.id).copied().unwrap_or(0);
-            // First compare by pain count (higher is better)
-            pain_a.cmp(&pain_b)
-                // On tie, prefer lexicographically smaller ID (consistent ordering)
-                .then_with(|| b.id.cmp(&a.id))
-        })
-        .map(|t| (*t).clone())
-        .expect("No blocker found");
-
-    // Check if this blocker itself has open blockers - recursively find leaf blocker
-    if has_open_blockers(&best_blocker.id, tasks, fs) {
-        // Recursively find the best blocker of this blocker
-        return find_best_blocker(&best_blocker.id, tasks, pain_counts, fs);
+    open_blockers.sort_by(|a, b| score(b).total_cmp(&score(a)).then_with(|| a.id.cmp(&b.id)));
+
+    for blocker in open_blockers {
+        chain.push(blocker.clone());
+        if has_open_blockers(&blocker.id, tasks, workflow, fs) {
+            // Recursively find the best blocker of this blocker
+            if let Some(leaf) = find_best_blocker(&blocker.id, tasks, pain_scores, workflow, expired, chain, fs) {
+                return Some(leaf);
+            }
+        } else if is_claimable(blocker, workflow, expired) {
+            return Some(blocker.clone());
+        }
+        chain.pop();
     }
-
-    Some(best_blocker)
+    None
 }
 
-/// Find the best task from a list by highest pain count, with consistent tiebreaking by ID
-fn find_best_by_priority(tasks: &[&Task], pain_counts: &HashMap<String, u32>) -> Option<Task> {
+fn find_best_by_priority(tasks: &[&Task], pain_scores: &HashMap<String, f64>) -> Option<Task> {
     tasks.iter()
         .max_by(|a, b| {
-            let pain_a = pain_counts.get(&a.id).copied().unwrap_or(0);
-            let pain_b = pain_counts.get(&b.id).copied().unwrap_or(0);
-            // First compare by pain count (higher is better)
-            pain_a.cmp(&pain_b)
+            let pain_a = pain_scores.get(&a.id).copied().unwrap_or(0.0);
+            let pain_b = pain_scores.get(&b.id).copied().unwrap_or(0.0);
+            // First compare by pain score (higher is better)
+            pain_a.total_cmp(&pain_b)
                 // On tie, prefer lexicographically smaller ID (consistent ordering)
                 .then_with(|| b.id.cmp(&a.id))
         })
         .map(|t| (*t).clone())
 }
 
+/// Suggest the next task using the strategy configured by `next_strategy`
 pub fn find_next_task_with_fs(fs: &dyn FileSystem) -> Result<Option<Task>, KnechtError> {
-    let tasks = read_tasks_with_fs(fs)?;
+    find_next_task_with_strategy_with_fs(priority_strategy_with_fs(None, fs)?.as_ref(), fs)
+}
 
-    // Get pain counts from the pain log (efficient bulk read)
-    let pain_counts = get_all_pain_counts(fs)?;
+/// Suggest the next task: delivered tasks awaiting verification first, then the active task
+/// `strategy` scores highest, or its best leaf blocker when it is blocked
+pub fn find_next_task_with_strategy_with_fs(strategy: &dyn PriorityStrategy, fs: &dyn FileSystem) -> Result<Option<Task>, KnechtError> {
+    Ok(explain_next_task_with_fs(strategy, fs)?.suggestion)
+}
+
+/// A task `knecht next` considered, with its strategy score
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScoredTask {
+    pub task_id: String,
+    pub title: String,
+    pub status: String,
+    pub score: f64,
+    /// Has open blockers
+    pub blocked: bool,
+}
+
+/// A pain entry and what it currently weighs, after `pain_half_life` decay
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PainContribution {
+    #[serde(flatten)]
+    pub entry: PainEntry,
+    pub weight: f64,
+}
+
+/// How `knecht next` arrived at its suggestion
+#[derive(Debug, Clone, Serialize)]
+pub struct NextExplanation {
+    pub strategy: String,
+    pub suggestion: Option<Task>,
+    /// Delivered tasks awaiting verification, best first; any of them preempts open work
+    pub delivered: Vec<ScoredTask>,
+    /// Active tasks and expired claims, best first
+    pub candidates: Vec<ScoredTask>,
+    /// The blocked candidate the suggestion stands in for and the blockers walked from it;
+    /// empty when the suggestion was not blocked
+    pub blocker_chain: Vec<ScoredTask>,
+    /// Pain entries on the suggestion
+    pub pain: Vec<PainContribution>,
+}
+
+/// Suggest the next task like `find_next_task_with_strategy_with_fs`, recording the candidates,
+/// scores and blocker chain behind the choice
+pub fn explain_next_task_with_fs(strategy: &dyn PriorityStrategy, fs: &dyn FileSystem) -> Result<NextExplanation, KnechtError> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (tasks, pain_scores) = score_tasks_with_fs(strategy, now, fs)?;
+    let workflow = workflow_with_fs(fs)?;
+
+    let scored = |task: &Task| ScoredTask {
+        task_id: task.id.clone(),
+        title: task.title.clone(),
+        status: task.status.clone(),
+        score: pain_scores.get(&task.id).copied().unwrap_or(0.0),
+        blocked: has_open_blockers(&task.id, &tasks, &workflow, fs),
+    };
+    let ranked = |candidates: &[&Task]| {
+        let mut ranked: Vec<ScoredTask> = candidates.iter().map(|t| scored(t)).collect();
+        ranked.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.task_id.cmp(&b.task_id)));
+        ranked
+    };
 
     // First, check for delivered tasks (needing verification) - they take priority
     let delivered_tasks: Vec<_> = tasks.iter()
         .filter(|t| t.status == "delivered")
         .collect();
 
-    if !delivered_tasks.is_empty() {
-        return Ok(find_best_by_priority(&delivered_tasks, &pain_counts));
-    }
-
-    // Otherwise, fall back to open tasks
+    // Otherwise, fall back to active (open) tasks, including claims whose lease ran out
+    let expired = expired_claims_with_fs(&tasks, fs)?;
     let open_tasks: Vec<_> = tasks.iter()
-        .filter(|t| t.status == "open")
+        .filter(|t| is_claimable(t, &workflow, &expired))
         .collect();
 
-    if open_tasks.is_empty() {
-        return Ok(None);
-    }
+    let mut blocker_chain = Vec::new();
+    let suggestion = if !delivered_tasks.is_empty() {
+        find_best_by_priority(&delivered_tasks, &pain_scores)
+    } else {
+        // The best task, or the best blocker to work on instead when it is blocked; a task
+        // blocked only by work someone holds gives way to the next best
+        let mut by_priority = open_tasks.clone();
+        by_priority.sort_by(|a, b| {
+            let pain_a = pain_scores.get(&a.id).copied().unwrap_or(0.0);
+            let pain_b = pain_scores.get(&b.id).copied().unwrap_or(0.0);
+            pain_b.total_cmp(&pain_a).then_with(|| a.id.cmp(&b.id))
+        });
+        by_priority.into_iter().find_map(|task| {
+            if !has_open_blockers(&task.id, &tasks, &workflow, fs) {
+                return Some(task.clone());
+            }
+            let mut chain = vec![task.clone()];
+            let blocker = find_best_blocker(&task.id, &tasks, &pain_scores, &workflow, &expired, &mut chain, fs)?;
+            blocker_chain = chain.iter().map(scored).collect();
+            Some(blocker)
+        })
+    };
+
+    let pain = match &suggestion {
+        Some(task) => get_pain_weights_with_fs(now, fs)?.into_iter()
+            .filter(|(entry, _)| entry.task_id == task.id)
+            .map(|(entry, weight)| PainContribution { entry, weight })
+            .collect(),
+        None => Vec::new(),
+    };
 
-    let best_task = find_best_by_priority(&open_tasks, &pain_counts);
+    Ok(NextExplanation {
+        strategy: strategy.name().to_string(),
+        suggestion,
+        delivered: ranked(&delivered_tasks),
+        candidates: ranked(&open_tasks),
+        blocker_chain,
+        pain,
+    })
+}
+
+/// Every task with its `strategy` score
+fn score_tasks_with_fs(strategy: &dyn PriorityStrategy, now: u64, fs: &dyn FileSystem) -> Result<(Vec<Task>, HashMap<String, f64>), KnechtError> {
+    let context = PriorityContext::load_with_fs(now, fs)?;
+    let scores = strategy.score_all(&context);
+    Ok((context.tasks, scores))
+}
 
-    // If the best task has open blockers, find the best blocker to work on instead
-    if let Some(ref task) = best_task
-        && has_open_blockers(&task.id, &tasks, fs) {
-            // find_best_blocker always returns Some (panics if no blocker found)
-            let blocker = find_best_blocker(&task.id, &tasks, &pain_counts, fs).unwrap();
-            return Ok(Some(blocker));
+/// A place in the work queue from `rank_tasks_with_fs`
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueEntry {
+    pub task: Task,
+    pub score: f64,
+    /// The higher-ranked blocked task this one stands in for, if any
+    pub unblocks: Option<String>,
+}
+
+/// The whole work queue in the order `knecht next` would suggest it: delivered tasks awaiting
+/// verification, then active tasks and expired claims by `strategy` score. A blocked task is
+/// replaced by its best leaf blocker nobody holds, or left out when every blocker is held, so
+/// the queue only offers unclaimed work. Each task appears once.
+pub fn rank_tasks_with_fs(strategy: &dyn PriorityStrategy, fs: &dyn FileSystem) -> Result<Vec<QueueEntry>, KnechtError> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (tasks, scores) = score_tasks_with_fs(strategy, now, fs)?;
+    let workflow = workflow_with_fs(fs)?;
+    let expired = expired_claims_with_fs(&tasks, fs)?;
+
+    let score = |task: &Task| scores.get(&task.id).copied().unwrap_or(0.0);
+    let by_score = |a: &&Task, b: &&Task| score(b).total_cmp(&score(a)).then_with(|| a.id.cmp(&b.id));
+    let mut delivered: Vec<&Task> = tasks.iter().filter(|t| t.status == "delivered").collect();
+    delivered.sort_by(by_score);
+    let mut open: Vec<&Task> = tasks.iter()
+        .filter(|t| is_claimable(t, &workflow, &expired))
+        .collect();
+    open.sort_by(by_score);
+
+    let mut queue: Vec<QueueEntry> = delivered.into_iter()
+        .map(|task| QueueEntry { task: task.clone(), score: score(task), unblocks: None })
+        .collect();
+    for task in open {
+        let entry = if has_open_blockers(&task.id, &tasks, &workflow, fs) {
+            let mut chain = vec![task.clone()];
+            match find_best_blocker(&task.id, &tasks, &scores, &workflow, &expired, &mut chain, fs) {
+                Some(leaf) => QueueEntry { score: score(&leaf), task: leaf, unblocks: Some(task.id.clone()) },
+                None => continue,
+            }
+        } else {
+            QueueEntry { task: task.clone(), score: score(task), unblocks: None }
+        };
+        if !queue.iter().any(|queued| queued.task.id == entry.task.id) {
+            queue.push(entry);
         }
+    }
+    Ok(queue)
+}
 
-    Ok(best_task)
+/// IDs of claimed tasks whose latest claim is older than `claim_lease`; they are offered by
+/// `next` again. Claims made before the history log existed never expire.
+pub fn expired_claims_with_fs(tasks: &[Task], fs: &dyn FileSystem) -> Result<HashSet<String>, KnechtError> {
+    let Some(lease) = claim_lease_secs_with_fs(fs)? else { return Ok(HashSet::new()) };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+    let claims = latest_claims_with_fs(fs)?;
+    Ok(tasks.iter()
+        .filter(|t| t.status == "claimed")
+        .filter(|t| claims.get(&t.id).is_some_and(|claim| claim.timestamp.saturating_add(lease) < now))
+        .map(|t| t.id.clone())
+        .collect())
 }
 
 /// Append a pain entry to the append-only pain log (.knecht/pain)
 pub fn append_pain_entry_with_fs(entry: &PainEntry, fs: &dyn FileSystem) -> Result<(), KnechtError> {
-    let pain_path = Path::new(".knecht/pain");
+    let mut writer = fs.append(Path::new(".knecht/pain"))?;
+    writeln!(writer, "{}", format_pain_line(entry))?;
+    Ok(())
+}
 
+fn format_pain_line(entry: &PainEntry) -> String {
     let source_type_str = match entry.source_type {
         PainSourceType::Manual => "manual",
         PainSourceType::Skip => "skip",
         PainSourceType::Sentry => "sentry",
     };
     let source_id_str = entry.source_id.as_deref().unwrap_or("");
+    // Optional fields are `;key=value` annotations on the source type, so older lines keep parsing
+    let mut annotations = String::new();
+    if let Some(during) = &entry.during_task {
+        annotations.push_str(&format!(";during={}", during));
+    }
+    if entry.count != 1 {
+        annotations.push_str(&format!(";count={}", entry.count));
+    }
 
-    let mut writer = fs.append(pain_path)?;
-    writeln!(writer, "{}|{}|{}|{}|{}",
-        entry.task_id, entry.timestamp, source_type_str, source_id_str, entry.description)?;
-
-    Ok(())
+    format!("{}|{}|{}{}|{}|{}",
+        entry.task_id, entry.timestamp, source_type_str, annotations, source_id_str, entry.description)
 }
 
-/// Read all pain entries from the pain log
+/// Read all pain entries from the pain log, with retractions and moves applied
 pub fn read_pain_entries_with_fs(fs: &dyn FileSystem) -> Result<Vec<PainEntry>, KnechtError> {
+    Ok(read_pain_log_with_fs(fs)?.0.into_iter().map(|(_, entry)| entry).collect())
+}
+
+/// Pain entries still in effect, each with the key corrections refer to it by
+/// (`pain_entry_key`), and the number of lines read, correction records (`retract`, `move`)
+/// included
+fn read_pain_log_with_fs(fs: &dyn FileSystem) -> Result<(Vec<(String, PainEntry)>, usize), KnechtError> {
     let pain_path = Path::new(".knecht/pain");
 
     if !fs.exists(pain_path) {
-        return Ok(Vec::new());
+        return Ok((Vec::new(), 0));
     }
 
     let reader = fs.open(pain_path)?;
-    let mut entries = Vec::new();
+    let mut entries: Vec<(String, PainEntry)> = Vec::new();
+    let mut lines = 0;
 
     for line in reader.lines() {
         let line = line?;
         if line.is_empty() { continue; }
+        lines += 1;
 
         let parts: Vec<&str> = line.splitn(5, '|').collect();
         if parts.len() >= 5 {
-            entries.push(PainEntry {
+            let mut source = parts[2].split(';');
+            let source_type = source.next().unwrap_or("");
+            let annotation = |key: &str| source.clone()
+                .find_map(|field| field.strip_prefix(key)?.strip_prefix('='))
+                .filter(|value| !value.is_empty())
+                .map(str::to_string);
+
+            // Corrections name the key of the entry they apply to in the source ID column
+            if source_type == "retract" || source_type == "move" {
+                if let Some(position) = entries.iter().position(|(key, _)| key == parts[3]) {
+                    if source_type == "retract" {
+                        entries.remove(position);
+                    } else {
+                        entries[position].1.task_id = parts[0].to_string();
+                    }
+                }
+                continue;
+            }
+
+            let entry = PainEntry {
                 task_id: parts[0].to_string(),
                 timestamp: parts[1].parse().unwrap_or(0),
-                source_type: match parts[2] {
+                source_type: match source_type {
                     "skip" => PainSourceType::Skip,
                     "sentry" => PainSourceType::Sentry,
                     _ => PainSourceType::Manual,
                 },
                 source_id: if parts[3].is_empty() { None } else { Some(parts[3].to_string()) },
                 description: parts[4].to_string(),
-            });
+                during_task: annotation("during"),
+                count: annotation("count").and_then(|count| count.parse().ok()).unwrap_or(1),
+            };
+            entries.push((pain_entry_key(&entry), entry));
         }
     }
 
-    Ok(entries)
+    Ok((entries, lines))
+}
+
+/// Identifies a pain entry by what was logged - task, timestamp, source and description - so
+/// corrections keep pointing at it when lines are merged in before it or folded by
+/// `compact_pain_log_with_fs`. A 64-bit FNV-1a hash, in hex.
+fn pain_entry_key(entry: &PainEntry) -> String {
+    let logged = PainEntry { count: 1, ..entry.clone() };
+    let hash = format_pain_line(&logged).bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// The key of a task's `entry`th (1-based) pain entry, as numbered by `knecht show`
+fn pain_entry_key_with_fs(task_id: &str, entry: usize, fs: &dyn FileSystem) -> Result<(String, PainEntry), KnechtError> {
+    let task_entries: Vec<(String, PainEntry)> = read_pain_log_with_fs(fs)?.0.into_iter()
+        .filter(|(_, e)| e.task_id == task_id)
+        .collect();
+    let count = task_entries.len();
+    entry.checked_sub(1)
+        .and_then(|index| task_entries.into_iter().nth(index))
+        .ok_or_else(|| KnechtError::PainEntryNotFound { task_id: task_id.to_string(), entry, count })
+}
+
+/// Retract a task's `entry`th pain entry by appending a retraction record. Returns the
+/// retracted entry.
+pub fn retract_pain_entry_with_fs(task_id: &str, entry: usize, reason: Option<&str>, fs: &dyn FileSystem) -> Result<PainEntry, KnechtError> {
+    let (key, retracted) = pain_entry_key_with_fs(task_id, entry, fs)?;
+    let mut writer = fs.append(Path::new(".knecht/pain"))?;
+    writeln!(writer, "{}|{}|retract|{}|{}", task_id, now_secs(), key, reason.unwrap_or(""))?;
+    Ok(retracted)
+}
+
+/// Move a task's `entry`th pain entry to another task by appending a move record. Returns
+/// the entry as it now reads.
+pub fn move_pain_entry_with_fs(task_id: &str, entry: usize, to_task: &str, fs: &dyn FileSystem) -> Result<PainEntry, KnechtError> {
+    find_task_by_id_with_fs(to_task, fs)?;
+    let (key, moved) = pain_entry_key_with_fs(task_id, entry, fs)?;
+    let mut writer = fs.append(Path::new(".knecht/pain"))?;
+    writeln!(writer, "{}|{}|move|{}|", to_task, now_secs(), key)?;
+    Ok(PainEntry { task_id: to_task.to_string(), ..moved })
 }
 
 /// Get pain entries for a specific task
@@ -564,10 +1242,10 @@ pub fn get_pain_entries_for_task(task_id: &str, fs: &dyn FileSystem) -> Result<V
 /// Get pain count for a specific task from the pain log
 pub fn get_pain_count_for_task(task_id: &str, fs: &dyn FileSystem) -> Result<u32, KnechtError> {
     let entries = read_pain_entries_with_fs(fs)?;
-    Ok(entries.iter().filter(|e| e.task_id == task_id).count() as u32)
+    Ok(entries.iter().filter(|e| e.task_id == task_id).map(|e| e.count).sum())
 }
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Get pain counts for all tasks (more efficient for bulk operations like list)
 pub fn get_all_pain_counts(fs: &dyn FileSystem) -> Result<HashMap<String, u32>, KnechtError> {
@@ -575,15 +1253,57 @@ pub fn get_all_pain_counts(fs: &dyn FileSystem) -> Result<HashMap<String, u32>,
     let mut counts: HashMap<String, u32> = HashMap::new();
 
     for entry in entries {
-        *counts.entry(entry.task_id).or_insert(0) += 1;
+        *counts.entry(entry.task_id).or_insert(0) += entry.count;
     }
 
     Ok(counts)
 }
 
+/// Get decayed pain scores for all tasks. Each entry weighs its count, halved for every half-life
+/// (`pain_half_life`, per source `pain_half_life.<source>`) since it was recorded; without a
+/// half-life the score equals the pain count.
+pub fn get_all_pain_scores(now: u64, fs: &dyn FileSystem) -> Result<HashMap<String, f64>, KnechtError> {
+    let mut scores: HashMap<String, f64> = HashMap::new();
+
+    for (entry, weight) in get_pain_weights_with_fs(now, fs)? {
+        *scores.entry(entry.task_id).or_insert(0.0) += weight;
+    }
+
+    Ok(scores)
+}
+
+/// Every pain entry with its weight at `now`, as summed by `get_all_pain_scores`
+pub fn get_pain_weights_with_fs(now: u64, fs: &dyn FileSystem) -> Result<Vec<(PainEntry, f64)>, KnechtError> {
+    let half_lives = [
+        (PainSourceType::Manual, pain_half_life_secs_with_fs("manual", fs)?),
+        (PainSourceType::Skip, pain_half_life_secs_with_fs("skip", fs)?),
+        (PainSourceType::Sentry, pain_half_life_secs_with_fs("sentry", fs)?),
+    ];
+
+    Ok(read_pain_entries_with_fs(fs)?.into_iter()
+        .map(|entry| {
+            let half_life = half_lives.iter().find(|(source, _)| *source == entry.source_type).and_then(|(_, secs)| *secs);
+            let decay = match half_life {
+                Some(half_life) => 0.5_f64.powf(now.saturating_sub(entry.timestamp) as f64 / half_life as f64),
+                None => 1.0,
+            };
+            let weight = entry.count as f64 * decay;
+            (entry, weight)
+        })
+        .collect())
+}
+
 pub fn increment_pain_count_with_fs(task_id: &str, pain_description: Option<&str>, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
+    record_pain_with_fs(task_id, pain_description, None, fs)
+}
+
+/// Record a manual pain instance felt while working on `during_task`
+pub fn record_pain_with_fs(task_id: &str, pain_description: Option<&str>, during_task: Option<&str>, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
     // Verify task exists
     let task = find_task_by_id_with_fs(task_id, fs)?;
+    if let Some(during_id) = during_task {
+        find_task_by_id_with_fs(during_id, fs)?;
+    }
 
     // Append to pain log (append-only for merge conflict reduction)
     let entry = PainEntry {
@@ -595,12 +1315,47 @@ pub fn increment_pain_count_with_fs(task_id: &str, pain_description: Option<&str
         source_type: PainSourceType::Manual,
         source_id: None,
         description: pain_description.unwrap_or("").to_string(),
+        during_task: during_task.filter(|id| *id != task_id).map(str::to_string),
+        count: 1,
     };
     append_pain_entry_with_fs(&entry, fs)?;
 
     Ok(task)
 }
 
+/// Fold runs of consecutive, otherwise identical pain lines into single weighted entries and
+/// apply retractions and moves. Returns the number of log lines before and after.
+pub fn compact_pain_log_with_fs(fs: &dyn FileSystem) -> Result<(usize, usize), KnechtError> {
+    let (entries, before) = read_pain_log_with_fs(fs)?;
+    let entries = entries.into_iter().map(|(_, entry)| entry);
+
+    let mut compacted: Vec<PainEntry> = Vec::new();
+    for entry in entries {
+        match compacted.last_mut() {
+            Some(last) if PainEntry { count: entry.count, ..last.clone() } == entry => {
+                last.count = last.count.saturating_add(entry.count);
+            }
+            _ => compacted.push(entry),
+        }
+    }
+
+    if compacted.len() < before {
+        let mut writer = fs.create(Path::new(".knecht/pain"))?;
+        for entry in &compacted {
+            writeln!(writer, "{}", format_pain_line(entry))?;
+        }
+    }
+    Ok((before, compacted.len()))
+}
+
+/// The task `actor` is working on: of the tasks they hold, the one they claimed last
+pub fn current_task_for_actor_with_fs(actor: &str, fs: &dyn FileSystem) -> Result<Option<String>, KnechtError> {
+    let claims = latest_claims_with_fs(fs)?;
+    Ok(tasks_held_by_with_fs(actor, fs)?.into_iter()
+        .max_by_key(|t| (claims.get(&t.id).map(|claim| claim.timestamp), t.id.clone()))
+        .map(|t| t.id))
+}
+
 pub fn delete_task_with_fs(task_id: &str, fs: &dyn FileSystem) -> Result<Task, KnechtError> {
     // Read the task first to return its data
     let task = find_task_by_id_with_fs(task_id, fs)?;
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::{parse_edited_task, EditedTask, FileSystem, KnechtError};
+
+const TEMPLATES_DIR: &str = ".knecht/templates";
+
+/// Summary of a template in `.knecht/templates`, for `knecht templates list`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    /// The unrendered title line, e.g. `Extract Method: {{method}}`
+    pub title: String,
+    /// Placeholders the template uses, in order of first appearance
+    pub variables: Vec<String>,
+}
+
+fn template_path(name: &str) -> Result<PathBuf, KnechtError> {
+    if name.is_empty() || name.contains('/') || name.contains('\\') || name.starts_with('.') {
+        return Err(KnechtError::TemplateError { name: name.to_string(), message: "invalid template name".to_string() });
+    }
+    Ok(Path::new(TEMPLATES_DIR).join(name))
+}
+
+fn read_template_with_fs(name: &str, fs: &dyn FileSystem) -> Result<String, KnechtError> {
+    let path = template_path(name)?;
+    if !fs.is_file(&path) {
+        return Err(KnechtError::TemplateError { name: name.to_string(), message: "no such template in .knecht/templates".to_string() });
+    }
+    let mut content = String::new();
+    fs.open(&path)?.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Placeholder names (`{{name}}`) in `text`, in order of first appearance
+fn placeholders(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else { break };
+        let name = rest[start + 2..start + end].trim().to_string();
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+        rest = &rest[start + end + 2..];
+    }
+    names
+}
+
+fn substitute(text: &str, values: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else { break };
+        out.push_str(&rest[..start]);
+        let name = rest[start + 2..start + end].trim();
+        match values.get(name) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// List the templates in `.knecht/templates`, sorted by name
+pub fn list_templates_with_fs(fs: &dyn FileSystem) -> Result<Vec<TemplateInfo>, KnechtError> {
+    let dir = Path::new(TEMPLATES_DIR);
+    if !fs.is_dir(dir) {
+        return Ok(Vec::new());
+    }
+
+    let mut templates = Vec::new();
+    for entry in fs.read_dir(dir)? {
+        let Some(name) = entry.file_name().and_then(|n| n.to_str()) else { continue };
+        if name.starts_with('.') || !fs.is_file(&entry) {
+            continue;
+        }
+        let content = read_template_with_fs(name, fs)?;
+        let title = content.lines()
+            .skip_while(|line| line.trim_end() != "--- title ---")
+            .nth(1)
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        templates.push(TemplateInfo { name: name.to_string(), title, variables: placeholders(&content) });
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+/// Fill in a template's `{{placeholders}}` and parse it into task fields.
+///
+/// Templates use the same sectioned layout as `knecht edit`. `title` fills `{{title}}`; when the
+/// template's title has no such placeholder, a given `title` replaces the template's title instead.
+pub fn render_template_with_fs(name: &str, title: Option<&str>, vars: &BTreeMap<String, String>, fs: &dyn FileSystem) -> Result<EditedTask, KnechtError> {
+    let content = read_template_with_fs(name, fs)?;
+
+    let mut values = vars.clone();
+    if let Some(title) = title {
+        values.insert("title".to_string(), title.to_string());
+    }
+
+    let missing: Vec<String> = placeholders(&content).into_iter()
+        .filter(|name| !values.contains_key(name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(KnechtError::TemplateError {
+            name: name.to_string(),
+            message: format!("missing values for {} (use --var name=value)", missing.join(", ")),
+        });
+    }
+
+    let mut task = parse_edited_task(&substitute(&content, &values)).map_err(|err| KnechtError::TemplateError {
+        name: name.to_string(),
+        message: err.to_string(),
+    })?;
+
+    if let Some(title) = title
+        && !placeholders(&content).iter().any(|p| p == "title")
+    {
+        task.title = title.to_string();
+    }
+
+    Ok(task)
+}
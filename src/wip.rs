@@ -0,0 +1,41 @@
+use crate::{config_limit_with_fs, latest_claims_with_fs, read_tasks_with_fs, FileSystem, KnechtError, Task};
+
+/// Claimed tasks whose latest claim was made by `actor`
+pub fn tasks_held_by_with_fs(actor: &str, fs: &dyn FileSystem) -> Result<Vec<Task>, KnechtError> {
+    let claims = latest_claims_with_fs(fs)?;
+    Ok(read_tasks_with_fs(fs)?.into_iter()
+        .filter(|t| t.status == "claimed" && claims.get(&t.id).is_some_and(|claim| claim.actor == actor))
+        .collect())
+}
+
+/// Check that `actor` may claim another task under `wip_limit_per_agent` (claimed tasks per
+/// identity) and `wip_limit` (claimed or delivered tasks in the repository)
+pub fn check_wip_limits_with_fs(actor: &str, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    check_claim_limits_with_fs(actor, None, fs)
+}
+
+/// Check that `actor` may claim `task`. Taking over a task that is already claimed or
+/// delivered adds to the actor's work but not to the repository's.
+pub(crate) fn check_claim_limits_with_fs(actor: &str, task: Option<&Task>, fs: &dyn FileSystem) -> Result<(), KnechtError> {
+    let already_in_progress = task.is_some_and(|t| t.status == "claimed" || t.status == "delivered");
+
+    if let Some(limit) = config_limit_with_fs("wip_limit_per_agent", fs)? {
+        let held = tasks_held_by_with_fs(actor, fs)?;
+        if held.len() >= limit {
+            return Err(KnechtError::WipLimitExceeded { actor: Some(actor.to_string()), limit, held });
+        }
+    }
+
+    if !already_in_progress
+        && let Some(limit) = config_limit_with_fs("wip_limit", fs)?
+    {
+        let held: Vec<Task> = read_tasks_with_fs(fs)?.into_iter()
+            .filter(|t| t.status == "claimed" || t.status == "delivered")
+            .collect();
+        if held.len() >= limit {
+            return Err(KnechtError::WipLimitExceeded { actor: None, limit, held });
+        }
+    }
+
+    Ok(())
+}
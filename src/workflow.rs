@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use crate::config::{invalid, parse_list};
+use crate::{read_config_with_fs, FileSystem, KnechtError};
+
+/// Statuses knecht's own commands use: `add` creates open tasks, `start` claims them,
+/// `deliver` delivers them and `done`/`verify` complete them
+pub const DEFAULT_STATUSES: &[&str] = &["open", "claimed", "delivered", "done"];
+
+/// The statuses a repository uses and how tasks may move between them, from the
+/// `statuses`, `status.<name>.glyph`, `transitions.<name>`, `active_statuses` and
+/// `resolved_statuses` settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workflow {
+    pub statuses: Vec<String>,
+    glyphs: BTreeMap<String, String>,
+    /// Allowed targets per status; a status without an entry may move to any other
+    transitions: BTreeMap<String, Vec<String>>,
+    /// Statuses `next` suggests work from
+    pub active: Vec<String>,
+    /// Statuses that satisfy a blocker
+    pub resolved: Vec<String>,
+}
+
+impl Default for Workflow {
+    fn default() -> Self {
+        Workflow {
+            statuses: DEFAULT_STATUSES.iter().map(|s| s.to_string()).collect(),
+            glyphs: BTreeMap::new(),
+            transitions: BTreeMap::new(),
+            active: vec!["open".to_string()],
+            resolved: vec!["done".to_string()],
+        }
+    }
+}
+
+impl Workflow {
+    pub fn is_known(&self, status: &str) -> bool {
+        self.statuses.iter().any(|s| s == status)
+    }
+
+    pub fn is_active(&self, status: &str) -> bool {
+        self.active.iter().any(|s| s == status)
+    }
+
+    pub fn is_resolved(&self, status: &str) -> bool {
+        self.resolved.iter().any(|s| s == status)
+    }
+
+    /// The checkbox `knecht list` shows for a status
+    pub fn glyph(&self, status: &str) -> &str {
+        if let Some(glyph) = self.glyphs.get(status) {
+            return glyph;
+        }
+        match status {
+            "open" => "[ ]",
+            "claimed" => "[~]",
+            "delivered" => "[>]",
+            "done" => "[x]",
+            _ => "[?]",
+        }
+    }
+
+    /// Statuses a task in `from` may move to
+    pub fn allowed_moves(&self, from: &str) -> Vec<String> {
+        match self.transitions.get(from) {
+            Some(targets) => targets.clone(),
+            None => self.statuses.iter().filter(|s| *s != from).cloned().collect(),
+        }
+    }
+
+    /// Check that `task_id` may move from `from` to `to`. Staying in the same status is always allowed.
+    pub fn check_move(&self, task_id: &str, from: &str, to: &str) -> Result<(), KnechtError> {
+        if !self.is_known(to) {
+            return Err(KnechtError::UnknownStatus { status: to.to_string(), known: self.statuses.clone() });
+        }
+        let allowed = self.allowed_moves(from);
+        if from != to && !allowed.iter().any(|s| s == to) {
+            return Err(KnechtError::InvalidTransition { task_id: task_id.to_string(), from: from.to_string(), to: to.to_string(), allowed });
+        }
+        Ok(())
+    }
+}
+
+/// Load the repository's workflow; the default (open, claimed, delivered, done with any move
+/// allowed) when nothing is configured
+pub fn workflow_with_fs(fs: &dyn FileSystem) -> Result<Workflow, KnechtError> {
+    let config = read_config_with_fs(fs)?;
+    let mut workflow = Workflow::default();
+
+    if let Some(value) = config.get("statuses") {
+        workflow.statuses = parse_list(value);
+        for required in ["open", "done"] {
+            if !workflow.is_known(required) {
+                return Err(invalid("statuses", format!("must include '{}'", required)));
+            }
+        }
+    }
+
+    let check_known = |key: &str, statuses: &[String]| match statuses.iter().find(|s| !workflow.is_known(s)) {
+        Some(status) => Err(invalid(key, format!("'{}' is not listed in statuses", status))),
+        None => Ok(()),
+    };
+
+    let mut glyphs = BTreeMap::new();
+    let mut transitions = BTreeMap::new();
+    for (key, value) in &config {
+        if let Some(status) = key.strip_prefix("status.").and_then(|rest| rest.strip_suffix(".glyph")) {
+            check_known(key, &[status.to_string()])?;
+            glyphs.insert(status.to_string(), value.clone());
+        } else if let Some(status) = key.strip_prefix("transitions.") {
+            let targets = parse_list(value);
+            check_known(key, &[status.to_string()])?;
+            check_known(key, &targets)?;
+            transitions.insert(status.to_string(), targets);
+        }
+    }
+
+    let mut active = workflow.active.clone();
+    if let Some(value) = config.get("active_statuses") {
+        active = parse_list(value);
+        check_known("active_statuses", &active)?;
+    }
+    let mut resolved = workflow.resolved.clone();
+    if let Some(value) = config.get("resolved_statuses") {
+        resolved = parse_list(value);
+        check_known("resolved_statuses", &resolved)?;
+    }
+
+    workflow.glyphs = glyphs;
+    workflow.transitions = transitions;
+    workflow.active = active;
+    workflow.resolved = resolved;
+    Ok(workflow)
+}
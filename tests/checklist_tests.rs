@@ -0,0 +1,165 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, run_command_with_env, setup_temp_dir, with_initialized_repo};
+use knecht::{check_criteria_items_with_fs, find_task_by_id_with_fs, force_task_done_with_fs, get_history_for_task, mark_task_done_with_fs, parse_criteria_items, CriteriaItem, KnechtError, MemoryFileSystem};
+
+fn item(text: &str, checked: bool) -> CriteriaItem {
+    CriteriaItem { text: text.to_string(), checked }
+}
+
+#[test]
+fn test_parse_criteria_items_strips_bullets_and_checkboxes() {
+    let items = parse_criteria_items("- [x] Tests pass\n* [ ] Docs updated\n\n1. Reviewed\n  + [X] Nested\nPlain line");
+
+    assert_eq!(items, vec![
+        item("Tests pass", true),
+        item("Docs updated", false),
+        item("Reviewed", false),
+        item("Nested", true),
+        item("Plain line", false),
+    ]);
+}
+
+#[test]
+fn test_single_line_criteria_is_one_item() {
+    assert_eq!(parse_criteria_items("Tests pass, payments are recorded"), vec![item("Tests pass, payments are recorded", false)]);
+}
+
+fn fs_with_criteria(criteria: &str) -> MemoryFileSystem {
+    let fs = MemoryFileSystem::new().with_dir(".knecht/tasks");
+    let mut writer = Vec::new();
+    knecht::CsvSerializer::write(&[knecht::Task {
+        id: "abc".to_string(),
+        status: "open".to_string(),
+        title: "Task".to_string(),
+        description: None,
+        pain_count: None,
+        acceptance_criteria: Some(criteria.to_string()),
+    }], &mut writer).unwrap();
+    fs.with_file(".knecht/tasks/abc", &String::from_utf8(writer).unwrap())
+}
+
+#[test]
+fn test_check_item_rewrites_line_as_checkbox() {
+    let fs = fs_with_criteria("1. Tests pass\n2. Docs updated");
+
+    let task = check_criteria_items_with_fs("abc", &[2], true, &fs).unwrap();
+
+    assert_eq!(task.acceptance_criteria.as_deref(), Some("1. Tests pass\n2. [x] Docs updated"));
+    assert_eq!(find_task_by_id_with_fs("abc", &fs).unwrap().acceptance_criteria, task.acceptance_criteria);
+}
+
+#[test]
+fn test_uncheck_and_check_all() {
+    let fs = fs_with_criteria("Tests pass\n- [x] Docs updated");
+
+    let all = check_criteria_items_with_fs("abc", &[], true, &fs).unwrap();
+    assert_eq!(all.acceptance_criteria.as_deref(), Some("- [x] Tests pass\n- [x] Docs updated"));
+
+    let unchecked = check_criteria_items_with_fs("abc", &[1], false, &fs).unwrap();
+    assert_eq!(unchecked.acceptance_criteria.as_deref(), Some("- [ ] Tests pass\n- [x] Docs updated"));
+}
+
+#[test]
+fn test_check_out_of_range_item_fails() {
+    let fs = fs_with_criteria("Only item");
+    match check_criteria_items_with_fs("abc", &[2], true, &fs) {
+        Err(KnechtError::CriteriaItemNotFound { item, count, .. }) => assert_eq!((item, count), (2, 1)),
+        other => panic!("expected CriteriaItemNotFound, got {:?}", other),
+    }
+    assert!(check_criteria_items_with_fs("abc", &[0], true, &fs).is_err());
+}
+
+#[test]
+fn test_done_refuses_with_unchecked_items() {
+    let fs = fs_with_criteria("- [x] Tests pass\n- [ ] Docs updated");
+
+    match mark_task_done_with_fs("abc", &fs) {
+        Err(KnechtError::UncheckedCriteria { unchecked, .. }) => assert_eq!(unchecked, vec![(2, "Docs updated".to_string())]),
+        other => panic!("expected UncheckedCriteria, got {:?}", other),
+    }
+    assert_eq!(find_task_by_id_with_fs("abc", &fs).unwrap().status, "open");
+
+    check_criteria_items_with_fs("abc", &[2], true, &fs).unwrap();
+    assert_eq!(mark_task_done_with_fs("abc", &fs).unwrap().status, "done");
+}
+
+#[test]
+fn test_force_done_records_reason_in_history() {
+    let fs = fs_with_criteria("Docs updated");
+
+    force_task_done_with_fs("abc", "Docs live in another repo", &fs).unwrap();
+
+    assert_eq!(find_task_by_id_with_fs("abc", &fs).unwrap().status, "done");
+    let history = get_history_for_task("abc", &fs).unwrap();
+    let events: Vec<&str> = history.iter().map(|e| e.event.as_str()).collect();
+    assert_eq!(events, vec!["done", "forced_done"]);
+    assert_eq!(history[1].detail.as_deref(), Some("Docs live in another repo"));
+}
+
+#[test]
+fn check_command_shows_progress() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Checklist", "-a", "- Tests pass\n- Docs updated\n- Reviewed"], temp).stdout);
+
+        let result = run_command(&["check", &id, "1", "3"], temp);
+
+        assert!(result.success, "check should succeed, got: {}", result.stderr);
+        assert!(result.stdout.contains("2/3 acceptance criteria checked"), "got: {}", result.stdout);
+        assert!(result.stdout.contains("[x] 1. Tests pass"), "got: {}", result.stdout);
+        assert!(result.stdout.contains("[ ] 2. Docs updated"), "got: {}", result.stdout);
+
+        let show = run_command(&["show", &id], temp);
+        assert!(show.stdout.contains("[x] 3. Reviewed"), "show should number items, got: {}", show.stdout);
+    });
+}
+
+#[test]
+fn done_lists_unchecked_items() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Checklist", "-a", "- Tests pass\n- Docs updated"], temp).stdout);
+        run_command(&["check", &id, "1"], temp);
+
+        let result = run_command(&["done", &id], temp);
+
+        assert_eq!(result.exit_code, Some(15));
+        assert!(result.stderr.contains("[E_UNCHECKED_CRITERIA]"), "got: {}", result.stderr);
+        assert!(result.stderr.contains("[ ] 2. Docs updated"), "got: {}", result.stderr);
+        assert!(!result.stderr.contains("Tests pass"), "checked items are not listed, got: {}", result.stderr);
+    });
+}
+
+#[test]
+fn done_force_requires_reason() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Unverifiable"], temp).stdout);
+
+        let no_reason = run_command(&["done", &id, "--force"], temp);
+        assert_eq!(no_reason.exit_code, Some(2), "--force without --reason is a usage error");
+
+        let result = run_command_with_env(&["done", &id, "--force", "--reason", "Verified manually\nin staging"], temp, &[("KNECHT_AGENT", "agent-7")]);
+        assert!(result.success, "got: {}", result.stderr);
+
+        let history = std::fs::read_to_string(temp.join(".knecht/history")).unwrap();
+        assert!(history.contains(&format!("{}|", id)), "got: {}", history);
+        assert!(history.contains("|forced_done|agent-7|Verified manually in staging\n"), "got: {}", history);
+    });
+}
+
+#[test]
+fn check_json_and_errors() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Only"], temp).stdout);
+
+        let out_of_range = run_command(&["check", &id, "5"], temp);
+        assert_eq!(out_of_range.exit_code, Some(16));
+
+        let json = run_command(&["check", &id, "--all", "--json"], temp);
+        let value: serde_json::Value = serde_json::from_str(json.stdout.trim()).unwrap();
+        assert_eq!(value["criteria"][0]["checked"], true);
+
+        let unchecked = run_command(&["done", &id, "--json"], temp);
+        assert!(unchecked.success, "all items checked, got: {}", unchecked.stdout);
+    });
+}
@@ -0,0 +1,154 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, fs_with_tasks, run_command, run_command_with_env, setup_temp_dir, with_initialized_repo};
+use knecht::{acquire_lock_with_fs, claim_next_task_with_fs, get_history_for_task, now_secs, strategy_for_name, FileSystem, KnechtError, MemoryFileSystem};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+fn fs_with_queue() -> MemoryFileSystem {
+    fs_with_tasks(&[("aaa", "open", "Most pain"), ("bbb", "open", "Some pain"), ("ccc", "claimed", "Someone else's"), ("ddd", "delivered", "Awaiting review")])
+        .with_file(".knecht/pain", "aaa|1|manual||A\naaa|1|manual||A\nbbb|1|manual||B\nccc|1|manual||C\nccc|1|manual||C\nccc|1|manual||C\n")
+}
+
+#[test]
+fn test_claim_next_claims_queue_in_order_then_runs_dry() {
+    let fs = fs_with_queue();
+    let strategy = strategy_for_name("pain").unwrap();
+
+    let first = claim_next_task_with_fs(strategy.as_ref(), "agent-1", &fs).unwrap().unwrap();
+    let second = claim_next_task_with_fs(strategy.as_ref(), "agent-2", &fs).unwrap().unwrap();
+    assert_eq!((first.id.as_str(), second.id.as_str()), ("aaa", "bbb"));
+    assert_eq!(first.status, "claimed");
+    assert!(claim_next_task_with_fs(strategy.as_ref(), "agent-3", &fs).unwrap().is_none());
+
+    let history = get_history_for_task("bbb", &fs).unwrap();
+    assert_eq!((history[0].event.as_str(), history[0].actor.as_str()), ("claimed", "agent-2"));
+    assert!(!fs.exists(Path::new(".knecht/lock")), "the lock is released afterwards");
+}
+
+#[test]
+fn test_claim_next_does_not_take_a_claimed_leaf_blocker() {
+    // aaa hurts most but waits on bbb, which alice is working on
+    let fs = fs_with_tasks(&[("aaa", "open", "Blocked"), ("bbb", "claimed", "Alice's"), ("ccc", "open", "Free")])
+        .with_file(".knecht/blockers", "aaa|bbb\n")
+        .with_file(".knecht/pain", "aaa|1|manual||A\naaa|1|manual||A\n")
+        .with_file(".knecht/history", &format!("bbb|{}|claimed|alice|\n", now_secs()))
+        .with_file(".knecht/config", "claim_lease = 1h\n");
+    let strategy = strategy_for_name("pain").unwrap();
+
+    let claimed = claim_next_task_with_fs(strategy.as_ref(), "bob", &fs).unwrap().unwrap();
+    assert_eq!(claimed.id, "ccc");
+    let history = get_history_for_task("bbb", &fs).unwrap();
+    assert_eq!(history.len(), 1, "bbb stays alice's");
+    assert!(claim_next_task_with_fs(strategy.as_ref(), "carol", &fs).unwrap().is_none());
+}
+
+#[test]
+fn test_lock_excludes_others_until_dropped() {
+    let fs = MemoryFileSystem::new().with_dir(".knecht");
+
+    let lock = acquire_lock_with_fs(Duration::ZERO, &fs).unwrap();
+    match acquire_lock_with_fs(Duration::ZERO, &fs) {
+        Err(KnechtError::Locked { holder }) => assert!(holder.contains(&std::process::id().to_string()), "got {}", holder),
+        other => panic!("expected Locked, got {:?}", other.map(|_| ())),
+    }
+    drop(lock);
+    assert!(acquire_lock_with_fs(Duration::ZERO, &fs).is_ok());
+}
+
+#[test]
+fn test_stale_lock_is_broken() {
+    let fs = MemoryFileSystem::new()
+        .with_dir(".knecht")
+        .with_file(".knecht/lock", &format!("999 {}\n", now_secs() - 3_600));
+    assert!(acquire_lock_with_fs(Duration::ZERO, &fs).is_ok());
+}
+
+#[test]
+fn test_only_one_waiter_breaks_a_stale_lock() {
+    let stale = format!("999 {}\n", now_secs() - 3_600);
+    let breaking = MemoryFileSystem::new()
+        .with_dir(".knecht")
+        .with_file(".knecht/lock", &stale)
+        .with_file(".knecht/lock.break", &format!("998 {}\n", now_secs()));
+    assert!(matches!(acquire_lock_with_fs(Duration::ZERO, &breaking), Err(KnechtError::Locked { .. })), "another waiter is breaking it");
+    assert!(breaking.exists(Path::new(".knecht/lock")));
+
+    // A breaker that crashed midway does not wedge the repository
+    let crashed = MemoryFileSystem::new()
+        .with_dir(".knecht")
+        .with_file(".knecht/lock", &stale)
+        .with_file(".knecht/lock.break", &stale);
+    assert!(acquire_lock_with_fs(Duration::from_millis(100), &crashed).is_ok());
+    assert!(!crashed.exists(Path::new(".knecht/lock.break")));
+}
+
+#[test]
+fn test_dropping_a_broken_lock_leaves_the_new_holder_alone() {
+    let fs = MemoryFileSystem::new().with_dir(".knecht");
+    let lock = acquire_lock_with_fs(Duration::ZERO, &fs).unwrap();
+
+    // Held past the stale limit, broken and taken by another process
+    writeln!(fs.create(Path::new(".knecht/lock")).unwrap(), "4242 {}", now_secs()).unwrap();
+    drop(lock);
+    assert!(fs.exists(Path::new(".knecht/lock")));
+}
+
+#[test]
+fn claim_next_leaves_another_agents_blocker_alone() {
+    with_initialized_repo(|temp| {
+        let blocked = extract_task_id(&run_command(&["add", "Blocked", "-a", "Done"], temp).stdout);
+        let blocker = extract_task_id(&run_command(&["add", "Blocker", "-a", "Done"], temp).stdout);
+        run_command(&["block", &blocked, "by", &blocker], temp);
+        run_command_with_env(&["start", &blocker], temp, &[("KNECHT_AGENT", "alice")]);
+
+        let result = run_command(&["claim-next", "--agent", "bob"], temp);
+        assert_eq!(result.exit_code, Some(24), "got: {}{}", result.stdout, result.stderr);
+        let show = run_command(&["show", &blocker], temp);
+        assert!(!show.stdout.contains("claimed by bob"), "got: {}", show.stdout);
+    });
+}
+
+#[test]
+fn concurrent_claim_next_hands_out_distinct_tasks() {
+    with_initialized_repo(|temp| {
+        for title in ["One", "Two", "Three"] {
+            run_command(&["add", title, "-a", "Done"], temp);
+        }
+
+        let agents: Vec<_> = (0..4).map(|n| {
+            let dir = temp.clone();
+            std::thread::spawn(move || run_command(&["--json", "claim-next", "--agent", &format!("agent-{}", n)], &dir))
+        }).collect();
+        let results: Vec<_> = agents.into_iter().map(|handle| handle.join().unwrap()).collect();
+
+        let claimed: Vec<String> = results.iter()
+            .filter(|r| r.success)
+            .map(|r| serde_json::from_str::<serde_json::Value>(&r.stdout).unwrap()["task"]["id"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(claimed.len(), 3, "results: {:?}", results.iter().map(|r| &r.stderr).collect::<Vec<_>>());
+        assert_eq!(claimed.iter().collect::<HashSet<_>>().len(), 3, "each task is claimed once: {:?}", claimed);
+
+        let empty = results.iter().find(|r| !r.success).unwrap();
+        assert_eq!(empty.exit_code, Some(24));
+        assert!(empty.stdout.contains("E_QUEUE_EMPTY"), "got: {}", empty.stdout);
+    });
+}
+
+#[test]
+fn claim_next_uses_agent_env_by_default() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Only", "-a", "Done"], temp).stdout);
+
+        let result = run_command_with_env(&["claim-next"], temp, &[("KNECHT_AGENT", "agent-9")]);
+        assert!(result.success, "claim-next failed: {}", result.stderr);
+        assert!(result.stdout.contains(&format!("agent-9 claimed task-{}", id)), "got: {}", result.stdout);
+
+        let again = run_command(&["claim-next"], temp);
+        assert_eq!(again.exit_code, Some(24));
+        assert!(again.stderr.contains("[E_QUEUE_EMPTY]"), "got: {}", again.stderr);
+    });
+}
@@ -1,15 +1,25 @@
 #![allow(dead_code)]
 
+use knecht::MemoryFileSystem;
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
 
 pub struct TestResult {
     pub success: bool,
+    pub exit_code: Option<i32>,
     pub stdout: String,
     pub stderr: String,
 }
 
+/// An in-memory repository holding `(id, status, title)` tasks in CSV form; chain
+/// `with_file` for config, blockers, pain or history
+pub fn fs_with_tasks(tasks: &[(&str, &str, &str)]) -> MemoryFileSystem {
+    tasks.iter().fold(MemoryFileSystem::new().with_dir(".knecht/tasks"), |fs, (id, status, title)| {
+        fs.with_file(format!(".knecht/tasks/{}", id), &format!("{},{},{},,\n", id, status, title))
+    })
+}
+
 pub fn setup_temp_dir() -> PathBuf {
     let temp = std::env::temp_dir().join(format!("knecht-test-{}", rand_string()));
     fs::create_dir_all(&temp).unwrap();
@@ -30,15 +40,39 @@ pub fn cleanup_temp_dir(path: PathBuf) {
     let _ = fs::remove_dir_all(path);
 }
 
+/// The knecht binary, isolated from the developer's own `~/.config/knecht/config`
+fn knecht_command() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_knecht"));
+    command.env("XDG_CONFIG_HOME", std::env::temp_dir().join("knecht-test-no-user-config"));
+    command
+}
+
 pub fn run_command(args: &[&str], working_dir: &PathBuf) -> TestResult {
-    let output = Command::new(env!("CARGO_BIN_EXE_knecht"))
+    let output = knecht_command()
+        .args(args)
+        .current_dir(working_dir)
+        .output()
+        .expect("Failed to execute command");
+
+    TestResult {
+        success: output.status.success(),
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    }
+}
+
+pub fn run_command_with_env(args: &[&str], working_dir: &PathBuf, env: &[(&str, &str)]) -> TestResult {
+    let output = knecht_command()
         .args(args)
+        .envs(env.iter().copied())
         .current_dir(working_dir)
         .output()
         .expect("Failed to execute command");
 
     TestResult {
         success: output.status.success(),
+        exit_code: output.status.code(),
         stdout: String::from_utf8_lossy(&output.stdout).to_string(),
         stderr: String::from_utf8_lossy(&output.stderr).to_string(),
     }
@@ -0,0 +1,178 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, run_command_with_env, setup_temp_dir, with_initialized_repo};
+use knecht::{
+    add_task_with_fs, claim_lease_secs_with_fs, done_banner_with_fs, find_next_task_with_fs, required_fields_with_fs, validate_config_value,
+    KnechtError, MemoryFileSystem,
+};
+use std::fs;
+
+#[test]
+fn test_defaults_without_config() {
+    let fs = MemoryFileSystem::new();
+
+    assert_eq!(required_fields_with_fs(&fs).unwrap(), vec!["acceptance_criteria"]);
+    assert_eq!(claim_lease_secs_with_fs(&fs).unwrap(), None);
+    assert_eq!(done_banner_with_fs(&fs).unwrap().as_deref(), Some(knecht::DEFAULT_DONE_BANNER));
+}
+
+#[test]
+fn test_id_length_controls_new_ids() {
+    let fs = MemoryFileSystem::new().with_file(".knecht/config", "id_length = 10\n");
+
+    let id = add_task_with_fs("Task".to_string(), None, Some("Done".to_string()), &fs).unwrap();
+
+    assert_eq!(id.len(), 10);
+}
+
+#[test]
+fn test_done_banner_escapes_and_empty() {
+    let custom = MemoryFileSystem::new().with_file(".knecht/config", "done_banner = Run the tests\\nThen commit\n");
+    assert_eq!(done_banner_with_fs(&custom).unwrap().as_deref(), Some("Run the tests\nThen commit"));
+
+    let empty = MemoryFileSystem::new().with_file(".knecht/config", "done_banner =\n");
+    assert_eq!(done_banner_with_fs(&empty).unwrap(), None);
+}
+
+#[test]
+fn test_validate_config_value() {
+    assert!(validate_config_value("id_length", "8").is_ok());
+    assert!(validate_config_value("claim_lease", "2h").is_ok());
+    assert!(validate_config_value("required_fields", "description, acceptance_criteria").is_ok());
+
+    for (key, value) in [("id_length", "2"), ("claim_lease", "soon"), ("required_fields", "owner"), ("list_footer", "maybe"), ("colour", "red")] {
+        assert!(matches!(validate_config_value(key, value), Err(KnechtError::InvalidConfig { .. })), "{} = {} should be rejected", key, value);
+    }
+}
+
+#[test]
+fn test_next_offers_claims_past_their_lease() {
+    let fs = MemoryFileSystem::new()
+        .with_dir(".knecht/tasks")
+        .with_file(".knecht/tasks/abc", "abc,claimed,Stale claim,,\n")
+        .with_file(".knecht/history", "abc|1|claimed|agent-1|\n");
+
+    assert!(find_next_task_with_fs(&fs).unwrap().is_none(), "claims hold without a lease");
+
+    let fs = fs.with_file(".knecht/config", "claim_lease = 1h\n");
+    assert_eq!(find_next_task_with_fs(&fs).unwrap().map(|t| t.id), Some("abc".to_string()));
+}
+
+#[test]
+fn config_set_and_get_round_trip() {
+    with_initialized_repo(|temp| {
+        let xdg = temp.join("xdg");
+        let env = [("XDG_CONFIG_HOME", xdg.to_str().unwrap())];
+
+        let default = run_command_with_env(&["config", "get", "id_length", "--json"], temp, &env);
+        let value: serde_json::Value = serde_json::from_str(default.stdout.trim()).unwrap();
+        assert_eq!((value["value"].as_str(), value["source"].as_str()), (Some("6"), Some("default")));
+
+        let set = run_command_with_env(&["config", "set", "id_length", "8"], temp, &env);
+        assert!(set.success, "got: {}", set.stderr);
+        assert!(fs::read_to_string(temp.join(".knecht/config")).unwrap().contains("id_length = 8"));
+
+        let get = run_command_with_env(&["config", "get", "id_length"], temp, &env);
+        assert_eq!(get.stdout.trim(), "8");
+
+        let add = run_command_with_env(&["add", "Task", "-a", "Done"], temp, &env);
+        assert_eq!(extract_task_id(&add.stdout).len(), 8);
+    });
+}
+
+#[test]
+fn user_config_overrides_repository() {
+    with_initialized_repo(|temp| {
+        let xdg = temp.join("xdg");
+        let env = [("XDG_CONFIG_HOME", xdg.to_str().unwrap())];
+        run_command_with_env(&["config", "set", "list_footer", "true"], temp, &env);
+
+        let set = run_command_with_env(&["config", "set", "list_footer", "false", "--user"], temp, &env);
+        assert!(set.success, "got: {}", set.stderr);
+        assert!(fs::read_to_string(xdg.join("knecht/config")).unwrap().contains("list_footer = false"));
+
+        let get = run_command_with_env(&["config", "get", "list_footer", "--json"], temp, &env);
+        let value: serde_json::Value = serde_json::from_str(get.stdout.trim()).unwrap();
+        assert_eq!(value["source"], "user");
+
+        run_command_with_env(&["add", "Task", "-a", "Done"], temp, &env);
+        let list = run_command_with_env(&["list"], temp, &env);
+        assert!(!list.stdout.contains("Usage instructions"), "got: {}", list.stdout);
+    });
+}
+
+#[test]
+fn user_config_cannot_override_repository_policy() {
+    with_initialized_repo(|temp| {
+        let xdg = temp.join("xdg");
+        let env = [("XDG_CONFIG_HOME", xdg.to_str().unwrap())];
+        run_command_with_env(&["config", "set", "require_independent_verifier", "true"], temp, &env);
+
+        let set = run_command_with_env(&["config", "set", "require_independent_verifier", "false", "--user"], temp, &env);
+        assert_eq!(set.exit_code, Some(12));
+        assert!(set.stderr.contains("repository setting"), "got: {}", set.stderr);
+        assert!(!xdg.join("knecht/config").exists());
+
+        // A hand-edited user file is ignored for repository keys
+        fs::create_dir_all(xdg.join("knecht")).unwrap();
+        fs::write(xdg.join("knecht/config"), "require_independent_verifier = false\nformat = markdown\nwip_limit = 1\n").unwrap();
+        let get = run_command_with_env(&["config", "get", "require_independent_verifier", "--json"], temp, &env);
+        let value: serde_json::Value = serde_json::from_str(get.stdout.trim()).unwrap();
+        assert_eq!((value["value"].as_str(), value["source"].as_str()), (Some("true"), Some("repo")));
+
+        let id = extract_task_id(&run_command_with_env(&["add", "Task", "-a", "Done"], temp, &env).stdout);
+        let other = extract_task_id(&run_command_with_env(&["add", "Other", "-a", "Done"], temp, &env).stdout);
+        assert!(fs::read_to_string(temp.join(".knecht/tasks").join(&id)).unwrap().starts_with(&format!("{},open,Task", id)));
+        assert!(run_command_with_env(&["list"], temp, &env).success, "the CSV repository stays readable");
+        assert!(run_command_with_env(&["start", &id], temp, &env).success);
+        assert!(run_command_with_env(&["start", &other], temp, &env).success, "the user file's wip_limit is ignored");
+    });
+}
+
+#[test]
+fn config_set_rejects_bad_values_and_format() {
+    with_initialized_repo(|temp| {
+        let unknown = run_command(&["config", "set", "colour", "red"], temp);
+        assert_eq!(unknown.exit_code, Some(12));
+        assert!(unknown.stderr.contains("[E_CONFIG]"), "got: {}", unknown.stderr);
+
+        let format = run_command(&["config", "set", "format", "markdown"], temp);
+        assert_eq!(format.exit_code, Some(2));
+        assert!(format.stderr.contains("knecht migrate --to markdown"), "got: {}", format.stderr);
+    });
+}
+
+#[test]
+fn required_fields_can_drop_or_add_requirements() {
+    with_initialized_repo(|temp| {
+        fs::write(temp.join(".knecht/config"), "required_fields = none\n").unwrap();
+        let relaxed = run_command(&["add", "No criteria"], temp);
+        assert!(relaxed.success, "got: {}", relaxed.stderr);
+
+        fs::write(temp.join(".knecht/config"), "required_fields = description, acceptance_criteria\n").unwrap();
+        let strict = run_command(&["add", "Task", "-a", "Done"], temp);
+        assert_eq!(strict.exit_code, Some(2));
+        assert!(strict.stderr.contains("Description is required"), "got: {}", strict.stderr);
+    });
+}
+
+#[test]
+fn list_statuses_and_done_banner_from_config() {
+    with_initialized_repo(|temp| {
+        fs::write(temp.join(".knecht/config"), "list_statuses = claimed\ndone_banner = Post in #standup\n").unwrap();
+        let open = extract_task_id(&run_command(&["add", "Open task", "-a", "Done"], temp).stdout);
+        let claimed = extract_task_id(&run_command(&["add", "Claimed task", "-a", "Done"], temp).stdout);
+        run_command(&["start", &claimed], temp);
+
+        let list = run_command(&["list"], temp);
+        assert!(list.stdout.contains("Claimed task"), "got: {}", list.stdout);
+        assert!(!list.stdout.contains("Open task"), "got: {}", list.stdout);
+        assert!(list.stdout.contains("Showing claimed tasks only"), "got: {}", list.stdout);
+
+        run_command(&["check", &open, "--all"], temp);
+        let done = run_command(&["done", &open], temp);
+        assert!(done.stdout.contains("Post in #standup"), "got: {}", done.stdout);
+        assert!(!done.stdout.contains("REFLECTION REQUIRED"), "got: {}", done.stdout);
+    });
+}
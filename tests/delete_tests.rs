@@ -68,6 +68,7 @@ fn delete_works_for_done_tasks() {
     with_initialized_repo(|temp| {
         let add_result = run_command(&["add", "Completed task", "-a", "Done"], &temp);
         let task_id = extract_task_id(&add_result.stdout);
+        run_command(&["check", &format!("task-{}", task_id), "--all"], &temp);
         run_command(&["done", &format!("task-{}", task_id)], &temp);
 
         let result = run_command(&["delete", &format!("task-{}", task_id)], &temp);
@@ -171,6 +172,7 @@ fn delete_maintains_file_format() {
         let id1 = extract_task_id(&r1.stdout);
         let id2 = extract_task_id(&r2.stdout);
         let id3 = extract_task_id(&r3.stdout);
+        run_command(&["check", &format!("task-{}", id2), "--all"], &temp);
         run_command(&["done", &format!("task-{}", id2)], &temp);
 
         run_command(&["delete", &format!("task-{}", id2)], &temp);
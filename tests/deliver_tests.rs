@@ -81,6 +81,7 @@ fn deliver_fails_for_already_done_task() {
     with_initialized_repo(|temp| {
         let add_result = run_command(&["add", "Task that is done", "-a", "Done"], temp);
         let task_id = extract_task_id(&add_result.stdout);
+        run_command(&["check", &format!("task-{}", task_id), "--all"], temp);
         run_command(&["done", &format!("task-{}", task_id)], temp);
 
         // Trying to deliver a done task should fail
@@ -103,6 +104,7 @@ fn deliver_success_message_matches_done_format() {
         let id1 = extract_task_id(&r1.stdout);
         let id2 = extract_task_id(&r2.stdout);
 
+        run_command(&["check", &format!("task-{}", id1), "--all"], &temp);
         let done_result = run_command(&["done", &format!("task-{}", id1)], &temp);
         let deliver_result = run_command(&["deliver", &format!("task-{}", id2)], &temp);
 
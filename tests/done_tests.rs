@@ -44,6 +44,7 @@ fn done_marks_task_complete() {
         let add_result = run_command(&["add", "Task to complete", "-a", "Done"], &temp);
         let task_id = extract_task_id(&add_result.stdout);
 
+        run_command(&["check", &format!("task-{}", task_id), "--all"], &temp);
         let result = run_command(&["done", &format!("task-{}", task_id)], &temp);
         assert!(result.success, "done command should succeed");
 
@@ -104,6 +105,7 @@ fn done_reflection_prompt_uses_actionable_language() {
     let add_result = run_command(&["add", "Task to complete", "-a", "Done"], &temp);
     let task_id = extract_task_id(&add_result.stdout);
 
+    run_command(&["check", &format!("task-{}", task_id), "--all"], &temp);
     let result = run_command(&["done", &format!("task-{}", task_id)], &temp);
 
     assert!(result.success, "done command should succeed");
@@ -186,6 +188,7 @@ fn done_marks_task_without_description_complete() {
     let task_id = extract_task_id(&add_result.stdout);
 
     // Mark it done
+    run_command(&["check", &format!("task-{}", task_id), "--all"], &temp);
     let result = run_command(&["done", &format!("task-{}", task_id)], &temp);
     assert!(result.success, "done command should succeed");
 
@@ -206,6 +209,7 @@ fn done_marks_task_with_description_complete() {
     let task_id = extract_task_id(&add_result.stdout);
 
     // Mark it done
+    run_command(&["check", &format!("task-{}", task_id), "--all"], &temp);
     let result = run_command(&["done", &format!("task-{}", task_id)], &temp);
     assert!(result.success, "done command should succeed");
 
@@ -231,6 +235,7 @@ fn done_increments_pain_on_skipped_top_task() {
         let (top_id, other_id) = if id1 < id2 { (&id1, &id2) } else { (&id2, &id1) };
 
         // Complete the non-top task (skipping the top task)
+        run_command(&["check", &format!("task-{}", other_id), "--all"], &temp);
         let done_result = run_command(&["done", &format!("task-{}", other_id)], &temp);
         assert!(done_result.success, "done should succeed");
 
@@ -265,6 +270,7 @@ fn done_on_oldest_task_does_not_increment_pain() {
         let (top_id, other_id) = if id1 < id2 { (&id1, &id2) } else { (&id2, &id1) };
 
         // Complete the top task (not skipping it)
+        run_command(&["check", &format!("task-{}", top_id), "--all"], &temp);
         let done_result = run_command(&["done", &format!("task-{}", top_id)], &temp);
         assert!(done_result.success);
 
@@ -294,6 +300,7 @@ fn done_increments_pain_on_task_with_existing_description() {
         let (top_id, other_id) = if id1 < id2 { (&id1, &id2) } else { (&id2, &id1) };
 
         // Complete the non-top task, skipping the top task
+        run_command(&["check", &format!("task-{}", other_id), "--all"], &temp);
         let done_result = run_command(&["done", &format!("task-{}", other_id)], &temp);
         assert!(done_result.success);
 
@@ -316,6 +323,7 @@ fn done_instructs_agent_to_run_reflect_skill() {
         let add_result = run_command(&["add", "Task to complete", "-a", "Done"], &temp);
         let task_id = extract_task_id(&add_result.stdout);
 
+        run_command(&["check", &format!("task-{}", task_id), "--all"], &temp);
         let result = run_command(&["done", &format!("task-{}", task_id)], &temp);
 
         assert!(result.success, "done should succeed: {}", result.stderr);
@@ -332,6 +340,7 @@ fn done_fails_for_already_done_task() {
         let task_id = extract_task_id(&add_result.stdout);
 
         // First done should succeed
+        run_command(&["check", &format!("task-{}", task_id), "--all"], temp);
         let first = run_command(&["done", &format!("task-{}", task_id)], temp);
         assert!(first.success, "First done should succeed");
 
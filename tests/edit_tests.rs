@@ -0,0 +1,185 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, run_command_with_env, setup_temp_dir, with_initialized_repo};
+use knecht::{format_task_for_editing, parse_edited_task, KnechtError};
+use std::fs;
+use std::path::Path;
+
+/// Writes a shell script that acts as the editor and returns the EDITOR value running it.
+/// The script receives the file to edit as $1.
+fn fake_editor(dir: &Path, script: &str) -> String {
+    let path = dir.join("editor.sh");
+    fs::write(&path, script).unwrap();
+    format!("sh '{}'", path.display())
+}
+
+fn run_with_editor(args: &[&str], dir: &std::path::PathBuf, editor: &str) -> common::TestResult {
+    run_command_with_env(args, dir, &[("VISUAL", ""), ("EDITOR", editor)])
+}
+
+#[test]
+fn test_editing_layout_round_trips() {
+    let content = format_task_for_editing("Title", Some("Line one\n\n# Markdown heading\nLine two"), Some("Done"));
+
+    let edited = parse_edited_task(&content).unwrap();
+
+    assert_eq!(edited.title, "Title");
+    assert_eq!(edited.description.as_deref(), Some("Line one\n\n# Markdown heading\nLine two"));
+    assert_eq!(edited.acceptance_criteria.as_deref(), Some("Done"));
+}
+
+#[test]
+fn test_empty_sections_parse_as_none() {
+    let edited = parse_edited_task(&format_task_for_editing("Title", None, None)).unwrap();
+    assert_eq!(edited.description, None);
+    assert_eq!(edited.acceptance_criteria, None);
+}
+
+#[test]
+fn test_parse_errors_report_lines() {
+    let empty_title = format_task_for_editing("", None, Some("Done"));
+    match parse_edited_task(&empty_title) {
+        Err(KnechtError::InvalidRecord { line, message }) => {
+            assert_eq!(line, 3);
+            assert!(message.contains("title"));
+        }
+        other => panic!("expected InvalidRecord, got {:?}", other),
+    }
+
+    let missing_marker = "--- title ---\nTitle\n--- description ---\n";
+    assert!(matches!(parse_edited_task(missing_marker), Err(KnechtError::InvalidRecord { .. })));
+
+    let stray_text = "stray\n--- title ---\nTitle\n";
+    assert!(matches!(parse_edited_task(stray_text), Err(KnechtError::InvalidRecord { line: 1, .. })));
+}
+
+#[test]
+fn edit_updates_task_from_editor() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Old title", "-d", "Old description", "-a", "Done"], temp).stdout);
+        let editor = fake_editor(temp, "sed -i 's/Old title/New title/; s/Old description/Long\\\nmulti-line description/' \"$1\"\n");
+
+        let result = run_with_editor(&["edit", &id], temp, &editor);
+
+        assert!(result.success, "edit should succeed, got: {}", result.stderr);
+        assert!(result.stdout.contains(&format!("Updated task-{}", id)), "got: {}", result.stdout);
+        let show = run_command(&["show", &id], temp);
+        assert!(show.stdout.contains("New title"), "got: {}", show.stdout);
+        assert!(show.stdout.contains("Long\nmulti-line description"), "got: {}", show.stdout);
+    });
+}
+
+#[test]
+#[cfg(unix)]
+fn edit_uses_a_private_temporary_directory() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Title", "-a", "Done"], temp).stdout);
+        let seen = temp.join("seen");
+        let editor = fake_editor(temp, &format!(
+            "dirname \"$1\" > '{seen}'\nstat -c %a \"$(dirname \"$1\")\" >> '{seen}'\n",
+            seen = seen.display(),
+        ));
+
+        assert!(run_with_editor(&["edit", &id], temp, &editor).success);
+
+        let seen = fs::read_to_string(&seen).unwrap();
+        let mut lines = seen.lines();
+        let dir = Path::new(lines.next().unwrap());
+        assert_eq!(lines.next(), Some("700"), "only the user may enter the directory");
+        assert!(!dir.exists(), "the directory is removed afterwards");
+    });
+}
+
+#[test]
+fn edit_without_changes_reports_nothing_to_do() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Unchanged", "-a", "Done"], temp).stdout);
+
+        let result = run_with_editor(&["edit", &id], temp, "true");
+
+        assert!(result.success);
+        assert!(result.stdout.contains("No changes"), "got: {}", result.stdout);
+    });
+}
+
+#[test]
+fn edit_reopens_editor_after_parse_error() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Title", "-a", "Done"], temp).stdout);
+        // First run removes the title marker; the second run sees the error and restores it
+        let editor = fake_editor(temp, &format!(
+            "if grep -q '^# Error:' \"$1\"; then\n  echo yes > '{}'\n  sed -i 's/^Fixed title$/--- title ---\\\nFixed title/' \"$1\"\nelse\n  sed -i 's/^--- title ---$//; s/^Title$/Fixed title/' \"$1\"\nfi\n",
+            temp.join("saw-error").display()));
+
+        let result = run_with_editor(&["edit", &id], temp, &editor);
+
+        assert!(result.success, "second edit should succeed, got: {}", result.stderr);
+        assert!(temp.join("saw-error").exists(), "the editor should be reopened with the parse error");
+        assert!(run_command(&["show", &id], temp).stdout.contains("Fixed title"));
+    });
+}
+
+#[test]
+fn edit_aborts_when_error_is_not_fixed() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Title", "-a", "Done"], temp).stdout);
+        let editor = fake_editor(temp, "grep -q '^# Error:' \"$1\" || sed -i 's/^Title$//' \"$1\"\n");
+
+        let result = run_with_editor(&["edit", &id], temp, &editor);
+
+        assert_eq!(result.exit_code, Some(13));
+        assert!(result.stderr.contains("[E_EDIT_ABORTED]"), "got: {}", result.stderr);
+        assert!(run_command(&["show", &id], temp).stdout.contains("Title"), "task should be untouched");
+    });
+}
+
+#[test]
+fn edit_aborts_when_editor_fails() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Title", "-a", "Done"], temp).stdout);
+
+        let result = run_with_editor(&["edit", &id], temp, "false");
+
+        assert_eq!(result.exit_code, Some(13));
+    });
+}
+
+#[test]
+fn edit_missing_task_fails() {
+    with_initialized_repo(|temp| {
+        let result = run_with_editor(&["edit", "task-nope"], temp, "true");
+        assert_eq!(result.exit_code, Some(4));
+    });
+}
+
+#[test]
+fn add_with_edit_creates_task_from_editor() {
+    with_initialized_repo(|temp| {
+        let editor = fake_editor(temp, "sed -i 's/^--- title ---$/&\\\nWritten in editor/; s/^--- acceptance criteria ---$/&\\\nCriteria from editor/' \"$1\"\n");
+
+        let result = run_with_editor(&["add", "--edit"], temp, &editor);
+
+        assert!(result.success, "add --edit should succeed, got: {}", result.stderr);
+        let id = extract_task_id(&result.stdout);
+        let show = run_command(&["show", &id], temp);
+        assert!(show.stdout.contains("Written in editor"), "got: {}", show.stdout);
+        assert!(show.stdout.contains("Criteria from editor"), "got: {}", show.stdout);
+    });
+}
+
+#[test]
+fn add_with_edit_prefills_arguments_and_requires_criteria() {
+    with_initialized_repo(|temp| {
+        // Saving the prefilled buffer as-is lacks criteria; saving it again unchanged aborts
+        let result = run_with_editor(&["add", "Prefilled", "title", "--edit"], temp, "true");
+
+        assert_eq!(result.exit_code, Some(13));
+        assert!(!run_command(&["list"], temp).stdout.contains("Prefilled"), "no task should be created");
+
+        let result = run_with_editor(&["add", "Prefilled", "title", "-a", "Done", "--edit"], temp, "true");
+        assert!(result.success, "got: {}", result.stderr);
+        let id = extract_task_id(&result.stdout);
+        assert!(run_command(&["show", &id], temp).stdout.contains("Prefilled title"));
+    });
+}
@@ -0,0 +1,139 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, setup_temp_dir, with_initialized_repo};
+use knecht::KnechtError;
+use std::collections::HashSet;
+use std::io;
+use std::path::PathBuf;
+
+#[test]
+fn not_found_exits_with_code_4_and_prints_code() {
+    with_initialized_repo(|temp| {
+        let result = run_command(&["show", "task-nope"], temp);
+
+        assert_eq!(result.exit_code, Some(4));
+        assert!(result.stderr.contains("[E_NOT_FOUND]"), "stderr should carry the error code, got: {}", result.stderr);
+    });
+}
+
+#[test]
+fn already_done_exits_with_code_5() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Finish me", "-a", "Done"], temp).stdout);
+        run_command(&["check", &id, "--all"], temp);
+        run_command(&["done", &id], temp);
+
+        let result = run_command(&["done", &id], temp);
+
+        assert_eq!(result.exit_code, Some(5));
+        assert!(result.stderr.contains("[E_ALREADY_DONE]"), "got: {}", result.stderr);
+    });
+}
+
+#[test]
+fn already_delivered_exits_with_code_6() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Ship me", "-a", "Done"], temp).stdout);
+        run_command(&["deliver", &id], temp);
+
+        let result = run_command(&["deliver", &id], temp);
+
+        assert_eq!(result.exit_code, Some(6));
+        assert!(result.stderr.contains("[E_ALREADY_DELIVERED]"), "got: {}", result.stderr);
+    });
+}
+
+#[test]
+fn missing_repository_exits_with_code_3() {
+    let temp = setup_temp_dir();
+
+    let result = run_command(&["next"], &temp);
+
+    assert_eq!(result.exit_code, Some(3));
+    assert!(result.stderr.contains("[E_NO_REPO]"), "got: {}", result.stderr);
+
+    cleanup_temp_dir(temp);
+}
+
+#[test]
+fn usage_errors_exit_with_code_2() {
+    with_initialized_repo(|temp| {
+        let no_criteria = run_command(&["add", "No criteria"], temp);
+        assert_eq!(no_criteria.exit_code, Some(2));
+        assert!(no_criteria.stderr.contains("[E_USAGE]"), "got: {}", no_criteria.stderr);
+
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Done"], temp).stdout);
+        let no_flags = run_command(&["update", &id], temp);
+        assert_eq!(no_flags.exit_code, Some(2));
+    });
+}
+
+#[test]
+fn blocked_start_exits_with_code_7() {
+    with_initialized_repo(|temp| {
+        let a = extract_task_id(&run_command(&["add", "A", "-a", "Done"], temp).stdout);
+        let b = extract_task_id(&run_command(&["add", "B", "-a", "Done"], temp).stdout);
+        run_command(&["block", &a, "by", &b], temp);
+
+        let result = run_command(&["start", &a], temp);
+
+        assert_eq!(result.exit_code, Some(7));
+        assert!(result.stderr.contains("[E_BLOCKED]"), "got: {}", result.stderr);
+    });
+}
+
+#[test]
+fn unblocking_missing_relationship_exits_with_code_8() {
+    with_initialized_repo(|temp| {
+        let a = extract_task_id(&run_command(&["add", "A", "-a", "Done"], temp).stdout);
+        let b = extract_task_id(&run_command(&["add", "B", "-a", "Done"], temp).stdout);
+
+        let result = run_command(&["unblock", &a, "from", &b], temp);
+
+        assert_eq!(result.exit_code, Some(8));
+        assert!(result.stderr.contains("[E_NOT_BLOCKED]"), "got: {}", result.stderr);
+    });
+}
+
+#[test]
+fn json_errors_use_the_same_exit_codes() {
+    with_initialized_repo(|temp| {
+        let result = run_command(&["show", "task-nope", "--json"], temp);
+
+        assert_eq!(result.exit_code, Some(4));
+        assert!(result.stdout.contains("\"code\":\"E_NOT_FOUND\""), "got: {}", result.stdout);
+    });
+}
+
+#[test]
+fn test_error_codes_and_exit_codes_are_distinct() {
+    let errors = [
+        KnechtError::IoError(io::Error::other("x")),
+        KnechtError::CsvError(csv::Error::from(io::Error::other("x"))),
+        KnechtError::TaskNotFound("a".to_string()),
+        KnechtError::TaskAlreadyDelivered("a".to_string()),
+        KnechtError::TaskAlreadyDone("a".to_string()),
+        KnechtError::RepoNotFound(PathBuf::from("/")),
+        KnechtError::InvalidRecord { line: 1, message: "x".to_string() },
+        KnechtError::InvalidConfig { key: "x".to_string(), message: "x".to_string() },
+        KnechtError::TemplateError { name: "x".to_string(), message: "x".to_string() },
+        KnechtError::UncheckedCriteria { task_id: "a".to_string(), unchecked: vec![(1, "x".to_string())] },
+        KnechtError::CriteriaItemNotFound { task_id: "a".to_string(), item: 2, count: 1 },
+        KnechtError::TaskNotDelivered("a".to_string()),
+        KnechtError::SelfVerification { task_id: "a".to_string(), actor: "me".to_string() },
+        KnechtError::InvalidTransition { task_id: "a".to_string(), from: "open".to_string(), to: "done".to_string(), allowed: vec![] },
+        KnechtError::UnknownStatus { status: "x".to_string(), known: vec![] },
+        KnechtError::WipLimitExceeded { actor: None, limit: 1, held: vec![] },
+        KnechtError::Locked { holder: "1 2".to_string() },
+        KnechtError::PainEntryNotFound { task_id: "a".to_string(), entry: 2, count: 1 },
+    ];
+
+    let codes: HashSet<&str> = errors.iter().map(|e| e.code()).collect();
+    let exit_codes: HashSet<i32> = errors.iter().map(|e| e.exit_code()).collect();
+
+    assert_eq!(codes.len(), errors.len(), "every variant needs its own code");
+    assert_eq!(exit_codes.len(), errors.len(), "every variant needs its own exit code");
+    assert!(exit_codes.iter().all(|c| *c > 2), "0, 1 and 2 are reserved");
+    assert!(codes.iter().all(|c| c.starts_with("E_")));
+}
@@ -0,0 +1,318 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, setup_temp_dir, with_initialized_repo};
+use knecht::{export_records_with_fs, import_records_with_fs, read_blockers_with_fs, read_jsonl, read_pain_entries_with_fs, read_sentry_mappings_with_fs, read_sessions_with_fs, read_tasks_with_fs, write_jsonl, ExportRecord, ImportMode, KnechtError, MemoryFileSystem};
+use std::fs;
+
+fn populated_fs() -> MemoryFileSystem {
+    MemoryFileSystem::new()
+        .with_file(".knecht/tasks/bbb", "bbb,open,Blocker,,,Done\n")
+        .with_file(".knecht/tasks/aaa", "aaa,open,Blocked,\"Line one\nLine two\",,Done\n")
+        .with_file(".knecht/blockers", "task-aaa|task-bbb\n")
+        .with_file(".knecht/pain", "aaa|100|manual||Hurts\naaa|200|skip|bbb|Skip: task-bbb completed instead\n")
+        .with_file(".knecht/sentry-mapping", "42|bbb|300|7\n")
+        .with_file(".knecht/sessions", "aaa|400|start|alice\naaa|460|stop|alice\nbbb|500|start|bob\n")
+}
+
+#[test]
+fn test_export_records_cover_every_entity() {
+    let records = export_records_with_fs(&populated_fs()).unwrap();
+
+    assert!(matches!(records[0], ExportRecord::Header { version: 2 }));
+    let task_ids: Vec<&str> = records.iter().filter_map(|r| match r {
+        ExportRecord::Task(t) => Some(t.id.as_str()),
+        _ => None,
+    }).collect();
+    assert_eq!(task_ids, vec!["aaa", "bbb"], "tasks should be sorted by ID");
+    assert_eq!(records.iter().filter(|r| matches!(r, ExportRecord::Blocker { .. })).count(), 1);
+    assert_eq!(records.iter().filter(|r| matches!(r, ExportRecord::Pain(_))).count(), 2);
+    assert_eq!(records.iter().filter(|r| matches!(r, ExportRecord::SentryMapping(_))).count(), 1);
+}
+
+#[test]
+fn test_jsonl_round_trip_uses_type_discriminator() {
+    let records = export_records_with_fs(&populated_fs()).unwrap();
+    let mut buffer = Vec::new();
+    write_jsonl(&records, &mut buffer).unwrap();
+    let text = String::from_utf8(buffer.clone()).unwrap();
+
+    assert_eq!(text.lines().count(), records.len(), "one record per line");
+    assert!(text.lines().next().unwrap().contains("\"type\":\"header\""));
+    assert!(text.contains("\"type\":\"sentry_mapping\""));
+
+    let parsed = read_jsonl(&buffer[..]).unwrap();
+    assert_eq!(parsed.len(), records.len());
+}
+
+#[test]
+fn test_read_jsonl_reports_bad_line() {
+    let input = "{\"type\":\"header\",\"version\":1}\n{\"type\":\"mystery\"}\n";
+    match read_jsonl(input.as_bytes()) {
+        Err(KnechtError::InvalidRecord { line, .. }) => assert_eq!(line, 2),
+        other => panic!("expected InvalidRecord, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_read_jsonl_rejects_newer_versions() {
+    let input = "{\"type\":\"header\",\"version\":99}\n";
+    assert!(matches!(read_jsonl(input.as_bytes()), Err(KnechtError::InvalidRecord { .. })));
+}
+
+#[test]
+fn test_import_into_empty_repository_restores_everything() {
+    let records = export_records_with_fs(&populated_fs()).unwrap();
+    let target = MemoryFileSystem::new().with_dir(".knecht/tasks");
+
+    let summary = import_records_with_fs(&records, ImportMode::Merge, false, &target).unwrap();
+
+    assert_eq!((summary.tasks, summary.blockers, summary.pain_entries, summary.sentry_mappings), (2, 1, 2, 1));
+    let tasks = read_tasks_with_fs(&target).unwrap();
+    let blocked = tasks.iter().find(|t| t.id == "aaa").unwrap();
+    assert_eq!(blocked.description.as_deref(), Some("Line one\nLine two"));
+    assert_eq!(read_blockers_with_fs(&target).unwrap(), vec![("aaa".to_string(), "bbb".to_string())]);
+}
+
+#[test]
+fn test_merge_import_twice_does_not_duplicate_logs() {
+    let records = export_records_with_fs(&populated_fs()).unwrap();
+    let target = MemoryFileSystem::new().with_dir(".knecht/tasks");
+
+    import_records_with_fs(&records, ImportMode::Merge, false, &target).unwrap();
+    let second = import_records_with_fs(&records, ImportMode::Merge, false, &target).unwrap();
+
+    assert_eq!((second.blockers, second.pain_entries, second.sentry_mappings), (0, 0, 0));
+    assert_eq!(read_pain_entries_with_fs(&target).unwrap().len(), 2);
+    assert_eq!(read_blockers_with_fs(&target).unwrap().len(), 1);
+}
+
+#[test]
+fn test_merge_keeps_existing_tasks() {
+    let records = export_records_with_fs(&populated_fs()).unwrap();
+    let target = MemoryFileSystem::new().with_file(".knecht/tasks/zzz", "zzz,open,Local,,,Done\n");
+
+    import_records_with_fs(&records, ImportMode::Merge, false, &target).unwrap();
+
+    assert_eq!(read_tasks_with_fs(&target).unwrap().len(), 3);
+}
+
+#[test]
+fn test_replace_discards_existing_data() {
+    let records = export_records_with_fs(&populated_fs()).unwrap();
+    let target = MemoryFileSystem::new()
+        .with_file(".knecht/tasks/zzz", "zzz,open,Local,,,Done\n")
+        .with_file(".knecht/pain", "zzz|1|manual||Local pain\n")
+        .with_file(".knecht/blockers", "task-zzz|task-aaa\n");
+
+    import_records_with_fs(&records, ImportMode::Replace, false, &target).unwrap();
+
+    let ids: Vec<String> = read_tasks_with_fs(&target).unwrap().into_iter().map(|t| t.id).collect();
+    assert!(!ids.contains(&"zzz".to_string()));
+    assert!(read_pain_entries_with_fs(&target).unwrap().iter().all(|e| e.task_id != "zzz"));
+    assert_eq!(read_blockers_with_fs(&target).unwrap(), vec![("aaa".to_string(), "bbb".to_string())]);
+}
+
+#[test]
+fn test_sessions_round_trip_and_are_replaced() {
+    let records = export_records_with_fs(&populated_fs()).unwrap();
+    assert_eq!(records.iter().filter(|r| matches!(r, ExportRecord::Session(_))).count(), 2);
+
+    let target = MemoryFileSystem::new()
+        .with_file(".knecht/tasks/zzz", "zzz,open,Local,,,Done\n")
+        .with_file(".knecht/sessions", "zzz|1|start|carol\n");
+    let summary = import_records_with_fs(&records, ImportMode::Replace, false, &target).unwrap();
+
+    assert_eq!(summary.sessions, 2);
+    assert_eq!(read_sessions_with_fs(&target).unwrap(), read_sessions_with_fs(&populated_fs()).unwrap());
+    assert_eq!(import_records_with_fs(&records, ImportMode::Merge, false, &target).unwrap().sessions, 0, "merging again adds nothing");
+}
+
+#[test]
+fn test_remap_ids_rewrites_references() {
+    let records = export_records_with_fs(&populated_fs()).unwrap();
+    let target = MemoryFileSystem::new().with_file(".knecht/tasks/aaa", "aaa,open,Unrelated local task,,,Done\n");
+
+    let summary = import_records_with_fs(&records, ImportMode::Merge, true, &target).unwrap();
+
+    let new_aaa = &summary.remapped_ids["aaa"];
+    let new_bbb = &summary.remapped_ids["bbb"];
+    assert_ne!(new_aaa, "aaa");
+    let tasks = read_tasks_with_fs(&target).unwrap();
+    assert_eq!(tasks.len(), 3, "the local task must not be overwritten");
+    assert_eq!(tasks.iter().find(|t| t.id == "aaa").unwrap().title, "Unrelated local task");
+    assert_eq!(read_blockers_with_fs(&target).unwrap(), vec![(new_aaa.clone(), new_bbb.clone())]);
+    let pain = read_pain_entries_with_fs(&target).unwrap();
+    assert!(pain.iter().all(|e| &e.task_id == new_aaa));
+    assert_eq!(pain[1].source_id.as_ref(), Some(new_bbb), "skip sources reference tasks too");
+    assert_eq!(&read_sentry_mappings_with_fs(&target).unwrap()[0].knecht_task_id, new_bbb);
+}
+
+#[test]
+fn test_remap_ids_use_configured_id_length() {
+    let records = export_records_with_fs(&populated_fs()).unwrap();
+    let target = MemoryFileSystem::new().with_file(".knecht/config", "id_length = 9\n");
+
+    let summary = import_records_with_fs(&records, ImportMode::Merge, true, &target).unwrap();
+
+    assert!(summary.remapped_ids.values().all(|id| id.len() == 9), "got: {:?}", summary.remapped_ids);
+}
+
+#[test]
+fn test_import_rejects_task_ids_that_leave_the_tasks_directory() {
+    for id in ["../../pwned", "/tmp/pwned", "sub/dir", "back\\slash", ".hidden", "..", ""] {
+        let target = MemoryFileSystem::new().with_file(".knecht/tasks/zzz", "zzz,open,Local,,,Done\n");
+        let task = read_tasks_with_fs(&populated_fs()).unwrap().remove(0);
+        let records = vec![
+            ExportRecord::Header { version: 1 },
+            ExportRecord::Task(knecht::Task { id: id.to_string(), ..task }),
+        ];
+
+        match import_records_with_fs(&records, ImportMode::Replace, false, &target) {
+            Err(KnechtError::InvalidRecord { line, .. }) => assert_eq!(line, 2, "id {:?}", id),
+            other => panic!("expected InvalidRecord for {:?}, got {:?}", id, other.map(|_| ())),
+        }
+        let ids: Vec<String> = read_tasks_with_fs(&target).unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec!["zzz"], "nothing is written or removed for {:?}", id);
+    }
+}
+
+#[test]
+fn test_import_rejects_log_records_that_would_corrupt_the_logs() {
+    let exported = export_records_with_fs(&populated_fs()).unwrap();
+    let pain = exported.iter().find_map(|r| match r {
+        ExportRecord::Pain(entry) => Some(entry.clone()),
+        _ => None,
+    }).unwrap();
+    let history = knecht::HistoryEntry { task_id: "aaa".to_string(), timestamp: 1, event: "created".to_string(), actor: "alice".to_string(), detail: None };
+
+    let bad = vec![
+        ExportRecord::Blocker { task_id: "aaa".to_string(), blocker_id: "../escape".to_string() },
+        ExportRecord::Blocker { task_id: "aaa".to_string(), blocker_id: "gone".to_string() },
+        ExportRecord::Pain(knecht::PainEntry { task_id: "gone".to_string(), ..pain.clone() }),
+        ExportRecord::Pain(knecht::PainEntry { description: "Two\nlines".to_string(), ..pain.clone() }),
+        ExportRecord::Pain(knecht::PainEntry { during_task: Some("a;count=9".to_string()), ..pain }),
+        ExportRecord::History(knecht::HistoryEntry { task_id: "gone".to_string(), ..history.clone() }),
+        ExportRecord::History(knecht::HistoryEntry { actor: "mallory|done".to_string(), ..history.clone() }),
+        ExportRecord::History(knecht::HistoryEntry { detail: Some("x\nbbb|1|done|mallory|".to_string()), ..history }),
+    ];
+    for record in bad {
+        let target = MemoryFileSystem::new().with_file(".knecht/tasks/zzz", "zzz,open,Local,,,Done\n");
+        let mut records = exported.clone();
+        records.push(record.clone());
+
+        match import_records_with_fs(&records, ImportMode::Replace, false, &target) {
+            Err(KnechtError::InvalidRecord { line, .. }) => assert_eq!(line, records.len(), "{:?}", record),
+            other => panic!("expected InvalidRecord for {:?}, got {:?}", record, other.map(|_| ())),
+        }
+        let ids: Vec<String> = read_tasks_with_fs(&target).unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec!["zzz"], "nothing is written or removed for {:?}", record);
+    }
+
+    // Merging may refer to tasks already in the repository
+    let target = MemoryFileSystem::new().with_file(".knecht/tasks/zzz", "zzz,open,Local,,,Done\n");
+    let mut records = exported.clone();
+    records.push(ExportRecord::Blocker { task_id: "zzz".to_string(), blocker_id: "aaa".to_string() });
+    assert!(import_records_with_fs(&records, ImportMode::Merge, false, &target).is_ok());
+}
+
+#[test]
+fn test_export_leaves_out_records_of_deleted_tasks() {
+    let fs = populated_fs()
+        .with_file(".knecht/blockers", "task-aaa|task-bbb\ntask-aaa|task-gone\n")
+        .with_file(".knecht/history", "gone|1|created|alice|\naaa|2|created|alice|\n");
+    let records = export_records_with_fs(&fs).unwrap();
+
+    assert_eq!(records.iter().filter(|r| matches!(r, ExportRecord::Blocker { .. })).count(), 1);
+    assert_eq!(records.iter().filter(|r| matches!(r, ExportRecord::History(_))).count(), 1);
+    assert!(import_records_with_fs(&records, ImportMode::Replace, false, &MemoryFileSystem::new()).is_ok());
+}
+
+#[test]
+fn import_does_not_write_outside_the_repository() {
+    with_initialized_repo(|temp| {
+        let export = temp.join("evil.jsonl");
+        fs::write(&export, concat!(
+            "{\"type\":\"header\",\"version\":1}\n",
+            "{\"type\":\"task\",\"id\":\"../../pwned\",\"status\":\"open\",\"title\":\"Evil\",\"description\":null,\"pain_count\":null,\"acceptance_criteria\":\"Done\"}\n",
+        )).unwrap();
+
+        let result = run_command(&["import", "evil.jsonl"], temp);
+
+        assert_eq!(result.exit_code, Some(11), "stderr: {}", result.stderr);
+        assert!(result.stderr.contains("invalid task id '../../pwned'"), "got: {}", result.stderr);
+        assert!(!temp.join("pwned").exists());
+        assert!(!temp.parent().unwrap().join("pwned").exists());
+    });
+}
+
+#[test]
+fn export_and_import_round_trip_between_repositories() {
+    with_initialized_repo(|source| {
+        let a = extract_task_id(&run_command(&["add", "First", "-d", "Multi\nline", "-a", "Done"], source).stdout);
+        let b = extract_task_id(&run_command(&["add", "Second", "-a", "Done"], source).stdout);
+        run_command(&["block", &a, "by", &b], source);
+        run_command(&["pain", "-t", &a, "-d", "Ouch"], source);
+
+        let export = run_command(&["export", "--format", "jsonl", "-o", "backup.jsonl"], source);
+        assert!(export.success, "export should succeed, got: {}", export.stderr);
+        assert!(source.join("backup.jsonl").exists());
+
+        let target = setup_temp_dir();
+        run_command(&["init"], &target);
+        let backup = source.join("backup.jsonl");
+        let import = run_command(&["import", backup.to_str().unwrap()], &target);
+        assert!(import.success, "import should succeed, got: {}", import.stderr);
+        assert!(import.stdout.contains("Imported 2 tasks, 1 blockers, 1 pain entries"), "got: {}", import.stdout);
+
+        let show = run_command(&["show", &a], &target);
+        assert!(show.stdout.contains("Multi\nline"), "got: {}", show.stdout);
+        assert!(show.stdout.contains(&format!("task-{}", b)), "blocker should survive, got: {}", show.stdout);
+        assert!(show.stdout.contains("Ouch"), "pain should survive, got: {}", show.stdout);
+
+        cleanup_temp_dir(target);
+    });
+}
+
+#[test]
+fn export_writes_to_stdout_by_default() {
+    with_initialized_repo(|temp| {
+        run_command(&["add", "Exported", "-a", "Done"], temp);
+
+        let result = run_command(&["export"], temp);
+
+        assert!(result.success);
+        assert!(result.stdout.lines().all(|l| serde_json::from_str::<serde_json::Value>(l).is_ok()),
+            "every line should be JSON, got: {}", result.stdout);
+        assert!(result.stdout.contains("\"title\":\"Exported\""));
+    });
+}
+
+#[test]
+fn import_path_is_relative_to_invocation_directory() {
+    with_initialized_repo(|temp| {
+        run_command(&["add", "From subdir", "-a", "Done"], temp);
+        let export = run_command(&["export"], temp);
+        let subdir = temp.join("sub");
+        fs::create_dir_all(&subdir).unwrap();
+        fs::write(subdir.join("dump.jsonl"), export.stdout).unwrap();
+
+        let result = run_command(&["import", "dump.jsonl", "--remap-ids", "--json"], &subdir);
+
+        assert!(result.success, "import should find file relative to subdir, got: {}", result.stdout);
+        let value: serde_json::Value = serde_json::from_str(result.stdout.trim()).unwrap();
+        assert_eq!(value["imported"]["tasks"], 1);
+    });
+}
+
+#[test]
+fn import_rejects_invalid_file() {
+    with_initialized_repo(|temp| {
+        fs::write(temp.join("bad.jsonl"), "not json\n").unwrap();
+
+        let result = run_command(&["import", "bad.jsonl"], temp);
+
+        assert_eq!(result.exit_code, Some(11));
+        assert!(result.stderr.contains("line 1"), "got: {}", result.stderr);
+    });
+}
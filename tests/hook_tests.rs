@@ -0,0 +1,123 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, setup_temp_dir, with_initialized_repo};
+use std::fs;
+use std::path::Path;
+
+fn write_hook(temp: &Path, name: &str, script: &str) {
+    let dir = temp.join(".knecht/hooks");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
+
+#[test]
+fn post_add_hook_receives_task_json() {
+    with_initialized_repo(|temp| {
+        write_hook(temp, "post-add", "cat > .knecht/hook-input");
+
+        let result = run_command(&["add", "Hooked task", "-a", "Works"], temp);
+
+        assert!(result.success, "add should succeed, got: {}", result.stderr);
+        let input: serde_json::Value = serde_json::from_str(&fs::read_to_string(temp.join(".knecht/hook-input")).unwrap()).unwrap();
+        assert_eq!(input["event"], "post-add");
+        assert_eq!(input["task"]["id"], extract_task_id(&result.stdout));
+        assert_eq!(input["task"]["title"], "Hooked task");
+    });
+}
+
+#[test]
+fn failing_pre_add_hook_aborts_with_its_stderr() {
+    with_initialized_repo(|temp| {
+        write_hook(temp, "pre-add", "echo 'titles must name a ticket' >&2; exit 1");
+
+        let result = run_command(&["add", "Vague task", "-a", "Works"], temp);
+
+        assert_eq!(result.exit_code, Some(19));
+        assert!(result.stderr.contains("[E_HOOK_FAILED]"), "got: {}", result.stderr);
+        assert!(result.stderr.contains("titles must name a ticket"), "got: {}", result.stderr);
+        let tasks = fs::read_dir(temp.join(".knecht/tasks")).unwrap().count();
+        assert_eq!(tasks, 0, "no task should be created");
+    });
+}
+
+#[test]
+fn failing_pre_done_hook_leaves_task_open() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Works"], temp).stdout);
+        run_command(&["check", &id, "--all"], temp);
+        write_hook(temp, "pre-done", "exit 3");
+
+        let result = run_command(&["done", &id, "--json"], temp);
+
+        assert_eq!(result.exit_code, Some(19));
+        let value: serde_json::Value = serde_json::from_str(result.stdout.trim()).unwrap();
+        assert_eq!(value["error"]["code"], "E_HOOK_FAILED");
+        let show = run_command(&["show", &id, "--json"], temp);
+        assert!(show.stdout.contains("\"status\":\"open\""), "got: {}", show.stdout);
+    });
+}
+
+#[test]
+fn post_done_hook_replaces_reflection_banner() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Works"], temp).stdout);
+        run_command(&["check", &id, "--all"], temp);
+        write_hook(temp, "post-done", "echo 'Post to #standup'");
+
+        let result = run_command(&["done", &id], temp);
+
+        assert!(result.success, "got: {}", result.stderr);
+        assert!(result.stdout.contains("Post to #standup"), "got: {}", result.stdout);
+        assert!(!result.stdout.contains("REFLECTION REQUIRED"), "got: {}", result.stdout);
+    });
+}
+
+#[test]
+fn failing_post_hook_only_warns() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Works"], temp).stdout);
+        write_hook(temp, "post-start", "echo 'channel unreachable' >&2; exit 1");
+
+        let result = run_command(&["start", &id], temp);
+
+        assert!(result.success, "start should still succeed, got: {}", result.stderr);
+        assert!(result.stderr.contains("Warning: post-start hook failed"), "got: {}", result.stderr);
+        assert!(result.stderr.contains("channel unreachable"), "got: {}", result.stderr);
+    });
+}
+
+#[test]
+fn hook_output_keeps_json_stdout_parseable() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Works"], temp).stdout);
+        write_hook(temp, "post-pain", "echo 'pain noted'");
+
+        let result = run_command(&["pain", "-t", &id, "-d", "Slow build", "--json"], temp);
+
+        assert!(result.success, "got: {}", result.stderr);
+        let value: serde_json::Value = serde_json::from_str(result.stdout.trim()).unwrap();
+        assert_eq!(value["task"]["pain_count"], 1);
+        assert!(result.stderr.contains("pain noted"), "got: {}", result.stderr);
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn non_executable_hook_is_ignored() {
+    with_initialized_repo(|temp| {
+        fs::create_dir_all(temp.join(".knecht/hooks")).unwrap();
+        fs::write(temp.join(".knecht/hooks/pre-add"), "#!/bin/sh\nexit 1\n").unwrap();
+
+        let result = run_command(&["add", "Task", "-a", "Works"], temp);
+
+        assert!(result.success, "got: {}", result.stderr);
+        assert!(result.stderr.contains("not executable"), "got: {}", result.stderr);
+    });
+}
@@ -0,0 +1,223 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, setup_temp_dir, with_initialized_repo};
+use knecht::{PainEntry, PainSourceType, Task};
+use serde_json::Value;
+
+fn parse(stdout: &str) -> Value {
+    serde_json::from_str(stdout.trim()).unwrap_or_else(|e| panic!("stdout should be one JSON object ({}), got: {}", e, stdout))
+}
+
+fn add_task(temp: &std::path::PathBuf, title: &str) -> String {
+    let result = run_command(&["add", title, "-a", "Done", "--json"], temp);
+    assert!(result.success, "add --json should succeed, got: {}", result.stdout);
+    parse(&result.stdout)["task"]["id"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn add_json_returns_created_task() {
+    with_initialized_repo(|temp| {
+        let result = run_command(&["--json", "add", "JSON task", "-d", "Details", "-a", "Criteria"], temp);
+
+        assert!(result.success, "add --json should succeed, got: {}", result.stderr);
+        let value = parse(&result.stdout);
+        assert_eq!(value["task"]["title"], "JSON task");
+        assert_eq!(value["task"]["status"], "open");
+        assert_eq!(value["task"]["description"], "Details");
+        assert_eq!(value["task"]["acceptance_criteria"], "Criteria");
+        assert_eq!(value["task"]["id"].as_str().unwrap().len(), 6);
+    });
+}
+
+#[test]
+fn list_json_includes_pain_counts() {
+    with_initialized_repo(|temp| {
+        let id = add_task(temp, "Painful");
+        run_command(&["pain", "-t", &id, "-d", "Ouch"], temp);
+
+        let result = run_command(&["list", "--json"], temp);
+
+        assert!(result.success);
+        let value = parse(&result.stdout);
+        let tasks = value["tasks"].as_array().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0]["id"], id.as_str());
+        assert_eq!(tasks[0]["pain_count"], 1);
+        assert!(!result.stdout.contains("Usage instructions"), "JSON output should not include usage text");
+    });
+}
+
+#[test]
+fn show_json_includes_blockers_and_pain() {
+    with_initialized_repo(|temp| {
+        let blocked = add_task(temp, "Blocked");
+        let blocker = add_task(temp, "Blocker");
+        run_command(&["block", &blocked, "by", &blocker], temp);
+        run_command(&["pain", "-t", &blocked, "-d", "Waiting hurts"], temp);
+
+        let result = run_command(&["show", &blocked, "--json"], temp);
+
+        assert!(result.success);
+        let value = parse(&result.stdout);
+        assert_eq!(value["task"]["id"], blocked.as_str());
+        assert_eq!(value["blocked_by"][0]["id"], blocker.as_str());
+        assert_eq!(value["blocks"].as_array().unwrap().len(), 0);
+        assert_eq!(value["pain"][0]["description"], "Waiting hurts");
+        assert_eq!(value["pain"][0]["source_type"], "manual");
+    });
+}
+
+#[test]
+fn next_json_returns_task_or_null() {
+    with_initialized_repo(|temp| {
+        let empty = run_command(&["next", "--json"], temp);
+        assert!(empty.success);
+        assert!(parse(&empty.stdout)["task"].is_null());
+
+        let id = add_task(temp, "Only task");
+        let result = run_command(&["next", "--json"], temp);
+        assert_eq!(parse(&result.stdout)["task"]["id"], id.as_str());
+        assert_eq!(parse(&result.stdout)["task"]["pain_count"], 0);
+    });
+}
+
+#[test]
+fn status_commands_json_return_updated_task() {
+    with_initialized_repo(|temp| {
+        let id = add_task(temp, "Lifecycle");
+
+        let start = parse(&run_command(&["start", &id, "--json"], temp).stdout);
+        assert_eq!(start["task"]["status"], "claimed");
+
+        let deliver = parse(&run_command(&["deliver", &id, "--json"], temp).stdout);
+        assert_eq!(deliver["task"]["status"], "delivered");
+
+        run_command(&["check", &id, "--all"], temp);
+        let done = run_command(&["done", &id, "--json"], temp);
+        assert_eq!(parse(&done.stdout)["task"]["status"], "done");
+        assert!(!done.stdout.contains("REFLECTION"), "JSON output should not include the banner");
+    });
+}
+
+#[test]
+fn update_delete_and_pain_json() {
+    with_initialized_repo(|temp| {
+        let id = add_task(temp, "Old title");
+
+        let update = parse(&run_command(&["update", &id, "-t", "New title", "--json"], temp).stdout);
+        assert_eq!(update["task"]["title"], "New title");
+
+        let pain = parse(&run_command(&["pain", "-t", &id, "-d", "Hurts", "--json"], temp).stdout);
+        assert_eq!(pain["task"]["pain_count"], 1);
+
+        let delete = parse(&run_command(&["delete", &id, "--json"], temp).stdout);
+        assert_eq!(delete["task"]["id"], id.as_str());
+    });
+}
+
+#[test]
+fn block_and_unblock_json() {
+    with_initialized_repo(|temp| {
+        let a = add_task(temp, "A");
+        let b = add_task(temp, "B");
+
+        let block = parse(&run_command(&["block", &a, "by", &b, "--json"], temp).stdout);
+        assert_eq!(block["blocked"], a.as_str());
+        assert_eq!(block["blocker"], b.as_str());
+
+        let unblock = parse(&run_command(&["unblock", &a, "from", &b, "--json"], temp).stdout);
+        assert_eq!(unblock["unblocked"], a.as_str());
+    });
+}
+
+#[test]
+fn errors_are_json_with_codes() {
+    with_initialized_repo(|temp| {
+        let result = run_command(&["show", "task-missing", "--json"], temp);
+
+        assert!(!result.success, "show of a missing task should fail");
+        let value = parse(&result.stdout);
+        assert_eq!(value["error"]["code"], "E_NOT_FOUND");
+        assert_eq!(value["error"]["message"], "task-missing not found");
+
+        let id = add_task(temp, "Done twice");
+        run_command(&["check", &id, "--all"], temp);
+        run_command(&["done", &id], temp);
+        let again = parse(&run_command(&["done", &id, "--json"], temp).stdout);
+        assert_eq!(again["error"]["code"], "E_ALREADY_DONE");
+    });
+}
+
+#[test]
+fn usage_and_blocked_errors_are_json() {
+    with_initialized_repo(|temp| {
+        let missing_criteria = parse(&run_command(&["add", "No criteria", "--json"], temp).stdout);
+        assert_eq!(missing_criteria["error"]["code"], "E_USAGE");
+
+        let a = add_task(temp, "A");
+        let b = add_task(temp, "B");
+        run_command(&["block", &a, "by", &b], temp);
+        let blocked = parse(&run_command(&["start", &a, "--json"], temp).stdout);
+        assert_eq!(blocked["error"]["code"], "E_BLOCKED");
+        assert_eq!(blocked["error"]["blocked_by"][0]["id"], b.as_str());
+    });
+}
+
+#[test]
+fn missing_repository_error_is_json() {
+    let temp = setup_temp_dir();
+
+    let result = run_command(&["list", "--json"], &temp);
+
+    assert!(!result.success);
+    assert_eq!(parse(&result.stdout)["error"]["code"], "E_NO_REPO");
+
+    cleanup_temp_dir(temp);
+}
+
+#[test]
+fn init_json() {
+    let temp = setup_temp_dir();
+
+    let result = run_command(&["init", "--json"], &temp);
+
+    assert!(result.success);
+    assert!(parse(&result.stdout)["initialized"].as_str().unwrap().ends_with(".knecht"));
+
+    cleanup_temp_dir(temp);
+}
+
+#[test]
+fn test_task_serde_round_trip() {
+    let task = Task {
+        id: "abc123".to_string(),
+        status: "open".to_string(),
+        title: "Title".to_string(),
+        description: Some("Multi\nline".to_string()),
+        pain_count: None,
+        acceptance_criteria: Some("Done".to_string()),
+    };
+    let json = serde_json::to_string(&task).unwrap();
+    let back: Task = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.id, task.id);
+    assert_eq!(back.description, task.description);
+    assert_eq!(back.acceptance_criteria, task.acceptance_criteria);
+}
+
+#[test]
+fn test_pain_entry_serializes_source_type_lowercase() {
+    let entry = PainEntry {
+        task_id: "abc123".to_string(),
+        timestamp: 1,
+        source_type: PainSourceType::Sentry,
+        source_id: Some("PROJ-1".to_string()),
+        description: "Sentry event".to_string(),
+        during_task: None,
+        count: 1,
+    };
+    let value = serde_json::to_value(&entry).unwrap();
+    assert_eq!(value["source_type"], "sentry");
+    let back: PainEntry = serde_json::from_value(value).unwrap();
+    assert!(matches!(back.source_type, PainSourceType::Sentry));
+}
@@ -0,0 +1,162 @@
+use knecht::{add_task_with_fs, find_task_by_id_with_fs, increment_pain_count_with_fs, mark_task_done_with_fs, read_pain_entries_with_fs, read_tasks_with_fs, write_tasks_with_fs, FileSystem, FsOperation, MemoryFileSystem, Task};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+fn task(id: &str) -> Task {
+    Task { id: id.to_string(), status: "open".to_string(), title: format!("Task {}", id), description: None, pain_count: None, acceptance_criteria: None }
+}
+
+#[test]
+fn test_memory_fs_create_truncates_existing_file() {
+    let fs = MemoryFileSystem::new().with_file("notes", "old content");
+    fs.create(Path::new("notes")).unwrap().write_all(b"new").unwrap();
+    assert_eq!(fs.read_to_string("notes").unwrap(), "new");
+}
+
+#[test]
+fn test_memory_fs_append_extends_existing_file() {
+    let fs = MemoryFileSystem::new().with_file(".knecht/pain", "first\n");
+    writeln!(fs.append(Path::new(".knecht/pain")).unwrap(), "second").unwrap();
+    writeln!(fs.append(Path::new(".knecht/pain")).unwrap(), "third").unwrap();
+    assert_eq!(fs.read_to_string(".knecht/pain").unwrap(), "first\nsecond\nthird\n");
+}
+
+#[test]
+fn test_memory_fs_append_creates_missing_file() {
+    let fs = MemoryFileSystem::new().with_dir(".knecht");
+    writeln!(fs.append(Path::new(".knecht/pain")).unwrap(), "entry").unwrap();
+    assert!(fs.is_file(Path::new(".knecht/pain")));
+}
+
+#[test]
+fn test_memory_fs_writes_into_missing_directory_fail() {
+    let fs = MemoryFileSystem::new();
+    assert!(fs.create(Path::new(".knecht/tasks/abc")).is_err());
+    assert!(fs.append(Path::new(".knecht/pain")).is_err());
+}
+
+#[test]
+fn test_memory_fs_read_dir_lists_direct_children_sorted() {
+    let fs = MemoryFileSystem::new()
+        .with_file(".knecht/tasks/b", "")
+        .with_file(".knecht/tasks/a", "")
+        .with_file(".knecht/tasks/nested/c", "")
+        .with_file(".knecht/pain", "");
+    let entries = fs.read_dir(Path::new(".knecht/tasks")).unwrap();
+    assert_eq!(entries, vec![
+        PathBuf::from(".knecht/tasks/a"),
+        PathBuf::from(".knecht/tasks/b"),
+        PathBuf::from(".knecht/tasks/nested"),
+    ]);
+}
+
+#[test]
+fn test_memory_fs_read_dir_on_file_or_missing_fails() {
+    let fs = MemoryFileSystem::new().with_file(".knecht/tasks", "");
+    assert!(fs.read_dir(Path::new(".knecht/tasks")).is_err());
+    assert!(fs.read_dir(Path::new("missing")).is_err());
+}
+
+#[test]
+fn test_memory_fs_remove_file() {
+    let fs = MemoryFileSystem::new().with_file(".knecht/tasks/a", "content");
+    fs.remove_file(Path::new(".knecht/tasks/a")).unwrap();
+    assert!(!fs.exists(Path::new(".knecht/tasks/a")));
+    assert!(fs.remove_file(Path::new(".knecht/tasks/a")).is_err(), "removing twice should fail");
+    assert!(fs.remove_file(Path::new(".knecht/tasks")).is_err(), "removing a directory should fail");
+}
+
+#[test]
+fn test_memory_fs_create_dir_all_over_file_fails() {
+    let fs = MemoryFileSystem::new().with_file(".knecht/tasks", "old format");
+    assert!(fs.create_dir_all(Path::new(".knecht/tasks")).is_err());
+}
+
+#[test]
+fn test_memory_fs_ignores_current_dir_components() {
+    let fs = MemoryFileSystem::new().with_file("./.knecht/pain", "x");
+    assert!(fs.exists(Path::new(".knecht/pain")));
+}
+
+#[test]
+fn test_memory_fs_clones_share_contents() {
+    let fs = MemoryFileSystem::new().with_dir(".knecht");
+    let clone = fs.clone();
+    writeln!(clone.append(Path::new(".knecht/pain")).unwrap(), "shared").unwrap();
+    assert_eq!(fs.read_to_string(".knecht/pain").unwrap(), "shared\n");
+}
+
+#[test]
+fn test_memory_fs_fail_nth_only_fails_that_call() {
+    let fs = MemoryFileSystem::new().with_dir("d").fail_nth(FsOperation::Create, 2);
+    assert!(fs.create(Path::new("d/one")).is_ok());
+    assert!(fs.create(Path::new("d/two")).is_err());
+    assert!(fs.create(Path::new("d/three")).is_ok());
+    assert_eq!(fs.call_count(FsOperation::Create), 3);
+}
+
+#[test]
+fn test_memory_fs_fail_on_path() {
+    let fs = MemoryFileSystem::new().with_file("a", "").with_file("b", "").fail_on_path(FsOperation::Open, "b");
+    assert!(fs.open(Path::new("a")).is_ok());
+    assert!(fs.open(Path::new("b")).is_err());
+}
+
+#[test]
+fn test_memory_fs_read_fault_fails_reader() {
+    let fs = MemoryFileSystem::new().with_file("a", "line\n").fail_always(FsOperation::Read);
+    let mut reader = fs.open(Path::new("a")).unwrap();
+    let mut line = String::new();
+    assert!(reader.read_line(&mut line).is_err());
+}
+
+#[test]
+fn test_memory_fs_clear_faults() {
+    let fs = MemoryFileSystem::new().with_file("a", "").fail_always(FsOperation::Open);
+    assert!(fs.open(Path::new("a")).is_err());
+    fs.clear_faults();
+    assert!(fs.open(Path::new("a")).is_ok());
+}
+
+#[test]
+fn test_add_and_find_task_with_memory_fs() {
+    let fs = MemoryFileSystem::new().with_dir(".knecht/tasks");
+    let id = add_task_with_fs("Remember me".to_string(), None, Some("Done".to_string()), &fs).unwrap();
+    let task = find_task_by_id_with_fs(&id, &fs).unwrap();
+    assert_eq!(task.title, "Remember me");
+}
+
+#[test]
+fn test_write_tasks_partial_write_leaves_earlier_files() {
+    // The second task file's write fails: the first task is persisted, the error surfaces
+    let fs = MemoryFileSystem::new().with_dir(".knecht/tasks").fail_nth(FsOperation::Write, 2);
+    let result = write_tasks_with_fs(&[task("a"), task("b"), task("c")], &fs);
+
+    assert!(result.is_err());
+    fs.clear_faults();
+    let ids: Vec<String> = read_tasks_with_fs(&fs).unwrap().into_iter().map(|t| t.id).collect();
+    assert!(ids.contains(&"a".to_string()), "first task should have been written, got: {:?}", ids);
+    assert!(!ids.contains(&"c".to_string()), "writing should stop at the failure, got: {:?}", ids);
+}
+
+#[test]
+fn test_mark_task_done_fails_when_pain_log_append_fails() {
+    let fs = MemoryFileSystem::new()
+        .with_file(".knecht/tasks/a", "a,open,First,,,\n")
+        .with_file(".knecht/tasks/b", "b,open,Second,,,\n")
+        .fail_always(FsOperation::Append);
+
+    // Completing b skips a, which requires appending a skip entry to the pain log
+    assert!(mark_task_done_with_fs("b", &fs).is_err());
+    assert_eq!(find_task_by_id_with_fs("b", &fs).unwrap().status, "open", "task should stay open");
+}
+
+#[test]
+fn test_pain_log_round_trip_with_memory_fs() {
+    let fs = MemoryFileSystem::new().with_file(".knecht/tasks/a", "a,open,First,,,\n");
+    increment_pain_count_with_fs("a", Some("hurts"), &fs).unwrap();
+    increment_pain_count_with_fs("a", Some("still hurts"), &fs).unwrap();
+    let entries = read_pain_entries_with_fs(&fs).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1].description, "still hurts");
+}
@@ -98,6 +98,7 @@ fn next_skips_done_tasks() {
         }
 
         // Mark first task as done
+        run_command(&["check", &format!("task-{}", id1), "--all"], &temp);
         run_command(&["done", &format!("task-{}", id1)], &temp);
 
         let result = run_command(&["next"], &temp);
@@ -117,6 +118,7 @@ fn next_handles_no_open_tasks() {
         // Add and complete a task
         let add_result = run_command(&["add", "Only task", "-a", "Done"], &temp);
         let task_id = extract_task_id(&add_result.stdout);
+        run_command(&["check", &format!("task-{}", task_id), "--all"], &temp);
         run_command(&["done", &format!("task-{}", task_id)], &temp);
 
         let result = run_command(&["next"], &temp);
@@ -243,6 +245,7 @@ fn next_prefers_unblocked_subtasks_over_parent_task() {
         run_command(&["block", &format!("task-{}", id4), "by", &format!("task-{}", id2)], &temp);
 
         // Complete the foundation
+        run_command(&["check", &format!("task-{}", id2), "--all"], &temp);
         run_command(&["done", &format!("task-{}", id2)], &temp);
 
         // Now next should suggest id3 or id4 (unblocked subtasks) instead of id1 (parent)
@@ -1,7 +1,7 @@
 mod common;
 
 #[allow(unused_imports)]
-use common::{cleanup_temp_dir, extract_task_id, run_command, setup_temp_dir, with_initialized_repo};
+use common::{cleanup_temp_dir, extract_task_id, run_command, run_command_with_env, setup_temp_dir, with_initialized_repo};
 #[allow(unused_imports)]
 use std::fs;
 
@@ -284,3 +284,266 @@ fn pain_count_computed_from_log() {
             "Pain count should be computed from log entries, got: {}", list.stdout);
     });
 }
+
+fn fs_with_old_sentry_and_fresh_manual_pain(config: &str, now: u64) -> knecht::MemoryFileSystem {
+    let year = 365 * 86_400;
+    knecht::MemoryFileSystem::new()
+        .with_dir(".knecht/tasks")
+        .with_file(".knecht/config", config)
+        .with_file(".knecht/tasks/aaa", "aaa,open,Old Sentry noise,,\n")
+        .with_file(".knecht/tasks/bbb", "bbb,open,Hurt this morning,,\n")
+        .with_file(".knecht/pain", &format!(
+            "aaa|{old}|sentry|ISSUE-1|Error\naaa|{old}|sentry|ISSUE-1|Error\naaa|{old}|sentry|ISSUE-1|Error\nbbb|{now}|manual||Slow build\n",
+            old = now - year,
+            now = now,
+        ))
+}
+
+#[test]
+fn test_pain_scores_decay_by_source_half_life() {
+    let now = 1_700_000_000;
+    let day = 86_400;
+
+    let undecayed = knecht::get_all_pain_scores(now, &fs_with_old_sentry_and_fresh_manual_pain("", now)).unwrap();
+    assert_eq!((undecayed["aaa"], undecayed["bbb"]), (3.0, 1.0));
+
+    let fs = fs_with_old_sentry_and_fresh_manual_pain("pain_half_life = 73d\npain_half_life.manual = 1d\n", now);
+    let scores = knecht::get_all_pain_scores(now, &fs).unwrap();
+    // A year is five 73-day half-lives
+    assert!((scores["aaa"] - 3.0 / 32.0).abs() < 1e-9, "got {}", scores["aaa"]);
+    assert_eq!(scores["bbb"], 1.0);
+
+    let a_day_later = knecht::get_all_pain_scores(now + day, &fs).unwrap();
+    assert!((a_day_later["bbb"] - 0.5).abs() < 1e-9, "got {}", a_day_later["bbb"]);
+}
+
+#[test]
+fn test_next_prefers_recent_pain_when_decay_configured() {
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+
+    let next = knecht::find_next_task_with_fs(&fs_with_old_sentry_and_fresh_manual_pain("", now)).unwrap().unwrap();
+    assert_eq!(next.id, "aaa");
+
+    let decayed = fs_with_old_sentry_and_fresh_manual_pain("pain_half_life.sentry = 30d\n", now);
+    assert_eq!(knecht::find_next_task_with_fs(&decayed).unwrap().unwrap().id, "bbb");
+}
+
+#[test]
+fn list_shows_raw_and_decayed_pain_when_half_life_set() {
+    with_initialized_repo(|temp| {
+        let task_id = extract_task_id(&run_command(&["add", "Fix bug", "-a", "Done"], temp).stdout);
+        run_command(&["pain", "-t", &task_id, "-d", "Hurt"], temp);
+        assert!(run_command(&["config", "set", "pain_half_life", "7d"], temp).success);
+        assert!(!run_command(&["config", "set", "pain_half_life.sentry", "soon"], temp).success);
+
+        let list = run_command(&["list"], temp);
+        assert!(list.stdout.contains("Fix bug (pain count: 1, decayed: 1.0)"), "got: {}", list.stdout);
+
+        let json = run_command(&["--json", "list"], temp);
+        let value: serde_json::Value = serde_json::from_str(&json.stdout).unwrap();
+        assert_eq!(value["tasks"][0]["pain_count"], 1);
+        assert!(value["tasks"][0]["pain_score"].as_f64().unwrap() > 0.99);
+    });
+}
+
+#[test]
+fn test_pain_during_task_round_trips_through_the_log() {
+    let fs = knecht::MemoryFileSystem::new()
+        .with_dir(".knecht/tasks")
+        .with_file(".knecht/tasks/aaa", "aaa,open,Flaky test,,\n")
+        .with_file(".knecht/tasks/bbb", "bbb,claimed,Feature,,\n")
+        .with_file(".knecht/pain", "aaa|1|manual||Old entry | with a pipe\n");
+
+    knecht::record_pain_with_fs("aaa", Some("Broke my build"), Some("bbb"), &fs).unwrap();
+    knecht::record_pain_with_fs("aaa", Some("Own task"), Some("aaa"), &fs).unwrap();
+    assert!(knecht::record_pain_with_fs("aaa", Some("Nope"), Some("zzz"), &fs).is_err());
+
+    let entries = knecht::read_pain_entries_with_fs(&fs).unwrap();
+    let during: Vec<(&str, Option<&str>)> = entries.iter().map(|e| (e.description.as_str(), e.during_task.as_deref())).collect();
+    assert_eq!(during, vec![("Old entry | with a pipe", None), ("Broke my build", Some("bbb")), ("Own task", None)]);
+    assert!(matches!(entries[1].source_type, knecht::PainSourceType::Manual));
+}
+
+#[test]
+fn pain_records_the_task_being_worked_on() {
+    with_initialized_repo(|temp| {
+        let flaky = extract_task_id(&run_command(&["add", "Flaky test", "-a", "Done"], temp).stdout);
+        let feature = extract_task_id(&run_command(&["add", "Feature", "-a", "Done"], temp).stdout);
+        let other = extract_task_id(&run_command(&["add", "Other", "-a", "Done"], temp).stdout);
+        let env = [("KNECHT_AGENT", "agent-1")];
+        run_command_with_env(&["start", &feature], temp, &env);
+
+        let result = run_command_with_env(&["pain", "-t", &flaky, "-d", "Failed my CI run"], temp, &env);
+        assert!(result.success, "pain failed: {}", result.stderr);
+        run_command_with_env(&["pain", "-t", &flaky, "-d", "Broke again", "--during", &other], temp, &env);
+        run_command_with_env(&["pain", "-t", &flaky, "-d", "Someone else's"], temp, &[("KNECHT_AGENT", "agent-2")]);
+
+        let show = run_command(&["show", &flaky], temp);
+        assert!(show.stdout.contains(&format!("Failed my CI run (while on task-{})", feature)), "got: {}", show.stdout);
+        assert!(show.stdout.contains(&format!("Broke again (while on task-{})", other)), "got: {}", show.stdout);
+        assert!(show.stdout.contains("  3. Someone else's\n"), "got: {}", show.stdout);
+
+        let json = run_command(&["--json", "show", &flaky], temp);
+        let value: serde_json::Value = serde_json::from_str(&json.stdout).unwrap();
+        assert_eq!(value["pain"][0]["during_task"], feature.as_str());
+
+        let missing = run_command(&["pain", "-t", &flaky, "-d", "x", "--during", "task-nope"], temp);
+        assert_eq!(missing.exit_code, Some(4));
+    });
+}
+
+#[test]
+fn test_weighted_pain_entries_count_their_weight() {
+    let fs = knecht::MemoryFileSystem::new()
+        .with_dir(".knecht/tasks")
+        .with_file(".knecht/tasks/aaa", "aaa,open,Noisy,,\n")
+        .with_file(".knecht/tasks/bbb", "bbb,open,Quiet,,\n")
+        .with_file(".knecht/config", "pain_half_life = 1d\n")
+        .with_file(".knecht/pain", "aaa|0|sentry;count=40|PROJ-1|Sentry event: Boom\nbbb|0|manual||Hurts\nbbb|0|manual;count=0|x|Bad count\n");
+
+    assert_eq!(knecht::get_pain_count_for_task("aaa", &fs).unwrap(), 40);
+    assert_eq!(knecht::get_all_pain_counts(&fs).unwrap()["bbb"], 1);
+    let scores = knecht::get_all_pain_scores(86_400, &fs).unwrap();
+    assert_eq!(scores["aaa"], 20.0);
+    assert_eq!(knecht::find_next_task_with_fs(&fs).unwrap().unwrap().id, "aaa");
+}
+
+#[test]
+fn test_compaction_folds_only_consecutive_identical_lines() {
+    let sentry = "aaa|5|sentry|PROJ-1|Sentry event: Boom\n";
+    let pain = format!(
+        "{}{}aaa|5|sentry;count=3|PROJ-1|Sentry event: Boom\naaa|6|sentry|PROJ-1|Sentry event: Boom\nbbb|6|manual;during=aaa||Hurts\nbbb|6|manual||Hurts\n{}",
+        sentry, sentry, sentry,
+    );
+    let fs = knecht::MemoryFileSystem::new().with_dir(".knecht").with_file(".knecht/pain", &pain);
+    let counts_before = knecht::get_all_pain_counts(&fs).unwrap();
+
+    assert_eq!(knecht::compact_pain_log_with_fs(&fs).unwrap(), (7, 5));
+    assert_eq!(knecht::get_all_pain_counts(&fs).unwrap(), counts_before);
+    let lines: Vec<(u64, u32, Option<String>)> = knecht::read_pain_entries_with_fs(&fs).unwrap().into_iter()
+        .map(|e| (e.timestamp, e.count, e.during_task))
+        .collect();
+    assert_eq!(lines, vec![(5, 5, None), (6, 1, None), (6, 1, Some("aaa".to_string())), (6, 1, None), (5, 1, None)]);
+
+    assert_eq!(knecht::compact_pain_log_with_fs(&fs).unwrap(), (5, 5));
+}
+
+#[test]
+fn compact_pain_command_folds_duplicates() {
+    with_initialized_repo(|temp| {
+        let task_id = extract_task_id(&run_command(&["add", "Noisy", "-a", "Done"], temp).stdout);
+        let line = format!("{}|1700000000|sentry|PROJ-1|Sentry event: Boom\n", task_id);
+        fs::write(temp.join(".knecht/pain"), line.repeat(25)).unwrap();
+
+        let result = run_command(&["compact-pain"], temp);
+        assert!(result.success, "compact-pain failed: {}", result.stderr);
+        assert!(result.stdout.contains("from 25 to 1 entries"), "got: {}", result.stdout);
+        assert_eq!(fs::read_to_string(temp.join(".knecht/pain")).unwrap().lines().count(), 1);
+
+        let show = run_command(&["show", &task_id], temp);
+        assert!(show.stdout.contains("Pain (25 instances)"), "got: {}", show.stdout);
+        assert!(show.stdout.contains("Sentry event: Boom (×25)"), "got: {}", show.stdout);
+        assert!(run_command(&["list"], temp).stdout.contains("(pain count: 25)"));
+
+        let json = run_command(&["--json", "compact-pain"], temp);
+        assert!(json.stdout.contains("\"after\":1"), "got: {}", json.stdout);
+    });
+}
+
+fn fs_with_mistaken_pain() -> knecht::MemoryFileSystem {
+    knecht::MemoryFileSystem::new()
+        .with_dir(".knecht/tasks")
+        .with_file(".knecht/tasks/aaa", "aaa,open,Right task,,\n")
+        .with_file(".knecht/tasks/bbb", "bbb,open,Wrong task,,\n")
+        .with_file(".knecht/pain", "bbb|1|manual||Meant for aaa\nbbb|2|skip|ccc|Skip: task-ccc completed instead\nbbb|3|manual;count=2||Real\n")
+}
+
+#[test]
+fn test_retract_and_move_append_corrections_honoured_by_counts() {
+    let fs = fs_with_mistaken_pain();
+
+    let moved = knecht::move_pain_entry_with_fs("bbb", 1, "aaa", &fs).unwrap();
+    assert_eq!((moved.task_id.as_str(), moved.description.as_str()), ("aaa", "Meant for aaa"));
+    // Entry 1 of bbb is now the skip
+    let retracted = knecht::retract_pain_entry_with_fs("bbb", 1, Some("Not a real skip"), &fs).unwrap();
+    assert!(matches!(retracted.source_type, knecht::PainSourceType::Skip));
+
+    let log = fs.read_to_string(std::path::Path::new(".knecht/pain")).unwrap();
+    assert_eq!(log.lines().count(), 5, "corrections are appended, got: {}", log);
+    let retraction = log.lines().nth(4).unwrap();
+    assert!(retraction.starts_with("bbb|") && retraction.contains("|retract|") && retraction.ends_with("|Not a real skip"), "got: {}", log);
+
+    let counts = knecht::get_all_pain_counts(&fs).unwrap();
+    assert_eq!((counts["aaa"], counts["bbb"]), (1, 2));
+    let descriptions: Vec<String> = knecht::get_pain_entries_for_task("aaa", &fs).unwrap().into_iter().map(|e| e.description).collect();
+    assert_eq!(descriptions, vec!["Meant for aaa"]);
+
+    // Compaction folds the corrections in
+    assert_eq!(knecht::compact_pain_log_with_fs(&fs).unwrap(), (5, 2));
+    assert_eq!(knecht::get_all_pain_counts(&fs).unwrap(), counts);
+}
+
+#[test]
+fn test_corrections_survive_lines_merged_in_before_them() {
+    let fs = fs_with_mistaken_pain();
+    knecht::move_pain_entry_with_fs("bbb", 1, "aaa", &fs).unwrap();
+    knecht::retract_pain_entry_with_fs("bbb", 1, None, &fs).unwrap();
+
+    // A branch merged in adds lines ahead of the corrected ones
+    let path = std::path::Path::new(".knecht/pain");
+    let log = fs.read_to_string(path).unwrap();
+    let merged = fs.with_file(".knecht/pain", &format!("bbb|0|manual||From the other branch\naaa|0|skip|ccc|Also merged\n{}", log));
+    let described = |task: &str| -> Vec<String> {
+        knecht::get_pain_entries_for_task(task, &merged).unwrap().into_iter().map(|e| e.description).collect()
+    };
+    assert_eq!(described("aaa"), vec!["Also merged", "Meant for aaa"]);
+    assert_eq!(described("bbb"), vec!["From the other branch", "Real"]);
+
+    knecht::compact_pain_log_with_fs(&merged).unwrap();
+    assert_eq!(described("bbb"), vec!["From the other branch", "Real"]);
+}
+
+#[test]
+fn test_pain_entry_references_are_checked() {
+    let fs = fs_with_mistaken_pain();
+
+    match knecht::retract_pain_entry_with_fs("bbb", 4, None, &fs) {
+        Err(knecht::KnechtError::PainEntryNotFound { entry, count, .. }) => assert_eq!((entry, count), (4, 3)),
+        other => panic!("expected PainEntryNotFound, got {:?}", other.map(|e| e.description)),
+    }
+    assert!(knecht::retract_pain_entry_with_fs("bbb", 0, None, &fs).is_err());
+    assert!(matches!(knecht::move_pain_entry_with_fs("bbb", 1, "zzz", &fs), Err(knecht::KnechtError::TaskNotFound(_))));
+
+    knecht::retract_pain_entry_with_fs("aaa", 1, None, &fs).unwrap_err();
+    knecht::retract_pain_entry_with_fs("bbb", 3, None, &fs).unwrap();
+    assert!(knecht::retract_pain_entry_with_fs("bbb", 3, None, &fs).is_err(), "a retracted entry is gone");
+}
+
+#[test]
+fn pain_retract_and_move_commands() {
+    with_initialized_repo(|temp| {
+        let right = extract_task_id(&run_command(&["add", "Right", "-a", "Done"], temp).stdout);
+        let wrong = extract_task_id(&run_command(&["add", "Wrong", "-a", "Done"], temp).stdout);
+        run_command(&["pain", "-t", &wrong, "-d", "Typo"], temp);
+        run_command(&["pain", "-t", &wrong, "-d", "Meant for right"], temp);
+
+        let moved = run_command(&["pain", "--move", &format!("task-{}:2", wrong), &right], temp);
+        assert!(moved.success, "move failed: {}", moved.stderr);
+        assert!(moved.stdout.contains(&format!("Moved pain entry 2 from task-{} to task-{}", wrong, right)), "got: {}", moved.stdout);
+
+        let retracted = run_command(&["pain", "--retract", &format!("task-{}:1", wrong), "-d", "Typo"], temp);
+        assert!(retracted.success, "retract failed: {}", retracted.stderr);
+
+        let list = run_command(&["list"], temp);
+        assert!(list.stdout.contains("Right (pain count: 1)"), "got: {}", list.stdout);
+        assert!(!run_command(&["show", &wrong], temp).stdout.contains("Pain ("));
+        assert!(run_command(&["show", &right], temp).stdout.contains("  1. Meant for right"));
+
+        let missing = run_command(&["pain", "--retract", &format!("{}:5", wrong)], temp);
+        assert_eq!(missing.exit_code, Some(25));
+        assert!(missing.stderr.contains("[E_NO_SUCH_PAIN]"), "got: {}", missing.stderr);
+
+        assert_eq!(run_command(&["pain", "--retract", &wrong], temp).exit_code, Some(2));
+        assert_eq!(run_command(&["pain", "-t", &wrong, "--retract", &format!("{}:1", wrong)], temp).exit_code, Some(2));
+    });
+}
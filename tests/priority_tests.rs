@@ -0,0 +1,168 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, fs_with_tasks, run_command, setup_temp_dir, with_initialized_repo};
+use knecht::{explain_next_task_with_fs, find_next_task_with_strategy_with_fs, find_next_task_with_fs, priority_strategy_with_fs, strategy_for_name, now_secs, MemoryFileSystem, PriorityContext};
+
+const DAY: u64 = 86_400;
+
+/// As of `now`, `old` was created long ago and hurts a little; `big` hurts most but is large; `small`
+/// hurts less than `big` but is tiny
+fn fs_with(config: &str) -> MemoryFileSystem {
+    fs_at(config, now_secs())
+}
+
+fn fs_at(config: &str, now: u64) -> MemoryFileSystem {
+    let history = format!(
+        "old|{}|created|alice|\nbig|{}|created|alice|\nsmall|{}|created|alice|\nbig|{}|estimated|alice|8\nsmall|{}|estimated|alice|1\n",
+        now - 60 * DAY, now - 2 * DAY, now - DAY, now - DAY, now - DAY,
+    );
+    let mut pain = String::from("old|1|manual||Annoying\n");
+    pain.push_str(&"big|1|manual||Hurts\n".repeat(4));
+    pain.push_str(&"small|1|manual||Stings\n".repeat(2));
+    fs_with_tasks(&[("old", "open", "Old"), ("big", "open", "Big"), ("small", "open", "Small")])
+        .with_file(".knecht/config", config)
+        .with_file(".knecht/history", &history)
+        .with_file(".knecht/pain", &pain)
+}
+
+fn next_with(name: &str, fs: &MemoryFileSystem) -> String {
+    let strategy = strategy_for_name(name).unwrap();
+    find_next_task_with_strategy_with_fs(strategy.as_ref(), fs).unwrap().unwrap().id
+}
+
+#[test]
+fn test_strategies_rank_differently() {
+    let fs = fs_with("");
+    assert_eq!(next_with("pain", &fs), "big");
+    assert_eq!(next_with("age", &fs), "old");
+    assert_eq!(next_with("pain-per-estimate", &fs), "small");
+}
+
+#[test]
+fn test_strategy_scores() {
+    let now = 1_700_000_000;
+    let context = PriorityContext::load_with_fs(now, &fs_at("", now)).unwrap();
+    let task = |id: &str| context.tasks.iter().find(|t| t.id == id).unwrap().clone();
+
+    assert_eq!(strategy_for_name("pain").unwrap().score(&task("big"), &context), 4.0);
+    assert_eq!(strategy_for_name("age").unwrap().score(&task("old"), &context), 60.0);
+    assert_eq!(strategy_for_name("pain-per-estimate").unwrap().score(&task("big"), &context), 0.5);
+    // Blend normalizes each strategy to the top task: big = 1 + 2/60 + 0.5/2
+    let blend = strategy_for_name("blend").unwrap().score(&task("big"), &context);
+    assert!((blend - (1.0 + 2.0 / 60.0 + 0.25)).abs() < 1e-9, "got {}", blend);
+}
+
+#[test]
+fn test_blend_normalizes_over_unresolved_tasks_once() {
+    let now = 1_700_000_000;
+    let config = "statuses = open, claimed, delivered, done, wontdo\nresolved_statuses = done, wontdo\n";
+    let fs = fs_at(config, now)
+        .with_file(".knecht/tasks/ancient", "ancient,wontdo,Abandoned,,\n")
+        .with_file(".knecht/history", &format!(
+            "ancient|{}|created|alice|\nold|{}|created|alice|\nbig|{}|created|alice|\nsmall|{}|created|alice|\nbig|{}|estimated|alice|8\nsmall|{}|estimated|alice|1\n",
+            now - 600 * DAY, now - 60 * DAY, now - 2 * DAY, now - DAY, now - DAY, now - DAY,
+        ));
+    let context = PriorityContext::load_with_fs(now, &fs).unwrap();
+    let blend = strategy_for_name("blend").unwrap();
+
+    // old is the oldest unresolved task, so it tops the age part; ancient is resolved.
+    // old = 1/4 pain + 60/60 days + (1/1)/(2/1) pain per estimate
+    let scores = blend.score_all(&context);
+    assert!((scores["old"] - (0.25 + 1.0 + 0.5)).abs() < 1e-9, "got {}", scores["old"]);
+    for task in &context.tasks {
+        assert_eq!(scores[&task.id], blend.score(task, &context), "{}", task.id);
+    }
+}
+
+#[test]
+fn test_configured_strategy_and_blend_weights() {
+    assert_eq!(find_next_task_with_fs(&fs_with("")).unwrap().unwrap().id, "big");
+    assert_eq!(find_next_task_with_fs(&fs_with("next_strategy = age\n")).unwrap().unwrap().id, "old");
+
+    let age_heavy = fs_with("next_strategy = blend\nblend.age = 5\n");
+    assert_eq!(priority_strategy_with_fs(None, &age_heavy).unwrap().name(), "blend");
+    assert_eq!(find_next_task_with_fs(&age_heavy).unwrap().unwrap().id, "old");
+
+    assert!(priority_strategy_with_fs(None, &fs_with("next_strategy = coin-flip\n")).is_err());
+    assert!(priority_strategy_with_fs(None, &fs_with("next_strategy = blend\nblend.age = -1\n")).is_err());
+}
+
+#[test]
+fn next_strategy_flag_and_estimate_command() {
+    with_initialized_repo(|temp| {
+        let big = extract_task_id(&run_command(&["add", "Big", "-a", "Done"], temp).stdout);
+        let small = extract_task_id(&run_command(&["add", "Small", "-a", "Done"], temp).stdout);
+        for _ in 0..3 {
+            run_command(&["pain", "-t", &big, "-d", "Hurts"], temp);
+        }
+        run_command(&["pain", "-t", &small, "-d", "Stings"], temp);
+
+        let estimate = run_command(&["estimate", &big, "5"], temp);
+        assert!(estimate.success, "estimate failed: {}", estimate.stderr);
+        assert!(!run_command(&["estimate", &big, "0"], temp).success);
+
+        assert!(run_command(&["next"], temp).stdout.contains(&format!("task-{}", big)));
+        let wsjf = run_command(&["next", "--strategy", "pain-per-estimate"], temp);
+        assert!(wsjf.stdout.contains(&format!("task-{}", small)), "got: {}", wsjf.stdout);
+
+        let unknown = run_command(&["next", "--strategy", "coin-flip"], temp);
+        assert_eq!(unknown.exit_code, Some(2));
+        assert!(unknown.stderr.contains("pain-per-estimate"), "got: {}", unknown.stderr);
+    });
+}
+
+#[test]
+fn test_explain_records_candidates_and_blocker_chain() {
+    let fs = fs_with("").with_file(".knecht/blockers", "big|task-small\nsmall|task-old\n");
+    let strategy = strategy_for_name("pain").unwrap();
+
+    let explanation = explain_next_task_with_fs(strategy.as_ref(), &fs).unwrap();
+    assert_eq!(explanation.strategy, "pain");
+    assert_eq!(explanation.suggestion.unwrap().id, "old");
+    let candidates: Vec<(&str, f64, bool)> = explanation.candidates.iter().map(|t| (t.task_id.as_str(), t.score, t.blocked)).collect();
+    assert_eq!(candidates, vec![("big", 4.0, true), ("small", 2.0, true), ("old", 1.0, false)]);
+    let chain: Vec<&str> = explanation.blocker_chain.iter().map(|t| t.task_id.as_str()).collect();
+    assert_eq!(chain, vec!["big", "small", "old"]);
+    assert_eq!(explanation.pain.len(), 1);
+    assert_eq!((explanation.pain[0].entry.description.as_str(), explanation.pain[0].weight), ("Annoying", 1.0));
+}
+
+#[test]
+fn test_explain_lists_delivered_tasks_preempting_open_work() {
+    let fs = fs_with("").with_file(".knecht/tasks/done1", "done1,delivered,Awaiting review,,\n");
+    let strategy = strategy_for_name("pain").unwrap();
+
+    let explanation = explain_next_task_with_fs(strategy.as_ref(), &fs).unwrap();
+    assert_eq!(explanation.suggestion.unwrap().id, "done1");
+    assert_eq!(explanation.delivered.len(), 1);
+    assert_eq!(explanation.candidates.len(), 3);
+    assert!(explanation.blocker_chain.is_empty());
+}
+
+#[test]
+fn next_explain_prints_reasoning() {
+    with_initialized_repo(|temp| {
+        let parent = extract_task_id(&run_command(&["add", "Parent", "-a", "Done"], temp).stdout);
+        let child = extract_task_id(&run_command(&["add", "Child", "-a", "Done"], temp).stdout);
+        run_command(&["pain", "-t", &parent, "-d", "Parent hurts"], temp);
+        run_command(&["pain", "-t", &parent, "-d", "Parent hurts again"], temp);
+        run_command(&["pain", "-t", &child, "-d", "Child stings"], temp);
+        run_command(&["block", &parent, "by", &child], temp);
+
+        let result = run_command(&["next", "--explain"], temp);
+        assert!(result.success, "next --explain failed: {}", result.stderr);
+        assert!(result.stdout.contains(&format!("Suggested next task: task-{}", child)), "got: {}", result.stdout);
+        assert!(result.stdout.contains("Why (strategy: pain)"), "got: {}", result.stdout);
+        assert!(result.stdout.contains(&format!("task-{} is blocked; walked to its best open blocker: task-{} → task-{}", parent, parent, child)), "got: {}", result.stdout);
+        assert!(result.stdout.contains("Child stings"), "got: {}", result.stdout);
+
+        let plain = run_command(&["next"], temp);
+        assert!(!plain.stdout.contains("Why"), "got: {}", plain.stdout);
+
+        let json = run_command(&["--json", "next", "--explain"], temp);
+        let value: serde_json::Value = serde_json::from_str(&json.stdout).unwrap();
+        assert_eq!(value["explanation"]["blocker_chain"].as_array().unwrap().len(), 2);
+        assert_eq!(value["explanation"]["pain"][0]["source_type"], "manual");
+    });
+}
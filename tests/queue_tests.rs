@@ -0,0 +1,87 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, fs_with_tasks, run_command, setup_temp_dir, with_initialized_repo};
+use knecht::{find_next_task_with_strategy_with_fs, now_secs, rank_tasks_with_fs, strategy_for_name, MemoryFileSystem};
+
+/// `parent` hurts most but waits on `middle`, which waits on `leaf`; `other` is independent
+fn fs_with_blocker_tree() -> MemoryFileSystem {
+    fs_with_tasks(&[
+        ("parent", "open", "Parent"),
+        ("middle", "open", "Middle"),
+        ("leaf", "open", "Leaf"),
+        ("other", "open", "Other"),
+        ("busy", "claimed", "Someone else's"),
+        ("review", "delivered", "Awaiting review"),
+    ])
+        .with_file(".knecht/blockers", "parent|middle\nmiddle|leaf\n")
+        .with_file(".knecht/pain", &[
+            "parent|1|manual||A\n".repeat(5),
+            "other|1|manual||B\n".repeat(3),
+            "middle|1|manual||C\n".repeat(2),
+        ].concat())
+}
+
+#[test]
+fn test_queue_replaces_blocked_tasks_with_leaf_blockers_once() {
+    let fs = fs_with_blocker_tree();
+    let strategy = strategy_for_name("pain").unwrap();
+
+    let queue = rank_tasks_with_fs(strategy.as_ref(), &fs).unwrap();
+    let order: Vec<(&str, Option<&str>)> = queue.iter().map(|e| (e.task.id.as_str(), e.unblocks.as_deref())).collect();
+    assert_eq!(order, vec![("review", None), ("leaf", Some("parent")), ("other", None)]);
+    assert_eq!(queue[2].score, 3.0);
+
+    let next = find_next_task_with_strategy_with_fs(strategy.as_ref(), &fs).unwrap().unwrap();
+    assert_eq!(next.id, queue[0].task.id);
+}
+
+#[test]
+fn test_queue_leaves_out_blockers_someone_holds() {
+    let strategy = strategy_for_name("pain").unwrap();
+    let held = |claimed_at: u64, config: &str| fs_with_blocker_tree()
+        .with_file(".knecht/tasks/leaf", "leaf,claimed,Leaf,,\n")
+        .with_file(".knecht/history", &format!("leaf|{}|claimed|alice|\nbusy|{}|claimed|bob|\n", claimed_at, now_secs()))
+        .with_file(".knecht/config", config);
+
+    let queue = rank_tasks_with_fs(strategy.as_ref(), &held(now_secs(), "claim_lease = 1h\n")).unwrap();
+    let order: Vec<(&str, Option<&str>)> = queue.iter().map(|e| (e.task.id.as_str(), e.unblocks.as_deref())).collect();
+    assert_eq!(order, vec![("review", None), ("other", None)], "parent and middle wait on alice");
+
+    let queue = rank_tasks_with_fs(strategy.as_ref(), &held(1, "claim_lease = 1h\n")).unwrap();
+    let order: Vec<(&str, Option<&str>)> = queue.iter().map(|e| (e.task.id.as_str(), e.unblocks.as_deref())).collect();
+    assert_eq!(order, vec![("review", None), ("leaf", Some("parent")), ("other", None)], "an expired claim is free again");
+}
+
+#[test]
+fn queue_command_limits_and_filters() {
+    with_initialized_repo(|temp| {
+        let first = extract_task_id(&run_command(&["add", "First", "-a", "Done"], temp).stdout);
+        let second = extract_task_id(&run_command(&["add", "Second", "-a", "Done"], temp).stdout);
+        let third = extract_task_id(&run_command(&["add", "Third", "-a", "Done"], temp).stdout);
+        for _ in 0..2 {
+            run_command(&["pain", "-t", &second, "-d", "Hurts"], temp);
+        }
+        run_command(&["pain", "-t", &third, "-d", "Stings"], temp);
+        run_command(&["start", &first], temp);
+        run_command(&["check", &first, "--all"], temp);
+        run_command(&["deliver", &first], temp);
+
+        let queue = run_command(&["queue"], temp);
+        assert!(queue.success, "queue failed: {}", queue.stderr);
+        let lines: Vec<&str> = queue.stdout.lines().collect();
+        assert_eq!(lines.len(), 3, "got: {}", queue.stdout);
+        assert!(lines[0].contains(&format!("task-{}", first)) && lines[0].contains("[delivered]"), "got: {}", queue.stdout);
+        assert!(lines[1].contains(&format!("task-{}", second)), "got: {}", queue.stdout);
+
+        let open_only = run_command(&["--json", "queue", "--status", "open", "-n", "1"], temp);
+        let value: serde_json::Value = serde_json::from_str(&open_only.stdout).unwrap();
+        let entries = value["queue"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["task"]["id"], second.as_str());
+
+        let next = run_command(&["next", "-n", "2"], temp);
+        assert_eq!(next.stdout.lines().count(), 2, "got: {}", next.stdout);
+        assert!(next.stdout.contains(&format!("task-{}", second)), "got: {}", next.stdout);
+    });
+}
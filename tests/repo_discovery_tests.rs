@@ -0,0 +1,191 @@
+mod common;
+mod test_helpers;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, run_command_with_env, setup_temp_dir, with_initialized_repo};
+use knecht::{find_repo_root_with_fs, resolve_repo_root_with_fs, KnechtError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use test_helpers::TestFileSystem;
+
+#[test]
+fn list_from_subdirectory_finds_parent_repository() {
+    with_initialized_repo(|temp| {
+        run_command(&["add", "Task from root", "-a", "Done"], temp);
+        let subdir = temp.join("src/nested");
+        fs::create_dir_all(&subdir).unwrap();
+
+        let result = run_command(&["list"], &subdir);
+
+        assert!(result.success, "list should succeed from subdirectory, got: {}", result.stderr);
+        assert!(result.stdout.contains("Task from root"),
+            "Should list tasks from the parent repository, got: {}", result.stdout);
+    });
+}
+
+#[test]
+fn add_from_subdirectory_does_not_create_stray_knecht_directory() {
+    with_initialized_repo(|temp| {
+        let subdir = temp.join("src");
+        fs::create_dir_all(&subdir).unwrap();
+
+        let result = run_command(&["add", "Added from src", "-a", "Done"], &subdir);
+        assert!(result.success, "add should succeed from subdirectory, got: {}", result.stderr);
+        let task_id = extract_task_id(&result.stdout);
+
+        assert!(!subdir.join(".knecht").exists(), "Should not create .knecht in the subdirectory");
+        assert!(temp.join(format!(".knecht/tasks/{}", task_id)).exists(),
+            "Task should be written to the parent repository");
+    });
+}
+
+#[test]
+fn commands_fail_clearly_outside_a_repository() {
+    let temp = setup_temp_dir();
+
+    let result = run_command(&["list"], &temp);
+
+    assert!(!result.success, "list should fail outside a repository");
+    assert!(result.stderr.contains("not a knecht repository") && result.stderr.contains("knecht init"),
+        "Should explain that no repository was found, got: {}", result.stderr);
+
+    cleanup_temp_dir(temp);
+}
+
+#[test]
+fn init_creates_repository_in_current_directory_even_inside_another() {
+    with_initialized_repo(|temp| {
+        let subdir = temp.join("vendor/other");
+        fs::create_dir_all(&subdir).unwrap();
+
+        let result = run_command(&["init"], &subdir);
+
+        assert!(result.success, "init should succeed, got: {}", result.stderr);
+        assert!(subdir.join(".knecht/tasks").is_dir(), "init should not walk up to the parent repository");
+    });
+}
+
+#[test]
+fn dash_c_runs_command_in_given_directory() {
+    with_initialized_repo(|temp| {
+        run_command(&["add", "Task via -C", "-a", "Done"], temp);
+        let elsewhere = setup_temp_dir();
+
+        let result = run_command(&["-C", temp.to_str().unwrap(), "list"], &elsewhere);
+
+        assert!(result.success, "list -C should succeed, got: {}", result.stderr);
+        assert!(result.stdout.contains("Task via -C"), "Should list tasks from -C directory, got: {}", result.stdout);
+
+        cleanup_temp_dir(elsewhere);
+    });
+}
+
+#[test]
+fn dash_c_also_searches_parents_of_given_directory() {
+    with_initialized_repo(|temp| {
+        run_command(&["add", "Parent task", "-a", "Done"], temp);
+        let subdir = temp.join("docs");
+        fs::create_dir_all(&subdir).unwrap();
+        let elsewhere = setup_temp_dir();
+
+        let result = run_command(&["list", "-C", subdir.to_str().unwrap()], &elsewhere);
+
+        assert!(result.success, "list -C should succeed, got: {}", result.stderr);
+        assert!(result.stdout.contains("Parent task"), "Should find repository above -C directory, got: {}", result.stdout);
+
+        cleanup_temp_dir(elsewhere);
+    });
+}
+
+#[test]
+fn dash_c_with_init_creates_repository_there() {
+    let temp = setup_temp_dir();
+    let target = temp.join("project");
+    fs::create_dir_all(&target).unwrap();
+
+    let result = run_command(&["-C", target.to_str().unwrap(), "init"], &temp);
+
+    assert!(result.success, "init -C should succeed, got: {}", result.stderr);
+    assert!(target.join(".knecht/tasks").is_dir(), "Should create repository in -C directory");
+    assert!(!temp.join(".knecht").exists(), "Should not create repository in working directory");
+
+    cleanup_temp_dir(temp);
+}
+
+#[test]
+fn knecht_dir_env_selects_repository() {
+    with_initialized_repo(|temp| {
+        run_command(&["add", "Task via env", "-a", "Done"], temp);
+        let elsewhere = setup_temp_dir();
+
+        let result = run_command_with_env(&["list"], &elsewhere, &[("KNECHT_DIR", temp.to_str().unwrap())]);
+
+        assert!(result.success, "list with KNECHT_DIR should succeed, got: {}", result.stderr);
+        assert!(result.stdout.contains("Task via env"), "Should list tasks from KNECHT_DIR, got: {}", result.stdout);
+
+        cleanup_temp_dir(elsewhere);
+    });
+}
+
+#[test]
+fn knecht_dir_env_accepts_path_to_knecht_directory() {
+    with_initialized_repo(|temp| {
+        run_command(&["add", "Task via .knecht path", "-a", "Done"], temp);
+        let elsewhere = setup_temp_dir();
+        let knecht_dir = temp.join(".knecht");
+
+        let result = run_command_with_env(&["list"], &elsewhere, &[("KNECHT_DIR", knecht_dir.to_str().unwrap())]);
+
+        assert!(result.success, "list with KNECHT_DIR should succeed, got: {}", result.stderr);
+        assert!(result.stdout.contains("Task via .knecht path"), "Should list tasks, got: {}", result.stdout);
+
+        cleanup_temp_dir(elsewhere);
+    });
+}
+
+#[test]
+fn knecht_dir_env_without_repository_fails() {
+    let temp = setup_temp_dir();
+    let elsewhere = setup_temp_dir();
+
+    let result = run_command_with_env(&["list"], &elsewhere, &[("KNECHT_DIR", temp.to_str().unwrap())]);
+
+    assert!(!result.success, "list should fail when KNECHT_DIR has no repository");
+    assert!(result.stderr.contains("not a knecht repository"),
+        "Should explain that no repository was found, got: {}", result.stderr);
+
+    cleanup_temp_dir(temp);
+    cleanup_temp_dir(elsewhere);
+}
+
+#[test]
+fn test_find_repo_root_in_start_directory() {
+    let fs = TestFileSystem::new().with_dir("/work/.knecht");
+    assert_eq!(find_repo_root_with_fs(Path::new("/work"), &fs), Some(PathBuf::from("/work")));
+}
+
+#[test]
+fn test_find_repo_root_walks_up_to_nearest_parent() {
+    let fs = TestFileSystem::new().with_dir("/work/.knecht").with_dir("/work/sub/.knecht");
+    assert_eq!(find_repo_root_with_fs(Path::new("/work/sub/deep/er"), &fs), Some(PathBuf::from("/work/sub")));
+}
+
+#[test]
+fn test_find_repo_root_none_when_missing() {
+    let fs = TestFileSystem::new();
+    assert_eq!(find_repo_root_with_fs(Path::new("/work/sub"), &fs), None);
+}
+
+#[test]
+fn test_resolve_repo_root_explicit_dir_skips_search() {
+    let fs = TestFileSystem::new().with_dir("/work/.knecht");
+    let result = resolve_repo_root_with_fs(Path::new("/work/sub"), Some(Path::new("/elsewhere")), &fs);
+    assert!(matches!(result, Err(KnechtError::RepoNotFound(_))));
+}
+
+#[test]
+fn test_resolve_repo_root_relative_explicit_dir() {
+    let fs = TestFileSystem::new().with_dir("/work/other/.knecht");
+    let result = resolve_repo_root_with_fs(Path::new("/work"), Some(Path::new("other/.knecht")), &fs).unwrap();
+    assert_eq!(result, PathBuf::from("/work/other"));
+}
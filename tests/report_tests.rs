@@ -0,0 +1,107 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, run_command_with_env, setup_temp_dir, with_initialized_repo};
+use knecht::{flow_report_with_fs, week_start, DurationStats, MemoryFileSystem};
+
+const DAY: u64 = 86_400;
+// Monday 2024-03-04 00:00 UTC
+const MONDAY: u64 = 1_709_510_400;
+
+fn fs_with_history() -> MemoryFileSystem {
+    let history = [
+        format!("aaa|{}|created|alice|", MONDAY),
+        format!("aaa|{}|claimed|alice|", MONDAY + DAY),
+        format!("aaa|{}|delivered|alice|", MONDAY + 2 * DAY),
+        format!("aaa|{}|done|bob|", MONDAY + 3 * DAY),
+        format!("bbb|{}|created|alice|", MONDAY),
+        format!("bbb|{}|claimed|alice|", MONDAY + 7 * DAY),
+        format!("bbb|{}|done|alice|", MONDAY + 9 * DAY),
+        format!("ccc|{}|created|alice|", MONDAY + DAY),
+        format!("ddd|{}|created|alice|", MONDAY),
+        format!("ddd|{}|done|alice|", MONDAY + DAY),
+    ];
+    MemoryFileSystem::new()
+        .with_dir(".knecht/tasks")
+        .with_file(".knecht/tasks/aaa", "aaa,done,Shipped,,\n")
+        .with_file(".knecht/tasks/bbb", "bbb,done,Also shipped,,\n")
+        .with_file(".knecht/tasks/ccc", "ccc,open,Waiting,,\n")
+        .with_file(".knecht/tasks/ddd", "ddd,open,Reopened,,\n")
+        .with_file(".knecht/tasks/eee", "eee,claimed,Predates history,,\n")
+        .with_file(".knecht/history", &(history.join("\n") + "\n"))
+        .with_file(".knecht/pain", "ccc|1|manual;during=eee||Slowed me down\nddd|1|manual;during=eee;count=2||Again\nccc|1|manual;during=gone||Deleted\nccc|1|skip|aaa|Skip\n")
+}
+
+#[test]
+fn test_week_start_is_monday() {
+    assert_eq!(week_start(MONDAY), MONDAY);
+    assert_eq!(week_start(MONDAY + 6 * DAY + 3_600), MONDAY);
+    assert_eq!(week_start(MONDAY + 7 * DAY), MONDAY + 7 * DAY);
+    assert_eq!(week_start(0), 0);
+    assert_eq!(week_start(4 * DAY), 4 * DAY);
+}
+
+#[test]
+fn test_duration_stats_use_nearest_rank() {
+    assert_eq!(DurationStats::from_durations(Vec::new()), None);
+    let stats = DurationStats::from_durations((1..=20).rev().collect()).unwrap();
+    assert_eq!((stats.count, stats.p50, stats.p85, stats.p95, stats.max), (20, 10, 17, 19, 20));
+}
+
+#[test]
+fn test_flow_report_ignores_unreadable_timestamps() {
+    let fs = MemoryFileSystem::new()
+        .with_dir(".knecht/tasks")
+        .with_file(".knecht/tasks/aaa", "aaa,done,Shipped,,\n")
+        .with_file(".knecht/history", &format!("aaa|garbage|created|alice|\naaa|{}|done|alice|\n", MONDAY));
+
+    let report = flow_report_with_fs(1, MONDAY, &fs).unwrap();
+    assert_eq!(report.throughput[0].done, 1);
+    assert!(report.lead_time.is_none(), "no creation time, no lead time");
+}
+
+#[test]
+fn test_flow_report_from_history() {
+    let report = flow_report_with_fs(3, MONDAY + 10 * DAY, &fs_with_history()).unwrap();
+
+    let weeks: Vec<(u64, usize)> = report.throughput.iter().map(|w| (w.week_start, w.done)).collect();
+    assert_eq!(weeks, vec![(MONDAY - 7 * DAY, 0), (MONDAY, 1), (MONDAY + 7 * DAY, 1)]);
+
+    let lead = report.lead_time.unwrap();
+    assert_eq!((lead.count, lead.p50, lead.max), (2, 3 * DAY, 9 * DAY));
+    let cycle = report.cycle_time.unwrap();
+    assert_eq!((cycle.count, cycle.p50, cycle.max), (2, 2 * DAY, 2 * DAY));
+    assert_eq!(report.awaiting_verification.unwrap().max, DAY);
+
+    let aging: Vec<(&str, Option<u64>)> = report.aging.iter().map(|t| (t.task_id.as_str(), t.age_secs)).collect();
+    assert_eq!(aging, vec![("ddd", Some(10 * DAY)), ("ccc", Some(9 * DAY)), ("eee", None)]);
+
+    let friction: Vec<(&str, Option<&str>, usize, usize)> = report.friction.iter()
+        .map(|f| (f.task_id.as_str(), f.title.as_deref(), f.pain, f.tasks))
+        .collect();
+    assert_eq!(friction, vec![("eee", Some("Predates history"), 3, 2), ("gone", None, 1, 1)]);
+}
+
+#[test]
+fn report_command_prints_table_and_json() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Measured", "-a", "Done"], temp).stdout);
+        run_command(&["add", "Still open", "-a", "Done"], temp);
+        run_command(&["start", &id], temp);
+        run_command(&["check", &id, "--all"], temp);
+        assert!(run_command(&["done", &id], temp).success);
+
+        let table = run_command(&["report", "--weeks", "2"], temp);
+        assert!(table.success, "report failed: {}", table.stderr);
+        assert!(table.stdout.contains("Lead time (created → done)"), "got: {}", table.stdout);
+        assert!(table.stdout.contains("Still open"), "got: {}", table.stdout);
+
+        let json = run_command(&["--json", "report"], temp);
+        let value: serde_json::Value = serde_json::from_str(&json.stdout).unwrap();
+        let throughput = value["report"]["throughput"].as_array().unwrap();
+        assert_eq!(throughput.len(), 8);
+        assert_eq!(throughput[7]["done"], 1);
+        assert_eq!(value["report"]["cycle_time"]["count"], 1);
+        assert_eq!(value["report"]["aging"].as_array().unwrap().len(), 1);
+    });
+}
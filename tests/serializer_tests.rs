@@ -0,0 +1,249 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, setup_temp_dir, with_initialized_repo};
+use knecht::{add_task_with_fs, find_task_by_id_with_fs, migrate_task_format_with_fs, read_config_with_fs, read_tasks_with_fs, serializer_for_format, set_config_value_with_fs, task_serializer_with_fs, CsvSerializer, KnechtError, MarkdownSerializer, MemoryFileSystem, Task, TaskSerializer};
+use std::fs;
+
+fn task() -> Task {
+    Task {
+        id: "abc123".to_string(),
+        status: "open".to_string(),
+        title: "Title, with comma".to_string(),
+        description: Some("Line one\nLine two".to_string()),
+        pain_count: None,
+        acceptance_criteria: Some("Done".to_string()),
+    }
+}
+
+#[test]
+fn test_csv_serializer_round_trips_a_task() {
+    let mut buffer = Vec::new();
+    CsvSerializer.write_task(&task(), &mut buffer).unwrap();
+
+    let back = CsvSerializer.read_task(&mut &buffer[..]).unwrap().unwrap();
+
+    assert_eq!(back.title, "Title, with comma");
+    assert_eq!(back.description.as_deref(), Some("Line one\nLine two"));
+}
+
+#[test]
+fn test_csv_serializer_reads_empty_file_as_none() {
+    assert!(CsvSerializer.read_task(&mut &b""[..]).unwrap().is_none());
+}
+
+#[test]
+fn test_serializer_for_format() {
+    assert_eq!(serializer_for_format("csv").unwrap().name(), "csv");
+    assert!(serializer_for_format("yaml").is_none());
+}
+
+#[test]
+fn test_read_config_parses_key_values_and_comments() {
+    let fs = MemoryFileSystem::new().with_file(".knecht/config", "# comment\n\nformat = csv\n  other=value  \n");
+
+    let config = read_config_with_fs(&fs).unwrap();
+
+    assert_eq!(config.get("format").map(String::as_str), Some("csv"));
+    assert_eq!(config.get("other").map(String::as_str), Some("value"));
+}
+
+#[test]
+fn test_read_config_rejects_lines_without_equals() {
+    let fs = MemoryFileSystem::new().with_file(".knecht/config", "format csv\n");
+    assert!(matches!(read_config_with_fs(&fs), Err(KnechtError::InvalidConfig { .. })));
+}
+
+#[test]
+fn test_missing_config_defaults_to_csv() {
+    let fs = MemoryFileSystem::new().with_dir(".knecht/tasks");
+    assert_eq!(task_serializer_with_fs(&fs).unwrap().name(), "csv");
+}
+
+#[test]
+fn test_unknown_format_is_a_config_error() {
+    let fs = MemoryFileSystem::new()
+        .with_dir(".knecht/tasks")
+        .with_file(".knecht/config", "format = yaml\n");
+
+    match add_task_with_fs("Task".to_string(), None, None, &fs) {
+        Err(KnechtError::InvalidConfig { key, .. }) => assert_eq!(key, "format"),
+        other => panic!("expected InvalidConfig, got {:?}", other),
+    }
+    assert!(fs.files().iter().all(|p| !p.starts_with(".knecht/tasks/")), "no task file should be written");
+}
+
+#[test]
+fn test_storage_functions_use_configured_serializer() {
+    let fs = MemoryFileSystem::new()
+        .with_dir(".knecht/tasks")
+        .with_file(".knecht/config", "format = csv\n");
+
+    let id = add_task_with_fs("Configured".to_string(), None, Some("Done".to_string()), &fs).unwrap();
+
+    assert_eq!(find_task_by_id_with_fs(&id, &fs).unwrap().title, "Configured");
+    assert_eq!(read_tasks_with_fs(&fs).unwrap().len(), 1);
+}
+
+#[test]
+fn unknown_format_exits_with_config_error() {
+    with_initialized_repo(|temp| {
+        fs::write(temp.join(".knecht/config"), "format = yaml\n").unwrap();
+
+        let result = run_command(&["list"], temp);
+
+        assert_eq!(result.exit_code, Some(12));
+        assert!(result.stderr.contains("[E_CONFIG]"), "got: {}", result.stderr);
+        assert!(result.stderr.contains("yaml"), "should name the bad format, got: {}", result.stderr);
+    });
+}
+
+#[test]
+fn test_markdown_serializer_layout() {
+    let mut buffer = Vec::new();
+    MarkdownSerializer.write_task(&Task { pain_count: Some(3), ..task() }, &mut buffer).unwrap();
+
+    assert_eq!(String::from_utf8(buffer).unwrap(), "---\nid: abc123\nstatus: open\ntitle: Title, with comma\npain_count: 3\n---\n\nLine one\nLine two\n\n## Acceptance Criteria\n\nDone\n");
+}
+
+#[test]
+fn test_markdown_serializer_round_trips_optional_fields() {
+    let tasks = [
+        task(),
+        Task { description: None, ..task() },
+        Task { acceptance_criteria: None, ..task() },
+        Task { description: None, acceptance_criteria: None, pain_count: Some(2), ..task() },
+    ];
+
+    for original in tasks {
+        let mut buffer = Vec::new();
+        MarkdownSerializer.write_task(&original, &mut buffer).unwrap();
+        let back = MarkdownSerializer.read_task(&mut &buffer[..]).unwrap().unwrap();
+
+        assert_eq!(back.id, original.id);
+        assert_eq!(back.title, original.title);
+        assert_eq!(back.description, original.description);
+        assert_eq!(back.pain_count, original.pain_count);
+        assert_eq!(back.acceptance_criteria, original.acceptance_criteria);
+    }
+}
+
+#[test]
+fn test_markdown_serializer_uses_last_criteria_heading() {
+    let content = "---\nid: a\nstatus: open\ntitle: T\n---\n\nIntro\n\n## Acceptance Criteria\n\nquoted in description\n\n## Acceptance Criteria\n\nReal criteria\n";
+
+    let task = MarkdownSerializer.read_task(&mut content.as_bytes()).unwrap().unwrap();
+
+    assert!(task.description.unwrap().contains("quoted in description"));
+    assert_eq!(task.acceptance_criteria.as_deref(), Some("Real criteria"));
+}
+
+#[test]
+fn test_markdown_serializer_round_trips_awkward_frontmatter_values() {
+    let titles = ["a\nb", "carriage\rreturn", "\"quoted\" start", "  padded  ", "tab\there", "back\\slash: colon"];
+
+    for title in titles {
+        let original = Task { title: title.to_string(), status: "in review\n".to_string(), ..task() };
+        let mut buffer = Vec::new();
+        MarkdownSerializer.write_task(&original, &mut buffer).unwrap();
+        let written = String::from_utf8(buffer.clone()).unwrap();
+        assert_eq!(written.lines().nth(3).map(|l| l.starts_with("title: ")), Some(true), "title stays on one line: {:?}", written);
+
+        let back = MarkdownSerializer.read_task(&mut &buffer[..]).unwrap().unwrap();
+        assert_eq!((back.title.as_str(), back.status.as_str()), (title, "in review\n"));
+        assert_eq!(back.acceptance_criteria, original.acceptance_criteria);
+    }
+}
+
+#[test]
+fn test_markdown_serializer_round_trips_descriptions_quoting_the_criteria_heading() {
+    let description = "Intro\n\n## Acceptance Criteria\n\nquoted in description".to_string();
+    let tasks = [
+        Task { description: Some(description.clone()), ..task() },
+        Task { description: Some(description.clone()), acceptance_criteria: None, ..task() },
+    ];
+
+    for original in tasks {
+        let mut buffer = Vec::new();
+        MarkdownSerializer.write_task(&original, &mut buffer).unwrap();
+        let back = MarkdownSerializer.read_task(&mut &buffer[..]).unwrap().unwrap();
+
+        assert_eq!(back.description, original.description);
+        assert_eq!(back.acceptance_criteria, original.acceptance_criteria);
+    }
+}
+
+#[test]
+fn multiline_title_keeps_markdown_repository_readable() {
+    with_initialized_repo(|temp| {
+        run_command(&["migrate", "--to", "markdown"], temp);
+        let id = extract_task_id(&run_command(&["add", "Single", "-a", "Done"], temp).stdout);
+
+        assert!(run_command(&["update", &id, "-t", "a\nb"], temp).success);
+
+        let list = run_command(&["list"], temp);
+        assert!(list.success, "list failed: {}", list.stderr);
+        let show = run_command(&["--json", "show", &id], temp);
+        let value: serde_json::Value = serde_json::from_str(&show.stdout).unwrap();
+        assert_eq!(value["task"]["title"], "a\nb");
+    });
+}
+
+#[test]
+fn test_markdown_serializer_rejects_missing_frontmatter() {
+    let result = MarkdownSerializer.read_task(&mut "id,open,Title,,,\n".as_bytes());
+    assert!(matches!(result, Err(KnechtError::InvalidRecord { .. })));
+
+    let unterminated = MarkdownSerializer.read_task(&mut "---\nid: a\nstatus: open\ntitle: T\n".as_bytes());
+    assert!(matches!(unterminated, Err(KnechtError::InvalidRecord { .. })));
+}
+
+#[test]
+fn test_set_config_value_keeps_other_lines() {
+    let fs = MemoryFileSystem::new().with_file(".knecht/config", "# format = csv\nformat = csv\nother = 1\n");
+
+    set_config_value_with_fs("format", "markdown", &fs).unwrap();
+    set_config_value_with_fs("new", "x", &fs).unwrap();
+
+    assert_eq!(fs.read_to_string(".knecht/config").unwrap(), "# format = csv\nformat = markdown\nother = 1\nnew = x\n");
+}
+
+#[test]
+fn test_migrate_task_format_converts_both_ways() {
+    let fs = MemoryFileSystem::new().with_file(".knecht/tasks/abc123", "abc123,open,Title,\"Line one\nLine two\",,Done\n");
+
+    assert_eq!(migrate_task_format_with_fs("markdown", &fs).unwrap(), 1);
+    assert!(fs.read_to_string(".knecht/tasks/abc123").unwrap().starts_with("---\n"));
+    assert_eq!(read_config_with_fs(&fs).unwrap()["format"], "markdown");
+    assert_eq!(find_task_by_id_with_fs("abc123", &fs).unwrap().description.as_deref(), Some("Line one\nLine two"));
+
+    migrate_task_format_with_fs("csv", &fs).unwrap();
+    assert_eq!(fs.read_to_string(".knecht/tasks/abc123").unwrap(), "abc123,open,Title,\"Line one\nLine two\",,Done\n");
+}
+
+#[test]
+fn migrate_command_switches_repository_to_markdown() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Readable", "-d", "Stack trace\nline 2", "-a", "Fixed"], temp).stdout);
+
+        let result = run_command(&["migrate", "--to", "markdown"], temp);
+
+        assert!(result.success, "migrate should succeed, got: {}", result.stderr);
+        assert!(result.stdout.contains("Migrated 1 tasks to markdown format"), "got: {}", result.stdout);
+        let content = fs::read_to_string(temp.join(".knecht/tasks").join(&id)).unwrap();
+        assert!(content.contains("\nStack trace\nline 2\n"), "description should be the body, got: {}", content);
+        assert!(content.contains("## Acceptance Criteria\n\nFixed"), "got: {}", content);
+
+        // New tasks follow the configured format
+        let second = extract_task_id(&run_command(&["add", "Second", "-a", "Done"], temp).stdout);
+        assert!(fs::read_to_string(temp.join(".knecht/tasks").join(&second)).unwrap().starts_with("---\n"));
+
+        let show = run_command(&["show", &id], temp);
+        assert!(show.stdout.contains("Stack trace\nline 2"), "got: {}", show.stdout);
+
+        let back = run_command(&["migrate", "--to", "csv"], temp);
+        assert!(back.success);
+        let content = fs::read_to_string(temp.join(".knecht/tasks").join(&id)).unwrap();
+        assert!(content.starts_with(&format!("{},open,Readable,", id)), "got: {}", content);
+    });
+}
@@ -154,6 +154,7 @@ fn show_indicates_blocker_status() {
         run_command(&["block", &format!("task-{}", id1), "by", &format!("task-{}", id3)], &temp);
 
         // Complete one blocker
+        run_command(&["check", &format!("task-{}", id3), "--all"], &temp);
         run_command(&["done", &format!("task-{}", id3)], &temp);
 
         // Check show output
@@ -113,6 +113,7 @@ fn start_succeeds_when_blocker_is_done() {
         run_command(&["block", &format!("task-{}", id1), "by", &format!("task-{}", id2)], &temp);
 
         // Complete the blocker
+        run_command(&["check", &format!("task-{}", id2), "--all"], &temp);
         run_command(&["done", &format!("task-{}", id2)], &temp);
 
         // Now start should succeed
@@ -148,7 +149,9 @@ fn start_succeeds_when_all_blockers_are_done() {
         run_command(&["block", &format!("task-{}", id1), "by", &format!("task-{}", id3)], &temp);
 
         // Complete both blockers
+        run_command(&["check", &format!("task-{}", id2), "--all"], &temp);
         run_command(&["done", &format!("task-{}", id2)], &temp);
+        run_command(&["check", &format!("task-{}", id3), "--all"], &temp);
         run_command(&["done", &format!("task-{}", id3)], &temp);
 
         // Start should succeed
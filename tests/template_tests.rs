@@ -0,0 +1,158 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, setup_temp_dir, with_initialized_repo};
+use knecht::{list_templates_with_fs, render_template_with_fs, KnechtError, MemoryFileSystem};
+use std::collections::BTreeMap;
+use std::fs;
+
+const EXTRACT_METHOD: &str = "# Refactor a long function\n\
+--- title ---\n\
+Extract Method: {{method}} in {{file}}\n\
+--- description ---\n\
+{{ method }} in {{file}} does too much.\n\
+--- acceptance criteria ---\n\
+{{method}} is split up, tests pass\n";
+
+fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+fn templates_fs() -> MemoryFileSystem {
+    MemoryFileSystem::new()
+        .with_file(".knecht/templates/extract-method", EXTRACT_METHOD)
+        .with_file(".knecht/templates/anti-pattern", "--- title ---\nAnti-pattern: {{title}}\n--- description ---\n--- acceptance criteria ---\nRule added to CLAUDE.md\n")
+}
+
+#[test]
+fn test_render_template_fills_placeholders() {
+    let task = render_template_with_fs("extract-method", None, &vars(&[("method", "run"), ("file", "main.rs")]), &templates_fs()).unwrap();
+
+    assert_eq!(task.title, "Extract Method: run in main.rs");
+    assert_eq!(task.description.as_deref(), Some("run in main.rs does too much."));
+    assert_eq!(task.acceptance_criteria.as_deref(), Some("run is split up, tests pass"));
+}
+
+#[test]
+fn test_render_template_reports_missing_variables() {
+    match render_template_with_fs("extract-method", None, &vars(&[("method", "run")]), &templates_fs()) {
+        Err(KnechtError::TemplateError { name, message }) => {
+            assert_eq!(name, "extract-method");
+            assert!(message.contains("file"), "got: {}", message);
+            assert!(!message.contains("method,"), "provided variables are not missing, got: {}", message);
+        }
+        other => panic!("expected TemplateError, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_title_fills_title_placeholder() {
+    let task = render_template_with_fs("anti-pattern", Some("Skipping tests"), &BTreeMap::new(), &templates_fs()).unwrap();
+    assert_eq!(task.title, "Anti-pattern: Skipping tests");
+    assert_eq!(task.description, None);
+}
+
+#[test]
+fn test_title_replaces_template_title_without_placeholder() {
+    let fs = templates_fs().with_file(".knecht/templates/plain", "--- title ---\nDefault title\n--- description ---\n--- acceptance criteria ---\nDone\n");
+    let task = render_template_with_fs("plain", Some("Custom"), &BTreeMap::new(), &fs).unwrap();
+    assert_eq!(task.title, "Custom");
+}
+
+#[test]
+fn test_unknown_and_invalid_template_names() {
+    let fs = templates_fs();
+    assert!(matches!(render_template_with_fs("missing", None, &BTreeMap::new(), &fs), Err(KnechtError::TemplateError { .. })));
+    assert!(matches!(render_template_with_fs("../tasks/abc", None, &BTreeMap::new(), &fs), Err(KnechtError::TemplateError { .. })));
+}
+
+#[test]
+fn test_list_templates_sorted_with_variables() {
+    let templates = list_templates_with_fs(&templates_fs()).unwrap();
+
+    let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["anti-pattern", "extract-method"]);
+    assert_eq!(templates[1].title, "Extract Method: {{method}} in {{file}}");
+    assert_eq!(templates[1].variables, vec!["method", "file"]);
+}
+
+#[test]
+fn test_list_templates_without_directory_is_empty() {
+    assert!(list_templates_with_fs(&MemoryFileSystem::new()).unwrap().is_empty());
+}
+
+fn write_template(temp: &std::path::Path, name: &str, content: &str) {
+    fs::create_dir_all(temp.join(".knecht/templates")).unwrap();
+    fs::write(temp.join(".knecht/templates").join(name), content).unwrap();
+}
+
+#[test]
+fn add_with_template_creates_task() {
+    with_initialized_repo(|temp| {
+        write_template(temp, "extract-method", EXTRACT_METHOD);
+
+        let result = run_command(&["add", "--template", "extract-method", "--var", "method=cmd_show", "--var", "file=src/main.rs"], temp);
+
+        assert!(result.success, "add --template should succeed, got: {}", result.stderr);
+        let id = extract_task_id(&result.stdout);
+        let show = run_command(&["show", &id], temp);
+        assert!(show.stdout.contains("Extract Method: cmd_show in src/main.rs"), "got: {}", show.stdout);
+        assert!(show.stdout.contains("cmd_show is split up, tests pass"), "got: {}", show.stdout);
+    });
+}
+
+#[test]
+fn add_with_template_lets_flags_override_sections() {
+    with_initialized_repo(|temp| {
+        write_template(temp, "anti-pattern", "--- title ---\nAnti-pattern: {{title}}\n--- description ---\nSkeleton\n--- acceptance criteria ---\nRule added\n");
+
+        let result = run_command(&["add", "Ignoring", "reflection", "--template", "anti-pattern", "-a", "Custom criteria"], temp);
+
+        assert!(result.success, "got: {}", result.stderr);
+        let show = run_command(&["show", &extract_task_id(&result.stdout)], temp);
+        assert!(show.stdout.contains("Anti-pattern: Ignoring reflection"), "got: {}", show.stdout);
+        assert!(show.stdout.contains("Skeleton"), "got: {}", show.stdout);
+        assert!(show.stdout.contains("Custom criteria"), "got: {}", show.stdout);
+        assert!(!show.stdout.contains("Rule added"), "got: {}", show.stdout);
+    });
+}
+
+#[test]
+fn add_with_template_fails_on_missing_variable() {
+    with_initialized_repo(|temp| {
+        write_template(temp, "extract-method", EXTRACT_METHOD);
+
+        let result = run_command(&["add", "--template", "extract-method", "--var", "method=run"], temp);
+
+        assert_eq!(result.exit_code, Some(14));
+        assert!(result.stderr.contains("[E_TEMPLATE]"), "got: {}", result.stderr);
+        assert!(result.stderr.contains("file"), "should name the missing variable, got: {}", result.stderr);
+    });
+}
+
+#[test]
+fn add_rejects_malformed_var() {
+    with_initialized_repo(|temp| {
+        let result = run_command(&["add", "Title", "-a", "Done", "--var", "novalue"], temp);
+        assert_eq!(result.exit_code, Some(2));
+    });
+}
+
+#[test]
+fn templates_list_shows_names_and_variables() {
+    with_initialized_repo(|temp| {
+        let empty = run_command(&["templates", "list"], temp);
+        assert!(empty.stdout.contains("No templates"), "got: {}", empty.stdout);
+
+        write_template(temp, "extract-method", EXTRACT_METHOD);
+        let result = run_command(&["templates", "list"], temp);
+
+        assert!(result.success);
+        assert!(result.stdout.contains("extract-method"), "got: {}", result.stdout);
+        assert!(result.stdout.contains("(vars: method, file)"), "got: {}", result.stdout);
+
+        let json = run_command(&["templates", "list", "--json"], temp);
+        let value: serde_json::Value = serde_json::from_str(json.stdout.trim()).unwrap();
+        assert_eq!(value["templates"][0]["name"], "extract-method");
+    });
+}
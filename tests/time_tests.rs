@@ -0,0 +1,85 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, run_command, run_command_with_env, setup_temp_dir, with_initialized_repo};
+use knecht::{format_duration, format_timestamp, read_sessions_with_fs, MemoryFileSystem, WorkSession};
+use std::fs;
+
+#[test]
+fn test_sessions_pair_start_and_stop_per_actor() {
+    let fs = MemoryFileSystem::new()
+        .with_file(".knecht/sessions", "aaa|100|start|alice\naaa|150|start|bob\naaa|160|start|alice\naaa|400|stop|alice\naaa|500|start|alice\nbbb|10|stop|alice\n");
+
+    let sessions = read_sessions_with_fs(&fs).unwrap();
+    assert_eq!(sessions, vec![
+        WorkSession { task_id: "aaa".to_string(), actor: "alice".to_string(), start: 100, stop: Some(400) },
+        WorkSession { task_id: "aaa".to_string(), actor: "bob".to_string(), start: 150, stop: None },
+        WorkSession { task_id: "aaa".to_string(), actor: "alice".to_string(), start: 500, stop: None },
+    ]);
+    assert_eq!(sessions[1].duration_secs(250), 100);
+    assert_eq!(sessions[0].duration_since(300, 1000), 100);
+}
+
+#[test]
+fn test_format_duration_and_timestamp() {
+    assert_eq!(format_duration(40), "40s");
+    assert_eq!(format_duration(12 * 60 + 5), "12m");
+    assert_eq!(format_duration(3_600 + 5 * 60), "1h 5m");
+    assert_eq!(format_duration(2 * 86_400 + 3 * 3_600), "2d 3h");
+    assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+    assert_eq!(format_timestamp(1_709_210_096), "2024-02-29 12:34");
+}
+
+#[test]
+fn start_stop_and_done_record_sessions() {
+    with_initialized_repo(|temp| {
+        let env = [("KNECHT_AGENT", "agent-7")];
+        let task_id = extract_task_id(&run_command(&["add", "Timed", "-a", "Done"], temp).stdout);
+
+        assert!(run_command_with_env(&["start", &task_id], temp, &env).success);
+        let stop = run_command_with_env(&["stop", &task_id], temp, &env);
+        assert!(stop.success, "stop failed: {}", stop.stderr);
+        assert!(stop.stdout.contains("Stopped work on"), "got: {}", stop.stdout);
+        assert!(run_command_with_env(&["stop", &task_id], temp, &env).stdout.contains("No running session"));
+
+        assert!(run_command_with_env(&["start", &task_id], temp, &env).success);
+        assert!(run_command(&["check", &task_id, "--all"], temp).success);
+        assert!(run_command_with_env(&["done", &task_id], temp, &env).success);
+
+        let log = fs::read_to_string(temp.join(".knecht/sessions")).unwrap();
+        let events: Vec<&str> = log.lines().map(|l| l.split('|').nth(2).unwrap()).collect();
+        assert_eq!(events, vec!["start", "stop", "start", "stop"]);
+        assert!(log.lines().all(|l| l.starts_with(&format!("{}|", task_id)) && l.ends_with("|agent-7")));
+
+        let time = run_command(&["time", &task_id], temp);
+        assert!(time.success, "time failed: {}", time.stderr);
+        assert!(time.stdout.contains("Total: "), "got: {}", time.stdout);
+        assert_eq!(time.stdout.matches("agent-7").count(), 2, "got: {}", time.stdout);
+    });
+}
+
+#[test]
+fn time_summary_since_counts_only_the_window() {
+    with_initialized_repo(|temp| {
+        let old = extract_task_id(&run_command(&["add", "Old work"], temp).stdout);
+        let recent = extract_task_id(&run_command(&["add", "Recent work"], temp).stdout);
+        let now = knecht::now_secs();
+        fs::write(temp.join(".knecht/sessions"), format!(
+            "{old}|{}|start|alice\n{old}|{}|stop|alice\n{recent}|{}|start|alice\n{recent}|{}|stop|alice\n",
+            now - 30 * 86_400, now - 29 * 86_400, now - 2 * 3_600, now - 3_600,
+        )).unwrap();
+
+        let all = run_command(&["time"], temp);
+        assert!(all.stdout.contains(&format!("task-{}", old)), "got: {}", all.stdout);
+
+        let week = run_command(&["--json", "time", "--since", "7d"], temp);
+        assert!(week.success, "time --since failed: {}", week.stderr);
+        let value: serde_json::Value = serde_json::from_str(&week.stdout).unwrap();
+        let tasks = value["tasks"].as_array().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0]["task_id"], recent.as_str());
+        assert_eq!(tasks[0]["total_secs"], 3_600);
+
+        assert!(!run_command(&["time", "--since", "7x"], temp).success);
+    });
+}
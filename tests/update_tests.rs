@@ -159,6 +159,7 @@ fn update_preserves_status() {
         // Add and complete a task
         let add_result = run_command(&["add", "Done Task", "-a", "Done"], &temp);
         let task_id = extract_task_id(&add_result.stdout);
+        run_command(&["check", &format!("task-{}", task_id), "--all"], &temp);
         run_command(&["done", &format!("task-{}", task_id)], &temp);
 
         // Update the title
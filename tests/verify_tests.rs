@@ -0,0 +1,156 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, fs_with_tasks, run_command, run_command_with_env, setup_temp_dir, with_initialized_repo};
+use knecht::{find_task_by_id_with_fs, get_history_for_task, mark_task_delivered_with_fs, verify_task_with_fs, KnechtError, MemoryFileSystem};
+use std::fs;
+
+fn fs_with_task(status: &str) -> MemoryFileSystem {
+    fs_with_tasks(&[("abc", status, "Task")])
+}
+
+#[test]
+fn test_deliver_records_evidence_in_history() {
+    let fs = fs_with_task("open");
+
+    mark_task_delivered_with_fs("abc", Some("cargo test at 4f2c1e0"), &fs).unwrap();
+
+    let history = get_history_for_task("abc", &fs).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].event, "delivered");
+    assert_eq!(history[0].detail.as_deref(), Some("cargo test at 4f2c1e0"));
+}
+
+#[test]
+fn test_verify_marks_delivered_task_done() {
+    let fs = fs_with_task("delivered");
+
+    let task = verify_task_with_fs("abc", &fs).unwrap();
+
+    assert_eq!(task.status, "done");
+    assert_eq!(find_task_by_id_with_fs("abc", &fs).unwrap().status, "done");
+    let history = get_history_for_task("abc", &fs).unwrap();
+    assert_eq!(history.last().map(|e| e.event.as_str()), Some("verified"));
+}
+
+#[test]
+fn test_verify_requires_delivered_status() {
+    assert!(matches!(verify_task_with_fs("abc", &fs_with_task("open")), Err(KnechtError::TaskNotDelivered(_))));
+    assert!(matches!(verify_task_with_fs("abc", &fs_with_task("done")), Err(KnechtError::TaskAlreadyDone(_))));
+}
+
+#[test]
+fn test_verify_enforces_independent_verifier_policy() {
+    let fs = fs_with_task("delivered")
+        .with_file(".knecht/config", "require_independent_verifier = true\n")
+        .with_file(".knecht/history", &format!("abc|1|claimed|{}|\nabc|2|delivered|someone-else|tests pass\n", knecht::current_actor()));
+
+    match verify_task_with_fs("abc", &fs) {
+        Err(KnechtError::SelfVerification { actor, .. }) => assert_eq!(actor, knecht::current_actor()),
+        other => panic!("expected SelfVerification, got {:?}", other),
+    }
+    assert_eq!(find_task_by_id_with_fs("abc", &fs).unwrap().status, "delivered");
+}
+
+#[test]
+fn test_invalid_policy_value_is_config_error() {
+    let fs = fs_with_task("delivered").with_file(".knecht/config", "require_independent_verifier = sometimes\n");
+    assert!(matches!(verify_task_with_fs("abc", &fs), Err(KnechtError::InvalidConfig { .. })));
+}
+
+#[test]
+fn deliver_evidence_shows_in_task_history() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Works"], temp).stdout);
+        run_command_with_env(&["start", &id], temp, &[("KNECHT_AGENT", "builder")]);
+
+        let result = run_command_with_env(&["deliver", &id, "--evidence", "cargo test passes at 4f2c1e0"], temp, &[("KNECHT_AGENT", "builder")]);
+        assert!(result.success, "deliver should succeed, got: {}", result.stderr);
+
+        let show = run_command(&["show", &id], temp);
+        assert!(show.stdout.contains("History:"), "got: {}", show.stdout);
+        assert!(show.stdout.contains("claimed by builder"), "got: {}", show.stdout);
+        assert!(show.stdout.contains("delivered by builder: cargo test passes at 4f2c1e0"), "got: {}", show.stdout);
+    });
+}
+
+#[test]
+fn verify_moves_delivered_task_to_done() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Works"], temp).stdout);
+        run_command(&["check", &id, "--all"], temp);
+        run_command(&["deliver", &id], temp);
+
+        let result = run_command_with_env(&["verify", &id], temp, &[("KNECHT_AGENT", "reviewer")]);
+
+        assert!(result.success, "verify should succeed, got: {}", result.stderr);
+        assert!(result.stdout.contains("verified by reviewer"), "got: {}", result.stdout);
+        let show = run_command(&["show", &id, "--json"], temp);
+        let value: serde_json::Value = serde_json::from_str(show.stdout.trim()).unwrap();
+        assert_eq!(value["task"]["status"], "done");
+        let events: Vec<&str> = value["history"].as_array().unwrap().iter().map(|e| e["event"].as_str().unwrap()).collect();
+        assert_eq!(events, vec!["created", "delivered", "done", "verified"]);
+        assert_eq!(value["history"][3]["actor"], "reviewer");
+    });
+}
+
+#[test]
+fn verify_rejects_undelivered_task() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Works"], temp).stdout);
+
+        let result = run_command(&["verify", &id], temp);
+
+        assert_eq!(result.exit_code, Some(17));
+        assert!(result.stderr.contains("[E_NOT_DELIVERED]"), "got: {}", result.stderr);
+    });
+}
+
+#[test]
+fn verify_rejects_claimant_when_policy_set() {
+    with_initialized_repo(|temp| {
+        fs::write(temp.join(".knecht/config"), "require_independent_verifier = true\n").unwrap();
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Works"], temp).stdout);
+        run_command(&["check", &id, "--all"], temp);
+        run_command_with_env(&["start", &id], temp, &[("KNECHT_AGENT", "builder")]);
+        run_command_with_env(&["deliver", &id], temp, &[("KNECHT_AGENT", "builder")]);
+
+        let own = run_command_with_env(&["verify", &id], temp, &[("KNECHT_AGENT", "builder")]);
+        assert_eq!(own.exit_code, Some(18));
+        assert!(own.stderr.contains("[E_SELF_VERIFICATION]"), "got: {}", own.stderr);
+
+        let other = run_command_with_env(&["verify", &id], temp, &[("KNECHT_AGENT", "reviewer")]);
+        assert!(other.success, "a different verifier is allowed, got: {}", other.stderr);
+    });
+}
+
+#[test]
+fn claimant_cannot_bypass_verification_with_done_or_move() {
+    with_initialized_repo(|temp| {
+        fs::write(temp.join(".knecht/config"), "require_independent_verifier = true\n").unwrap();
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Works"], temp).stdout);
+        run_command(&["check", &id, "--all"], temp);
+        let builder = [("KNECHT_AGENT", "builder")];
+        run_command_with_env(&["start", &id], temp, &builder);
+        run_command_with_env(&["deliver", &id], temp, &builder);
+
+        for args in [vec!["done", id.as_str()], vec!["move", id.as_str(), "done"], vec!["done", id.as_str(), "--force", "--reason", "just because"]] {
+            let own = run_command_with_env(&args, temp, &builder);
+            assert_eq!(own.exit_code, Some(18), "{:?} should be refused, got: {}", args, own.stderr);
+            assert!(own.stderr.contains("[E_SELF_VERIFICATION]"), "got: {}", own.stderr);
+        }
+        assert!(run_command(&["show", &id], temp).stdout.contains("Status: delivered"));
+
+        let other = run_command_with_env(&["done", &id], temp, &[("KNECHT_AGENT", "reviewer")]);
+        assert!(other.success, "someone else may sign it off, got: {}", other.stderr);
+    });
+}
+
+#[test]
+fn deliver_rejects_empty_evidence() {
+    with_initialized_repo(|temp| {
+        let id = extract_task_id(&run_command(&["add", "Task", "-a", "Works"], temp).stdout);
+        let result = run_command(&["deliver", &id, "--evidence", "  "], temp);
+        assert_eq!(result.exit_code, Some(2));
+    });
+}
@@ -0,0 +1,138 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, fs_with_tasks, run_command, run_command_with_env, setup_temp_dir, with_initialized_repo};
+use knecht::{
+    check_wip_limits_with_fs, claim_next_task_with_fs, now_secs, strategy_for_name, tasks_held_by_with_fs, KnechtError, MemoryFileSystem,
+};
+use std::fs;
+
+fn fs_with(config: &str) -> MemoryFileSystem {
+    fs_with_tasks(&[("aaa", "claimed", "Mine"), ("bbb", "claimed", "Theirs"), ("ccc", "delivered", "Awaiting review"), ("ddd", "open", "Free")])
+        .with_file(".knecht/config", config)
+        .with_file(".knecht/history", "aaa|1|claimed|alice|\nbbb|2|claimed|bob|\nbbb|3|claimed|alice|\nbbb|4|claimed|bob|\n")
+}
+
+#[test]
+fn test_held_tasks_follow_latest_claim() {
+    let fs = fs_with("");
+    let held: Vec<String> = tasks_held_by_with_fs("alice", &fs).unwrap().into_iter().map(|t| t.id).collect();
+    assert_eq!(held, vec!["aaa"]);
+}
+
+#[test]
+fn test_per_agent_limit() {
+    let fs = fs_with("wip_limit_per_agent = 1\n");
+
+    match check_wip_limits_with_fs("alice", &fs) {
+        Err(KnechtError::WipLimitExceeded { actor, limit, held }) => {
+            assert_eq!((actor.as_deref(), limit), (Some("alice"), 1));
+            assert_eq!(held.len(), 1);
+        }
+        other => panic!("expected WipLimitExceeded, got {:?}", other),
+    }
+    assert!(check_wip_limits_with_fs("carol", &fs).is_ok());
+}
+
+#[test]
+fn test_repository_limit_counts_claimed_and_delivered() {
+    assert!(check_wip_limits_with_fs("carol", &fs_with("wip_limit = 4\n")).is_ok());
+    match check_wip_limits_with_fs("carol", &fs_with("wip_limit = 3\n")) {
+        Err(KnechtError::WipLimitExceeded { actor: None, held, .. }) => assert_eq!(held.len(), 3),
+        other => panic!("expected WipLimitExceeded, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_taking_over_an_expired_claim_counts_against_the_agent() {
+    let claims = |config: &str| fs_with_tasks(&[("aaa", "claimed", "Abandoned"), ("bbb", "claimed", "Mine")])
+        .with_file(".knecht/config", config)
+        .with_file(".knecht/history", &format!("aaa|1|claimed|bob|\nbbb|{}|claimed|alice|\n", now_secs()));
+    let strategy = strategy_for_name("pain").unwrap();
+
+    let fs = claims("wip_limit_per_agent = 1\nclaim_lease = 1h\n");
+    assert!(matches!(
+        claim_next_task_with_fs(strategy.as_ref(), "alice", &fs),
+        Err(KnechtError::WipLimitExceeded { actor: Some(_), .. })
+    ));
+
+    // The repository's total is unchanged by a takeover
+    let fs = claims("wip_limit = 2\nclaim_lease = 1h\n");
+    let taken = claim_next_task_with_fs(strategy.as_ref(), "alice", &fs).unwrap().unwrap();
+    assert_eq!(taken.id, "aaa");
+}
+
+#[test]
+fn start_refuses_beyond_limit_and_lists_held_tasks() {
+    with_initialized_repo(|temp| {
+        fs::write(temp.join(".knecht/config"), "wip_limit_per_agent = 1\n").unwrap();
+        let env = [("KNECHT_AGENT", "agent-7")];
+        let first = extract_task_id(&run_command(&["add", "First", "-a", "Done"], temp).stdout);
+        let second = extract_task_id(&run_command(&["add", "Second", "-a", "Done"], temp).stdout);
+        assert!(run_command_with_env(&["start", &first], temp, &env).success);
+
+        let again = run_command_with_env(&["start", &first], temp, &env);
+        assert!(again.success, "re-claiming held work is allowed, got: {}", again.stderr);
+
+        let result = run_command_with_env(&["start", &second], temp, &env);
+        assert_eq!(result.exit_code, Some(22));
+        assert!(result.stderr.contains("[E_WIP_LIMIT]"), "got: {}", result.stderr);
+        assert!(result.stderr.contains(&format!("task-{} (claimed): First", first)), "got: {}", result.stderr);
+
+        let other = run_command_with_env(&["start", &second], temp, &[("KNECHT_AGENT", "agent-8")]);
+        assert!(other.success, "the limit is per agent, got: {}", other.stderr);
+    });
+}
+
+#[test]
+fn next_suggests_finishing_held_work_at_limit() {
+    with_initialized_repo(|temp| {
+        fs::write(temp.join(".knecht/config"), "wip_limit_per_agent = 1\n").unwrap();
+        let env = [("KNECHT_AGENT", "agent-7")];
+        let held = extract_task_id(&run_command(&["add", "Held", "-a", "Done"], temp).stdout);
+        run_command(&["add", "Waiting", "-a", "Done"], temp);
+        run_command_with_env(&["start", &held], temp, &env);
+
+        let result = run_command_with_env(&["next"], temp, &env);
+        assert!(result.success);
+        assert!(result.stdout.contains("WIP limit reached"), "got: {}", result.stdout);
+        assert!(result.stdout.contains(&format!("task-{}", held)), "got: {}", result.stdout);
+        assert!(result.stdout.contains("knecht move <task> open"), "got: {}", result.stdout);
+
+        let json = run_command_with_env(&["next", "--json"], temp, &env);
+        let value: serde_json::Value = serde_json::from_str(json.stdout.trim()).unwrap();
+        assert!(value["task"].is_null());
+        assert_eq!(value["wip_limit"]["held"][0]["id"], held);
+    });
+}
+
+#[test]
+fn next_still_offers_verification_at_limit() {
+    with_initialized_repo(|temp| {
+        let env = [("KNECHT_AGENT", "agent-7")];
+        let delivered = extract_task_id(&run_command(&["add", "Delivered", "-a", "Done"], temp).stdout);
+        let held = extract_task_id(&run_command(&["add", "Held", "-a", "Done"], temp).stdout);
+        let open = extract_task_id(&run_command(&["add", "Waiting", "-a", "Done"], temp).stdout);
+        run_command_with_env(&["start", &delivered], temp, &[("KNECHT_AGENT", "agent-8")]);
+        run_command_with_env(&["deliver", &delivered], temp, &[("KNECHT_AGENT", "agent-8")]);
+        run_command_with_env(&["start", &held], temp, &env);
+        fs::write(temp.join(".knecht/config"), "wip_limit = 2\n").unwrap();
+
+        let next = run_command_with_env(&["next", "--explain"], temp, &env);
+        assert!(next.success, "got: {}", next.stderr);
+        assert!(next.stdout.contains(&format!("Suggested next task: task-{}", delivered)), "got: {}", next.stdout);
+        assert!(next.stdout.contains("Why (strategy:"), "--explain is honoured, got: {}", next.stdout);
+
+        let queue = run_command_with_env(&["next", "-n", "5"], temp, &env);
+        assert!(queue.stdout.contains("WIP limit reached"), "got: {}", queue.stdout);
+        assert!(queue.stdout.contains(&format!("task-{}", delivered)), "got: {}", queue.stdout);
+        assert!(!queue.stdout.contains(&format!("task-{}", open)), "no new work at the limit, got: {}", queue.stdout);
+
+        run_command(&["check", &delivered, "1"], temp);
+        let verify = run_command(&["verify", &delivered], temp);
+        assert!(verify.success, "got: {}", verify.stderr);
+        let json = run_command_with_env(&["next", "--json", "--explain"], temp, &env);
+        let value: serde_json::Value = serde_json::from_str(json.stdout.trim()).unwrap();
+        assert_eq!(value["task"]["id"], open, "verifying freed a slot, got: {}", json.stdout);
+    });
+}
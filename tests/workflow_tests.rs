@@ -0,0 +1,166 @@
+mod common;
+
+#[allow(unused_imports)]
+use common::{cleanup_temp_dir, extract_task_id, fs_with_tasks, run_command, setup_temp_dir, with_initialized_repo};
+use knecht::{
+    explain_next_task_with_fs, find_next_task_with_fs, get_history_for_task, mark_task_claimed_with_fs, move_task_with_fs, now_secs,
+    rank_tasks_with_fs, strategy_for_name, workflow_with_fs, KnechtError, MemoryFileSystem, Workflow,
+};
+use std::fs;
+
+const REVIEW_WORKFLOW: &str = "statuses = open, claimed, review, done, wontdo\n\
+status.review.glyph = [R]\n\
+transitions.open = claimed, wontdo\n\
+transitions.review = done, claimed\n\
+transitions.done =\n\
+active_statuses = open\n\
+resolved_statuses = done, wontdo\n";
+
+fn fs_with(config: &str, tasks: &[(&str, &str, &str)]) -> MemoryFileSystem {
+    fs_with_tasks(tasks).with_file(".knecht/config", config)
+}
+
+#[test]
+fn test_default_workflow_allows_any_move() {
+    let workflow = workflow_with_fs(&MemoryFileSystem::new()).unwrap();
+
+    assert_eq!(workflow, Workflow::default());
+    assert_eq!(workflow.glyph("claimed"), "[~]");
+    assert!(workflow.check_move("a", "done", "open").is_ok());
+}
+
+#[test]
+fn test_configured_workflow() {
+    let workflow = workflow_with_fs(&fs_with(REVIEW_WORKFLOW, &[])).unwrap();
+
+    assert_eq!(workflow.glyph("review"), "[R]");
+    assert_eq!(workflow.glyph("wontdo"), "[?]");
+    assert!(workflow.is_resolved("wontdo"));
+    assert_eq!(workflow.allowed_moves("open"), vec!["claimed", "wontdo"]);
+    assert_eq!(workflow.allowed_moves("claimed"), vec!["open", "review", "done", "wontdo"], "unrestricted without transitions.claimed");
+    assert!(workflow.allowed_moves("done").is_empty());
+}
+
+#[test]
+fn test_workflow_rejects_unknown_statuses() {
+    for config in ["statuses = open, claimed\n", "transitions.open = review\n", "status.review.glyph = [R]\n", "resolved_statuses = wontdo\n"] {
+        assert!(matches!(workflow_with_fs(&fs_with(config, &[])), Err(KnechtError::InvalidConfig { .. })), "{:?} should be rejected", config);
+    }
+}
+
+#[test]
+fn test_move_enforces_transitions_and_records_history() {
+    let fs = fs_with(REVIEW_WORKFLOW, &[("abc", "claimed", "Task")]);
+
+    let task = move_task_with_fs("abc", "review", &fs).unwrap();
+    assert_eq!(task.status, "review");
+    let history = get_history_for_task("abc", &fs).unwrap();
+    assert_eq!((history[0].event.as_str(), history[0].detail.as_deref()), ("moved", Some("claimed -> review")));
+
+    match move_task_with_fs("abc", "open", &fs) {
+        Err(KnechtError::InvalidTransition { from, to, allowed, .. }) => {
+            assert_eq!((from.as_str(), to.as_str()), ("review", "open"));
+            assert_eq!(allowed, vec!["done", "claimed"]);
+        }
+        other => panic!("expected InvalidTransition, got {:?}", other),
+    }
+    assert!(matches!(move_task_with_fs("abc", "blocked-external", &fs), Err(KnechtError::UnknownStatus { .. })));
+}
+
+#[test]
+fn test_built_in_commands_follow_transitions() {
+    let fs = fs_with(REVIEW_WORKFLOW, &[("abc", "done", "Task")]);
+    assert!(matches!(mark_task_claimed_with_fs("abc", &fs), Err(KnechtError::InvalidTransition { .. })));
+}
+
+#[test]
+fn test_next_uses_active_statuses() {
+    let fs = fs_with("statuses = open, triage, done\nactive_statuses = triage\n", &[("aaa", "open", "Untriaged"), ("bbb", "triage", "Triaging")]);
+    assert_eq!(find_next_task_with_fs(&fs).unwrap().map(|t| t.id), Some("bbb".to_string()));
+}
+
+#[test]
+fn test_blockers_in_review_still_block() {
+    let fs = fs_with(REVIEW_WORKFLOW, &[("aaa", "open", "Blocked"), ("bbb", "review", "In review")])
+        .with_file(".knecht/blockers", "aaa|bbb\n");
+    assert_eq!(find_next_task_with_fs(&fs).unwrap().map(|t| t.id), None, "nothing is free to work on");
+
+    let fs = fs.with_file(".knecht/tasks/bbb", "bbb,done,In review,,\n");
+    assert_eq!(find_next_task_with_fs(&fs).unwrap().map(|t| t.id), Some("aaa".to_string()));
+}
+
+#[test]
+fn test_held_blockers_block_but_are_not_suggested() {
+    // aaa hurts most but waits on bbb, which someone holds; ccc is free
+    let fs = |blocker_status: &str| fs_with_tasks(&[("aaa", "open", "Blocked"), ("bbb", blocker_status, "Held"), ("ccc", "open", "Free")])
+        .with_file(".knecht/blockers", "aaa|bbb\n")
+        .with_file(".knecht/pain", "aaa|1|manual||Hurts\naaa|1|manual||Hurts\n")
+        .with_file(".knecht/history", &format!("bbb|{}|claimed|alice|\n", now_secs()));
+
+    assert_eq!(find_next_task_with_fs(&fs("claimed")).unwrap().map(|t| t.id), Some("ccc".to_string()));
+    let queue: Vec<String> = rank_tasks_with_fs(strategy_for_name("pain").unwrap().as_ref(), &fs("delivered")).unwrap()
+        .into_iter().map(|entry| entry.task.id).collect();
+    assert_eq!(queue, vec!["bbb", "ccc"], "the delivered blocker awaits verification, it is not work for aaa");
+
+    // A blocker whose claim expired is free again
+    let expired = fs("claimed").with_file(".knecht/config", "claim_lease = 1\n").with_file(".knecht/history", "bbb|1|claimed|alice|\n");
+    let explanation = explain_next_task_with_fs(strategy_for_name("pain").unwrap().as_ref(), &expired).unwrap();
+    assert_eq!(explanation.suggestion.map(|t| t.id), Some("bbb".to_string()));
+    let chain: Vec<&str> = explanation.blocker_chain.iter().map(|t| t.task_id.as_str()).collect();
+    assert_eq!(chain, vec!["aaa", "bbb"]);
+}
+
+#[test]
+fn test_blocker_cycles_end_the_walk() {
+    let fs = fs_with_tasks(&[("aaa", "open", "One"), ("bbb", "open", "Two"), ("ccc", "open", "Free")])
+        .with_file(".knecht/blockers", "aaa|bbb\nbbb|aaa\n")
+        .with_file(".knecht/pain", "aaa|1|manual||Hurts\nbbb|1|manual||Hurts\n");
+
+    assert_eq!(find_next_task_with_fs(&fs).unwrap().map(|t| t.id), Some("ccc".to_string()));
+    assert_eq!(rank_tasks_with_fs(strategy_for_name("pain").unwrap().as_ref(), &fs).unwrap().len(), 1);
+}
+
+#[test]
+fn move_command_and_list_glyphs() {
+    with_initialized_repo(|temp| {
+        fs::write(temp.join(".knecht/config"), REVIEW_WORKFLOW).unwrap();
+        let id = extract_task_id(&run_command(&["add", "Reviewed task", "-a", "Works"], temp).stdout);
+        run_command(&["start", &id], temp);
+
+        let result = run_command(&["move", &id, "review"], temp);
+        assert!(result.success, "move should succeed, got: {}", result.stderr);
+        assert!(result.stdout.contains("claimed → review"), "got: {}", result.stdout);
+
+        let list = run_command(&["list"], temp);
+        assert!(list.stdout.contains(&format!("[R] task-{}", id)), "got: {}", list.stdout);
+
+        let invalid = run_command(&["move", &id, "open"], temp);
+        assert_eq!(invalid.exit_code, Some(20));
+        assert!(invalid.stderr.contains("[E_INVALID_TRANSITION]"), "got: {}", invalid.stderr);
+        assert!(invalid.stderr.contains("allowed: done, claimed"), "got: {}", invalid.stderr);
+
+        let unknown = run_command(&["move", &id, "blocked-external", "--json"], temp);
+        assert_eq!(unknown.exit_code, Some(21));
+        let value: serde_json::Value = serde_json::from_str(unknown.stdout.trim()).unwrap();
+        assert_eq!(value["error"]["code"], "E_UNKNOWN_STATUS");
+    });
+}
+
+#[test]
+fn resolved_statuses_satisfy_blockers() {
+    with_initialized_repo(|temp| {
+        fs::write(temp.join(".knecht/config"), REVIEW_WORKFLOW).unwrap();
+        let blocker = extract_task_id(&run_command(&["add", "Abandoned", "-a", "Works"], temp).stdout);
+        let blocked = extract_task_id(&run_command(&["add", "Follow-up", "-a", "Works"], temp).stdout);
+        run_command(&["block", &blocked, "by", &blocker], temp);
+
+        assert_eq!(run_command(&["start", &blocked], temp).exit_code, Some(7));
+
+        run_command(&["move", &blocker, "wontdo"], temp);
+        let start = run_command(&["start", &blocked], temp);
+        assert!(start.success, "wontdo is resolved, got: {}", start.stderr);
+
+        let list = run_command(&["list"], temp);
+        assert!(!list.stdout.contains("Abandoned"), "resolved tasks are hidden, got: {}", list.stdout);
+    });
+}